@@ -1,12 +1,18 @@
+use crate::core::app_log;
+use crate::core::repo_manager;
+use crate::core::system;
 use crate::core::work_dir::WorkDir;
-use crate::settings::AppSettings;
+use crate::settings::{self, AppSettings};
+use std::path::Path;
 use crate::tabs::{
     build::BuildTab, config::ConfigTab, kernel::KernelTab, patches::PatchesTab,
     settings::SettingsTab,
+    wizard::{WizardAction, WizardState, WizardTab},
 };
 
 #[derive(PartialEq, Clone, Copy)]
 pub enum Tab {
+    Wizard,
     Kernel,
     Config,
     Patches,
@@ -14,6 +20,27 @@ pub enum Tab {
     Settings,
 }
 
+impl Tab {
+    const ALL: [Tab; 6] =
+        [Tab::Wizard, Tab::Kernel, Tab::Config, Tab::Patches, Tab::Build, Tab::Settings];
+
+    /// Name stored in `AppSettings::last_active_tab`
+    fn name(self) -> &'static str {
+        match self {
+            Tab::Wizard => "wizard",
+            Tab::Kernel => "kernel",
+            Tab::Config => "config",
+            Tab::Patches => "patches",
+            Tab::Build => "build",
+            Tab::Settings => "settings",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Tab> {
+        Tab::ALL.into_iter().find(|tab| tab.name() == name)
+    }
+}
+
 pub struct TkgApp {
     active_tab: Tab,
     kernel_tab: KernelTab,
@@ -21,27 +48,145 @@ pub struct TkgApp {
     patches_tab: PatchesTab,
     build_tab: BuildTab,
     settings_tab: SettingsTab,
+    wizard_tab: WizardTab,
     settings: AppSettings,
     work_dir: WorkDir,
     show_close_dialog: bool,
     close_confirmed: bool,
+    show_root_warning: bool,
+    // Why the custom work directory couldn't be used
+    work_dir_warning: Option<String>,
+    // What the last "Stop All" stopped
+    stop_report: String,
 }
 
 impl TkgApp {
-    pub fn new() -> Self {
+    pub fn new(ctx: &egui::Context) -> Self {
         let settings = AppSettings::load();
-        let work_dir = WorkDir::new().expect("Failed to create temporary work directory");
+        let _ = app_log::init(&AppSettings::data_dir(), &settings.log_level);
+        let (work_dir, work_dir_warning) = open_work_dir(&settings);
+        if settings.persist_kernel_sources {
+            let _ = work_dir.link_kernel_sources(&settings.kernel_sources());
+        }
+        let mut settings_tab = SettingsTab::default();
+        settings_tab.start_catalog_refresh(&settings, ctx.clone());
+        let mut kernel_tab = KernelTab::default();
+        kernel_tab.restore_selection(settings.last_selected_version.clone());
+        let active_tab = if settings.show_wizard {
+            Tab::Wizard
+        } else {
+            Tab::from_name(&settings.last_active_tab).unwrap_or(Tab::Kernel)
+        };
         Self {
-            active_tab: Tab::Kernel,
-            kernel_tab: KernelTab::default(),
+            active_tab,
+            kernel_tab,
             config_tab: ConfigTab::default(),
             patches_tab: PatchesTab::default(),
             build_tab: BuildTab::default(),
-            settings_tab: SettingsTab::default(),
+            settings_tab,
+            wizard_tab: WizardTab::default(),
             settings,
             work_dir,
             show_close_dialog: false,
             close_confirmed: false,
+            show_root_warning: system::is_root(),
+            work_dir_warning,
+            stop_report: String::new(),
+        }
+    }
+
+    /// Write the selected kernel version to customization.cfg and line the
+    /// patches and config template up with its series
+    fn apply_version(&mut self, version: &str, linux_tkg_path: &Path) {
+        self.config_tab.set_version(version);
+        self.config_tab.save_to(linux_tkg_path);
+        if let Some(series) = self.kernel_tab.get_kernel_series() {
+            self.patches_tab.set_kernel_series(&series);
+            self.config_tab.offer_template(&series);
+        }
+    }
+
+    /// Remember the open tab and selected kernel for the next start
+    fn save_session(&mut self) {
+        let tab = self.active_tab.name();
+        let selected = self.kernel_tab.get_selected_version();
+        // Nothing is selected until the version list is fetched; keep the old one
+        let selected_changed =
+            selected.is_some() && selected != self.settings.last_selected_version;
+        if self.settings.last_active_tab != tab || selected_changed {
+            self.settings.last_active_tab = tab.to_string();
+            if selected_changed {
+                self.settings.last_selected_version = selected;
+            }
+            let _ = self.settings.save();
+        }
+    }
+
+    /// Every background job still running, across all tabs
+    fn background_tasks(&self) -> Vec<&'static str> {
+        let mut tasks = self.kernel_tab.background_tasks();
+        tasks.extend(self.patches_tab.background_tasks());
+        tasks.extend(self.build_tab.background_tasks());
+        tasks.extend(self.settings_tab.background_tasks());
+        tasks
+    }
+
+    /// Cancel or detach every running background job
+    fn stop_all(&mut self) {
+        let mut stopped = self.kernel_tab.stop_background();
+        stopped.extend(self.patches_tab.stop_background());
+        stopped.extend(self.build_tab.stop_background());
+        stopped.extend(self.settings_tab.stop_background());
+        log::warn!("Stop All: {}", stopped.join(", "));
+        self.stop_report = format!("Stopped: {}", stopped.join(", "));
+    }
+
+    fn wizard_ui(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, linux_tkg_path: &Path) {
+        let selected = self.kernel_tab.get_selected_version();
+        let config_version = self.config_tab.get_version();
+        let state = WizardState {
+            selected_version: selected.as_deref(),
+            linux_tkg_ready: linux_tkg_path.join("customization.cfg").exists(),
+            cloning: self.settings_tab.is_cloning(),
+            clone_status: self.settings_tab.clone_status(),
+            config_version: config_version.as_deref(),
+            build_state: self.build_tab.state(),
+        };
+        match self.wizard_tab.ui(ui, &state) {
+            Some(WizardAction::Open(tab)) => self.active_tab = tab,
+            Some(WizardAction::CloneLinuxTkg) => {
+                self.settings_tab.start_clone(linux_tkg_path.to_path_buf(), ctx.clone());
+            }
+            Some(WizardAction::ApplyVersion) => {
+                if let Some(version) = selected {
+                    self.apply_version(&version, linux_tkg_path);
+                    self.active_tab = Tab::Config;
+                }
+            }
+            Some(WizardAction::Skip) => {
+                self.settings.show_wizard = false;
+                let _ = self.settings.save();
+                self.active_tab = Tab::Kernel;
+            }
+            None => {}
+        }
+    }
+}
+
+/// The work directory under `work_dir_override`, or in the temp dir with a
+/// warning when the override can't be used
+fn open_work_dir(settings: &AppSettings) -> (WorkDir, Option<String>) {
+    let temp = || WorkDir::new().expect("Failed to create temporary work directory");
+    let Some(base) = &settings.work_dir_override else {
+        return (temp(), None);
+    };
+    match WorkDir::in_dir(&settings::expand_path(base)) {
+        Ok(work_dir) => (work_dir, None),
+        Err(e) => {
+            log::warn!("Custom work directory unusable: {}", e);
+            let work_dir = temp();
+            let warning = format!("{}. Using {} instead.", e, work_dir.root().display());
+            (work_dir, Some(warning))
         }
     }
 }
@@ -70,7 +215,7 @@ impl eframe::App for TkgApp {
                         self.work_dir.root().display()
                     ));
                     ui.add_space(8.0);
-                    ui.label("Clean up temporary build files?");
+                    ui.label("Clean up the build files in it?");
                     ui.add_space(8.0);
                     ui.horizontal(|ui| {
                         if ui.button("🗑 Clean Up & Exit").clicked() {
@@ -94,12 +239,54 @@ impl eframe::App for TkgApp {
 
         // All mutable operations use paths inside the temp work directory
         let linux_tkg_path = self.work_dir.linux_tkg();
-        let kernel_sources_dir = self.work_dir.kernel_sources();
-        let work_dir_root = self.work_dir.root().to_path_buf();
+        let kernel_sources_dir = if self.settings.persist_kernel_sources {
+            self.settings.kernel_sources()
+        } else {
+            self.work_dir.kernel_sources()
+        };
         let data_dir = AppSettings::data_dir();
 
+        if self.show_root_warning {
+            egui::TopBottomPanel::top("root_warning").show(ctx, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(
+                        egui::RichText::new(
+                            "⚠ tkg-gui is running as root. Builds and installs will run as root \
+                             and settings are stored under root's home. Run it as a normal \
+                             user; the install step escalates via sudo/pkexec when needed.",
+                        )
+                        .color(egui::Color32::RED)
+                        .strong(),
+                    );
+                    if ui.button("Dismiss").clicked() {
+                        self.show_root_warning = false;
+                    }
+                });
+            });
+        }
+
+        if let Some(warning) = &self.work_dir_warning {
+            let mut dismissed = false;
+            egui::TopBottomPanel::top("work_dir_warning").show(ctx, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(
+                        egui::RichText::new(format!("⚠ {}", warning))
+                            .color(egui::Color32::RED)
+                            .strong(),
+                    );
+                    dismissed = ui.button("Dismiss").clicked();
+                });
+            });
+            if dismissed {
+                self.work_dir_warning = None;
+            }
+        }
+
+        self.settings_tab.poll_clone(ctx, &mut self.settings);
+
         egui::TopBottomPanel::top("tabs").show(ctx, |ui| {
             ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.active_tab, Tab::Wizard, "🧭 Wizard");
                 ui.selectable_value(&mut self.active_tab, Tab::Kernel, "🐧 Kernel");
                 ui.selectable_value(&mut self.active_tab, Tab::Config, "⚙ Config");
                 ui.selectable_value(&mut self.active_tab, Tab::Patches, "🩹 Patches");
@@ -111,27 +298,77 @@ impl eframe::App for TkgApp {
                 // Sync button to apply selected kernel version to config
                 if let Some(version) = self.kernel_tab.get_selected_version() {
                     if ui.button("📋 Apply Version to Config").clicked() {
-                        self.config_tab.set_version(&version);
-                        self.config_tab.save_to(&linux_tkg_path);
-                        if let Some(series) = self.kernel_tab.get_kernel_series() {
-                            self.patches_tab.set_kernel_series(&series);
-                        }
+                        self.apply_version(&version, &linux_tkg_path);
+                    }
+                }
+
+                let tasks = self.background_tasks();
+                if !tasks.is_empty() {
+                    if ui
+                        .button(
+                            egui::RichText::new(format!("⏹ Stop All ({})", tasks.len()))
+                                .color(egui::Color32::RED),
+                        )
+                        .on_hover_text(format!(
+                            "Cancel every running background task:\n{}",
+                            tasks.join("\n")
+                        ))
+                        .clicked()
+                    {
+                        self.stop_all();
                     }
+                } else if !self.stop_report.is_empty() {
+                    ui.label(
+                        egui::RichText::new(&self.stop_report)
+                            .small()
+                            .color(egui::Color32::GRAY),
+                    );
                 }
             });
         });
 
         egui::CentralPanel::default().show(ctx, |ui| {
             match self.active_tab {
-                Tab::Kernel => self.kernel_tab.ui(ui, ctx, &kernel_sources_dir),
-                Tab::Config => self.config_tab.ui(ui, &linux_tkg_path),
-                Tab::Patches => self.patches_tab.ui(ui, ctx, &linux_tkg_path, &data_dir),
-                Tab::Build => self.build_tab.ui(ui, ctx, &linux_tkg_path),
+                Tab::Wizard => self.wizard_ui(ui, ctx, &linux_tkg_path),
+                Tab::Kernel => {
+                    let supported = repo_manager::supported_series(
+                        &linux_tkg_path,
+                        &self.settings.supported_series_override,
+                    );
+                    self.kernel_tab
+                        .ui(ui, ctx, &kernel_sources_dir, &supported, &self.settings);
+                }
+                Tab::Config => self.config_tab.ui(ui, &linux_tkg_path, &mut self.settings),
+                Tab::Patches => {
+                    self.patches_tab
+                        .ui(ui, ctx, &linux_tkg_path, &data_dir, &mut self.settings)
+                }
+                Tab::Build => self.build_tab.ui(ui, ctx, &linux_tkg_path, &mut self.settings),
                 Tab::Settings => {
+                    let persist_before = (
+                        self.settings.persist_kernel_sources,
+                        self.settings.kernel_sources_dir.clone(),
+                    );
                     self.settings_tab
-                        .ui(ui, ctx, &mut self.settings, &work_dir_root, &linux_tkg_path);
+                        .ui(ui, ctx, &mut self.settings, &self.work_dir, &linux_tkg_path);
+                    if persist_before
+                        != (
+                            self.settings.persist_kernel_sources,
+                            self.settings.kernel_sources_dir.clone(),
+                        )
+                    {
+                        if self.settings.persist_kernel_sources {
+                            let _ = self
+                                .work_dir
+                                .link_kernel_sources(&self.settings.kernel_sources());
+                        } else {
+                            self.work_dir.unlink_kernel_sources();
+                        }
+                    }
                 }
             }
         });
+
+        self.save_session();
     }
 }
@@ -0,0 +1,140 @@
+//! Log of the app's own actions (clone, downloads, config saves, builds),
+//! written to `~/.local/share/tkg-gui/tkg-gui.log`.
+//!
+//! Records are handed to a writer thread over a channel so logging never
+//! blocks the UI. The file is rotated once it exceeds [`MAX_LOG_SIZE`],
+//! keeping [`KEEP_ROTATED`] older files (`tkg-gui.log.1`, `.2`, ...).
+
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Mutex;
+use std::thread;
+
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+const KEEP_ROTATED: usize = 3;
+
+/// Environment variable that overrides the configured level
+pub const LEVEL_ENV_VAR: &str = "TKG_GUI_LOG";
+
+/// Levels offered in Settings, from least to most verbose
+pub const LEVELS: &[&str] = &["off", "error", "warn", "info", "debug"];
+
+struct FileLogger {
+    tx: Mutex<Sender<String>>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        // Only the app's own events, not those of dependencies
+        if !self.enabled(record.metadata()) || !record.target().starts_with("tkg_gui") {
+            return;
+        }
+        let line = format!(
+            "{} {:<5} [{}] {}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            record.level(),
+            record.target().trim_start_matches("tkg_gui::"),
+            record.args()
+        );
+        if let Ok(tx) = self.tx.lock() {
+            let _ = tx.send(line);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Parse a level name; unknown names fall back to `info`.
+pub fn parse_level(name: &str) -> LevelFilter {
+    name.trim().parse().unwrap_or(LevelFilter::Info)
+}
+
+/// Install the file logger. `$TKG_GUI_LOG` takes precedence over `level`.
+pub fn init(log_dir: &Path, level: &str) -> Result<(), String> {
+    fs::create_dir_all(log_dir).map_err(|e| e.to_string())?;
+    let path = log_dir.join("tkg-gui.log");
+
+    let (tx, rx) = channel::<String>();
+    log::set_boxed_logger(Box::new(FileLogger { tx: Mutex::new(tx) }))
+        .map_err(|e| e.to_string())?;
+    set_level(level);
+
+    thread::spawn(move || {
+        let mut file = open_append(&path);
+        let mut size = file
+            .as_ref()
+            .and_then(|f| f.metadata().ok())
+            .map(|m| m.len())
+            .unwrap_or(0);
+        for line in rx {
+            if size + line.len() as u64 > MAX_LOG_SIZE {
+                rotate(&path);
+                file = open_append(&path);
+                size = 0;
+            }
+            if let Some(f) = file.as_mut() {
+                if f.write_all(line.as_bytes()).is_ok() {
+                    size += line.len() as u64;
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Change the active level. `$TKG_GUI_LOG` takes precedence over `level`.
+pub fn set_level(level: &str) {
+    let level = std::env::var(LEVEL_ENV_VAR)
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| level.to_string());
+    log::set_max_level(parse_level(&level));
+}
+
+fn open_append(path: &Path) -> Option<File> {
+    OpenOptions::new().create(true).append(true).open(path).ok()
+}
+
+/// Shift `tkg-gui.log.N` to `.N+1`, dropping the oldest, then move the
+/// current log to `.1`.
+fn rotate(path: &Path) {
+    let rotated = |n: usize| PathBuf::from(format!("{}.{}", path.display(), n));
+    let _ = fs::remove_file(rotated(KEEP_ROTATED));
+    for n in (1..KEEP_ROTATED).rev() {
+        let _ = fs::rename(rotated(n), rotated(n + 1));
+    }
+    let _ = fs::rename(path, rotated(1));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotate_keeps_a_bounded_number_of_files() {
+        let dir = std::env::temp_dir().join(format!("tkg-gui-log-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("tkg-gui.log");
+
+        for i in 0..=KEEP_ROTATED + 1 {
+            fs::write(&path, i.to_string()).unwrap();
+            rotate(&path);
+        }
+
+        assert!(!path.exists());
+        assert_eq!(
+            fs::read_to_string(dir.join("tkg-gui.log.1")).unwrap(),
+            (KEEP_ROTATED + 1).to_string()
+        );
+        assert!(dir.join(format!("tkg-gui.log.{}", KEEP_ROTATED)).exists());
+        assert!(!dir.join(format!("tkg-gui.log.{}", KEEP_ROTATED + 1)).exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
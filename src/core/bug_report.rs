@@ -0,0 +1,169 @@
+//! Markdown report for a failed build, put together from what the app
+//! already knows (preview facts and checks, config, patches, log) so it can
+//! be pasted into an issue in one go.
+
+use crate::core::config_manager::ConfigManager;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::process::Command;
+
+/// Lines of build output included at the end of the report
+pub const LOG_TAIL_LINES: usize = 150;
+
+/// A `customization.cfg` value that differs from the one linux-tkg ships
+#[derive(Debug, PartialEq)]
+pub struct ConfigChange {
+    pub key: String,
+    /// None when the shipped file doesn't set the key
+    pub default: Option<String>,
+    /// None when the key was removed from the file
+    pub value: Option<String>,
+}
+
+pub struct BugReport {
+    /// `(label, value)` rows: app version, OS, command, environment, ...
+    pub facts: Vec<(String, String)>,
+    /// `(ok, description)` prerequisite checks
+    pub checks: Vec<(bool, String)>,
+    /// Changes from the shipped config, or why they couldn't be determined
+    pub config: Result<Vec<ConfigChange>, String>,
+    /// Userpatch file names; disabled ones keep their `.disabled` suffix
+    pub patches: Vec<String>,
+    /// `(is_error, text)` for the last lines of the build log
+    pub log_tail: Vec<(bool, String)>,
+}
+
+impl BugReport {
+    pub fn to_markdown(&self) -> String {
+        let cell = |text: &str| text.replace('|', "\\|").replace('\n', " ");
+        let mut md = String::from("## Build failure report\n\n### Environment\n\n");
+        md.push_str("| | |\n|---|---|\n");
+        for (label, value) in &self.facts {
+            md.push_str(&format!("| {} | `{}` |\n", cell(label), cell(value)));
+        }
+
+        if !self.checks.is_empty() {
+            md.push_str("\n### Checks\n\n");
+            for (ok, check) in &self.checks {
+                md.push_str(&format!("- {} {}\n", if *ok { "✓" } else { "✗" }, check));
+            }
+        }
+
+        md.push_str("\n### customization.cfg changes from default\n\n");
+        match &self.config {
+            Ok(changes) if changes.is_empty() => md.push_str("None.\n"),
+            Ok(changes) => {
+                md.push_str("| Option | Default | Set to |\n|---|---|---|\n");
+                for change in changes {
+                    let value = |v: &Option<String>| {
+                        v.as_deref().map_or("(unset)".to_string(), |v| format!("`{}`", cell(v)))
+                    };
+                    md.push_str(&format!(
+                        "| `{}` | {} | {} |\n",
+                        change.key,
+                        value(&change.default),
+                        value(&change.value)
+                    ));
+                }
+            }
+            Err(e) => md.push_str(&format!("_Unavailable: {}_\n", e)),
+        }
+
+        md.push_str("\n### Userpatches\n\n");
+        if self.patches.is_empty() {
+            md.push_str("None.\n");
+        }
+        for patch in &self.patches {
+            match patch.strip_suffix(".disabled") {
+                Some(name) => md.push_str(&format!("- {} (disabled)\n", name)),
+                None => md.push_str(&format!("- {}\n", patch)),
+            }
+        }
+
+        // A diff block shows error lines in red on GitHub and most forges
+        md.push_str(&format!(
+            "\n### Build log (last {} lines)\n\n````diff\n",
+            self.log_tail.len()
+        ));
+        for (error, text) in &self.log_tail {
+            md.push_str(if *error { "- " } else { "  " });
+            md.push_str(text);
+            md.push('\n');
+        }
+        md.push_str("````\n");
+        md
+    }
+}
+
+/// App version, OS and running kernel
+pub fn system_facts() -> Vec<(String, String)> {
+    let mut facts = vec![("App".to_string(), format!("tkg-gui {}", env!("CARGO_PKG_VERSION")))];
+    let os = std::fs::read_to_string("/etc/os-release").ok().and_then(|content| {
+        content.lines().find_map(|line| {
+            let value = line.strip_prefix("PRETTY_NAME=")?;
+            Some(value.trim_matches('"').to_string())
+        })
+    });
+    facts.push(("OS".to_string(), os.unwrap_or_else(|| "unknown".to_string())));
+    let kernel = std::fs::read_to_string("/proc/sys/kernel/osrelease")
+        .map(|r| r.trim().to_string())
+        .unwrap_or_else(|_| "unknown".to_string());
+    facts.push(("Running kernel".to_string(), kernel));
+    facts
+}
+
+/// Values of `dir/customization.cfg` that differ from the committed file
+/// (git `HEAD`), sorted by key
+pub fn config_changes(dir: &Path) -> Result<Vec<ConfigChange>, String> {
+    let current = ConfigManager::load(dir.join("customization.cfg"))?.get_all_options();
+    let output = Command::new("git")
+        .args(["show", "HEAD:customization.cfg"])
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("Cannot run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("{} is not a git checkout of linux-tkg", dir.display()));
+    }
+    let shipped = String::from_utf8_lossy(&output.stdout);
+    let shipped = ConfigManager::parse(&shipped, dir.join("customization.cfg")).get_all_options();
+
+    let keys: BTreeSet<&String> = current.keys().chain(shipped.keys()).collect();
+    Ok(keys
+        .into_iter()
+        .filter(|key| current.get(*key) != shipped.get(*key))
+        .map(|key| ConfigChange {
+            key: key.clone(),
+            default: shipped.get(key).cloned(),
+            value: current.get(key).cloned(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_marks_errors_and_escapes_tables() {
+        let report = BugReport {
+            facts: vec![("Command".to_string(), "a | b".to_string())],
+            checks: vec![(false, "Not on PATH: clang".to_string())],
+            config: Ok(vec![ConfigChange {
+                key: "_cpusched".to_string(),
+                default: None,
+                value: Some("bore".to_string()),
+            }]),
+            patches: vec!["a.patch".to_string(), "b.mypatch.disabled".to_string()],
+            log_tail: vec![
+                (false, "  CC kernel/fork.o".to_string()),
+                (true, "==> ERROR: A failure occurred in build().".to_string()),
+            ],
+        };
+        let md = report.to_markdown();
+        assert!(md.contains("| Command | `a \\| b` |"), "{}", md);
+        assert!(md.contains("- ✗ Not on PATH: clang"));
+        assert!(md.contains("| `_cpusched` | (unset) | `bore` |"));
+        assert!(md.contains("- b.mypatch (disabled)"));
+        assert!(md.contains("    CC kernel/fork.o\n- ==> ERROR: A failure"));
+    }
+}
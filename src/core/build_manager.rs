@@ -1,22 +1,223 @@
-use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
-use std::process::{ChildStdin, Command, Stdio};
+use crate::core::pty;
+use crate::core::version;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
 use std::sync::mpsc::Sender;
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
 pub enum BuildMsg {
     Line(String),
-    Exit(i32),
+    Exit(BuildResult),
     SpawnError(String),
 }
 
+/// Outcome of a finished build process
+#[derive(Clone, Debug)]
+pub struct BuildResult {
+    pub exit_code: i32,
+    pub duration: Duration,
+    /// Packages written to the work dir (or its DEBS/RPMS) during the run
+    pub artifacts: Vec<PathBuf>,
+    pub warnings: usize,
+    pub errors: usize,
+}
+
+impl BuildResult {
+    /// One-line summary, e.g. "exit code 0 in 12m 03s, 4 warnings, 0 errors"
+    pub fn summary(&self) -> String {
+        let secs = self.duration.as_secs();
+        let elapsed = if secs >= 3600 {
+            format!("{}h {:02}m {:02}s", secs / 3600, secs / 60 % 60, secs % 60)
+        } else {
+            format!("{}m {:02}s", secs / 60, secs % 60)
+        };
+        let mut text = format!(
+            "exit code {} in {}, {} warnings, {} errors",
+            self.exit_code, elapsed, self.warnings, self.errors
+        );
+        if !self.artifacts.is_empty() {
+            text.push_str(&format!(", {} packages", self.artifacts.len()));
+        }
+        text
+    }
+}
+
+/// Rough classification of a build output line
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LineKind {
+    Normal,
+    Stage,
+    Warning,
+    Error,
+}
+
+/// Output reporting that the disk filled up (ENOSPC), whatever tool printed it
+pub fn is_out_of_space(text: &str) -> bool {
+    text.contains("No space left on device")
+}
+
+/// Classify a build output line. makepkg and linux-tkg report problems as
+/// `==> ERROR:` / `==> WARNING:` (or under a `->` sub-step marker), so the
+/// marker alone doesn't make a line a stage.
+pub fn classify_line(text: &str) -> LineKind {
+    let trimmed = text.trim_start();
+    let after_marker = trimmed
+        .strip_prefix("==>")
+        .or_else(|| trimmed.strip_prefix("->"))
+        .map(|rest| rest.trim_start().chars().take(7).collect::<String>().to_uppercase());
+    let marked = |word: &str| after_marker.as_deref().is_some_and(|rest| rest.starts_with(word));
+    if is_out_of_space(text) || marked("ERROR") {
+        LineKind::Error
+    } else if marked("WARNING") {
+        LineKind::Warning
+    } else if trimmed.starts_with("==>") {
+        LineKind::Stage
+    } else if text.contains("warning:") || text.contains("WARNING") {
+        LineKind::Warning
+    } else if text.contains("error:") || text.contains("ERROR") || text.contains("FAILED") {
+        LineKind::Error
+    } else {
+        LineKind::Normal
+    }
+}
+
+/// Sends output to the UI while tallying what is needed for the final
+/// [`BuildResult`].
+#[derive(Clone)]
+struct Reporter {
+    tx: Sender<BuildMsg>,
+    /// Copy of every line for an external reader; dropped once it fails
+    mirror: InputWriter,
+    /// Copy of every line kept on disk; dropped once a write fails
+    log_file: InputWriter,
+    warnings: Arc<AtomicUsize>,
+    errors: Arc<AtomicUsize>,
+    work_dir: Option<PathBuf>,
+    started: SystemTime,
+    started_at: Instant,
+    /// Signal target while running: minus the process group id of the
+    /// child, which leads its own group. 0 once it has exited.
+    target: Arc<AtomicI32>,
+}
+
+impl Reporter {
+    fn line(&self, text: String) {
+        match classify_line(&text) {
+            LineKind::Warning => {
+                self.warnings.fetch_add(1, Ordering::Relaxed);
+            }
+            LineKind::Error => {
+                self.errors.fetch_add(1, Ordering::Relaxed);
+            }
+            _ => {}
+        }
+        self.mirror_line(&text);
+        self.log_line(&text);
+        let _ = self.tx.send(BuildMsg::Line(text));
+    }
+
+    fn log_line(&self, text: &str) {
+        let Ok(mut guard) = self.log_file.lock() else {
+            return;
+        };
+        if let Some(writer) = guard.as_mut() {
+            if let Err(e) = writeln!(writer, "{}", text) {
+                log::warn!("Build log file closed: {}", e);
+                *guard = None;
+            }
+        }
+    }
+
+    fn mirror_line(&self, text: &str) {
+        let Ok(mut guard) = self.mirror.lock() else {
+            return;
+        };
+        if let Some(writer) = guard.as_mut() {
+            match writeln!(writer, "{}", text) {
+                // A full pipe drops the line rather than stalling the build
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(e) => {
+                    log::warn!("Build output mirror closed: {}", e);
+                    *guard = None;
+                }
+                Ok(()) => {}
+            }
+        }
+    }
+
+    fn spawn_error(&self, e: String) {
+        self.log_line(&format!("==> ERROR: {}", e));
+        let _ = self.tx.send(BuildMsg::SpawnError(e));
+    }
+
+    fn finish(&self, exit_code: i32) {
+        let artifacts = self
+            .work_dir
+            .as_deref()
+            .map(|dir| find_artifacts(dir, self.started))
+            .unwrap_or_default();
+        let result = BuildResult {
+            exit_code,
+            duration: self.started_at.elapsed(),
+            artifacts,
+            warnings: self.warnings.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+        };
+        self.log_line(&format!("==> Finished: {}", result.summary()));
+        let _ = self.tx.send(BuildMsg::Exit(result));
+    }
+}
+
+type InputWriter = Arc<Mutex<Option<Box<dyn Write + Send>>>>;
+
 /// Handle for sending input to the build process
 pub struct BuildHandle {
-    stdin: Arc<Mutex<Option<ChildStdin>>>,
+    stdin: InputWriter,
+    target: Arc<AtomicI32>,
+}
+
+/// How long [`BuildHandle::kill`] waits after SIGTERM before SIGKILL
+const KILL_GRACE: Duration = Duration::from_secs(5);
+
+fn send_signal(target: i32, signal: libc::c_int) -> Result<(), String> {
+    // SAFETY: kill has no memory-safety preconditions
+    if unsafe { libc::kill(target, signal) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error().to_string())
+    }
 }
 
 impl BuildHandle {
+    /// Stop the build: SIGTERM to its process group, which holds makepkg or
+    /// install.sh and every make and compiler process they started, then
+    /// SIGKILL to whatever is left of the group after [`KILL_GRACE`].
+    /// Returns once SIGTERM is sent.
+    pub fn kill(&self) -> Result<(), String> {
+        let target = self.target.load(Ordering::SeqCst);
+        if target == 0 {
+            return Err("Build process is not running".to_string());
+        }
+        log::warn!("Terminating build process group {}", -target);
+        send_signal(target, libc::SIGTERM)?;
+        thread::spawn(move || {
+            let deadline = Instant::now() + KILL_GRACE;
+            // Signal 0 only checks that some process of the group remains
+            while Instant::now() < deadline && send_signal(target, 0).is_ok() {
+                thread::sleep(Duration::from_millis(100));
+            }
+            if send_signal(target, 0).is_ok() {
+                log::warn!("Build process group {} ignored SIGTERM; killing it", -target);
+                let _ = send_signal(target, libc::SIGKILL);
+            }
+        });
+        Ok(())
+    }
+
     /// Send input to the build process (adds newline automatically)
     pub fn send_input(&self, input: &str) -> Result<(), String> {
         if let Ok(mut guard) = self.stdin.lock() {
@@ -30,93 +231,1101 @@ impl BuildHandle {
     }
 }
 
-pub fn start_build(work_dir: PathBuf, tx: Sender<BuildMsg>, use_makepkg: bool) -> BuildHandle {
-    let stdin_handle: Arc<Mutex<Option<ChildStdin>>> = Arc::new(Mutex::new(None));
-    let stdin_clone = stdin_handle.clone();
+/// Resolve the build directory to its canonical path so symlinked or
+/// bind-mounted linux-tkg trees are handled consistently.
+///
+/// Fails with a descriptive message if the path (or a symlink target along
+/// it) no longer exists.
+pub fn resolve_work_dir(work_dir: &Path) -> Result<PathBuf, String> {
+    match work_dir.canonicalize() {
+        Ok(path) if path.is_dir() => Ok(path),
+        Ok(path) => Err(format!("{} is not a directory", path.display())),
+        Err(e) => {
+            if work_dir.is_symlink() {
+                let target = std::fs::read_link(work_dir)
+                    .map(|t| t.display().to_string())
+                    .unwrap_or_else(|_| "?".to_string());
+                Err(format!(
+                    "{} is a symlink to {}, which no longer exists",
+                    work_dir.display(),
+                    target
+                ))
+            } else {
+                Err(format!("Cannot access {}: {}", work_dir.display(), e))
+            }
+        }
+    }
+}
+
+/// Check that `dir` looks like a linux-tkg tree. Returns the build entry
+/// point found (`PKGBUILD` or `install.sh`).
+pub fn validate_build_dir(dir: &Path) -> Result<&'static str, String> {
+    if !dir.is_dir() {
+        return Err(format!("{} is not a directory", dir.display()));
+    }
+    ["PKGBUILD", "install.sh"]
+        .into_iter()
+        .find(|name| dir.join(name).is_file())
+        .ok_or_else(|| format!("No PKGBUILD or install.sh in {}", dir.display()))
+}
+
+/// Shell command line the build runs, for display and external terminals.
+/// With `install` unset, makepkg only builds and the packages are installed
+/// separately by [`start_install`]. `keep_sources` adds `--noextract`, so
+/// makepkg compiles the existing `src/` without extracting or running
+/// prepare().
+pub fn build_command_line(use_makepkg: bool, install: bool, keep_sources: bool) -> &'static str {
+    match (use_makepkg, install, keep_sources) {
+        (true, true, false) => "makepkg -si",
+        (true, false, false) => "makepkg -s",
+        (true, true, true) => "makepkg -si --noextract",
+        (true, false, true) => "makepkg -s --noextract",
+        (false, _, _) => "./install.sh install",
+    }
+}
+
+/// `MAKEFLAGS` for a job count (0 for none) and load-average limit, or None
+/// when neither is set and the inherited value should be kept
+pub fn make_flags(jobs: usize, load_limit: Option<f64>) -> Option<String> {
+    let mut flags = Vec::new();
+    if jobs > 0 {
+        flags.push(format!("-j{}", jobs));
+    }
+    if let Some(limit) = load_limit {
+        flags.push(format!("-l{}", limit));
+    }
+    (!flags.is_empty()).then(|| flags.join(" "))
+}
+
+/// Parse a load-average limit as typed; empty means no limit
+pub fn parse_load_limit(text: &str) -> Result<Option<f64>, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+    match text.parse::<f64>() {
+        Ok(limit) if limit.is_finite() && limit > 0.0 => Ok(Some(limit)),
+        Ok(_) => Err("Load limit must be greater than 0".to_string()),
+        Err(_) => Err(format!("\"{}\" is not a number", text)),
+    }
+}
+
+/// Why `dir` can't be built without touching its sources, if it can't:
+/// install.sh always prepares the tree, and makepkg needs the `src/` of an
+/// earlier run
+pub fn keep_sources_problem(dir: &Path, use_makepkg: bool) -> Option<String> {
+    if !use_makepkg {
+        Some("install.sh always re-prepares the sources; only makepkg builds can skip it".into())
+    } else if !dir.join("src").is_dir() {
+        Some(format!("No src/ in {} from an earlier makepkg run", dir.display()))
+    } else {
+        None
+    }
+}
+
+/// What a clean build deletes from a makepkg dir: the `src/` and `pkg/`
+/// trees of earlier runs and the `*.pkg.tar.*` files they left. Kernel
+/// checkouts, the PKGBUILD, configs and patches are never included.
+pub fn clean_targets(dir: &Path) -> Vec<PathBuf> {
+    let mut targets: Vec<PathBuf> = ["src", "pkg"]
+        .iter()
+        .map(|name| dir.join(name))
+        .filter(|path| path.symlink_metadata().is_ok())
+        .collect();
+    let mut packages: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_name().to_string_lossy().contains(".pkg.tar"))
+                .filter(|e| e.file_type().is_ok_and(|t| t.is_file()))
+                .map(|e| e.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    packages.sort();
+    targets.extend(packages);
+    targets
+}
+
+/// Delete `targets` from [`clean_targets`]. Symlinks are removed, not
+/// followed.
+pub fn clean_build_dir(targets: &[PathBuf]) -> Result<(), String> {
+    for path in targets {
+        let result = match path.symlink_metadata() {
+            Ok(meta) if meta.is_dir() => std::fs::remove_dir_all(path),
+            Ok(_) => std::fs::remove_file(path),
+            Err(_) => continue,
+        };
+        result.map_err(|e| format!("Cannot delete {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Warning for a build dir that has a build entry point but not the
+/// linux-tkg layout around it, e.g. an unrelated PKGBUILD
+pub fn tkg_tree_warning(dir: &Path) -> Option<String> {
+    let missing: Vec<&str> = ["customization.cfg", "linux-tkg-config"]
+        .into_iter()
+        .filter(|name| !dir.join(name).exists())
+        .collect();
+    (!missing.is_empty()).then(|| {
+        format!("Not a recognizable linux-tkg tree: no {}", missing.join(" or "))
+    })
+}
+
+/// Programs a build needs on `$PATH`; `compiler` is linux-tkg's `_compiler`
+/// value ("llvm" for Clang, anything else for GCC).
+pub fn required_tools(use_makepkg: bool, compiler: &str) -> Vec<&'static str> {
+    let mut tools = vec!["git", "make", "bc", "bison", "flex", "perl"];
+    tools.extend(if compiler == "llvm" { ["clang", "ld.lld"] } else { ["gcc", "ld"] });
+    tools.push(if use_makepkg { "makepkg" } else { "bash" });
+    tools
+}
+
+/// Environment variables that change how the kernel is compiled or where
+/// packages end up, shown in the build preview when set
+pub const BUILD_ENV_VARS: &[&str] = &[
+    "MAKEFLAGS", "CC", "CXX", "LLVM", "CFLAGS", "KCFLAGS", "PKGDEST", "BUILDDIR",
+];
+
+/// Value of a plain `name=value` assignment in the PKGBUILD, with quotes and
+/// array parentheses removed. Values built from shell variables are returned
+/// as written.
+pub fn pkgbuild_field(work_dir: &Path, name: &str) -> Option<String> {
+    let content = std::fs::read_to_string(work_dir.join("PKGBUILD")).ok()?;
+    content.lines().find_map(|line| {
+        let value = line.trim_start().strip_prefix(name)?.strip_prefix('=')?;
+        let value = value
+            .trim()
+            .trim_matches(['(', ')'])
+            .replace(['"', '\''], "");
+        Some(value.trim().to_string()).filter(|v| !v.is_empty())
+    })
+}
+
+/// `uname -r` of the kernel linux-tkg builds from the `customization.cfg`
+/// values given by `option`: `<version>[-<pkgrel>]-<flavor>`, where the flavor
+/// is `_kernel_localversion` or `tkg-<scheduler>[-llvm]`. Only makepkg builds
+/// include `pkgrel`. None while `_version` is unset.
+pub fn expected_release(
+    option: impl Fn(&str) -> Option<String>,
+    pkgrel: Option<&str>,
+) -> Option<String> {
+    let set = |key: &str| option(key).filter(|v| !v.trim().is_empty());
+    let version = set("_version")?;
+    let (mut numbers, suffix) = version::parts(&version);
+    if numbers.len() == 2 {
+        // Kernel releases always have three components: 6.14 is 6.14.0
+        numbers.push(0);
+    }
+    let mut release = numbers
+        .iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>()
+        .join(".");
+    for part in suffix.into_iter().chain(pkgrel) {
+        release.push('-');
+        release.push_str(part);
+    }
+
+    let flavor = set("_kernel_localversion").unwrap_or_else(|| {
+        let scheduler = set("_cpusched").unwrap_or_else(|| "<scheduler>".to_string());
+        let llvm = if set("_compiler").as_deref() == Some("llvm") { "-llvm" } else { "" };
+        format!("tkg-{}{}", scheduler, llvm)
+    });
+    Some(format!("{}-{}", release, flavor))
+}
+
+/// Kernel release a built package installs, read from its file name. Only
+/// the kernel image of pacman and deb builds carries it; headers and RPMs
+/// give None.
+pub fn package_release(package: &Path) -> Option<String> {
+    let name = package.file_name()?.to_string_lossy();
+    if let Some((stem, _)) = name.split_once(".pkg.tar") {
+        // <pkgname>-<pkgver>-<pkgrel>-<arch>, e.g. linux613-tkg-eevdf-6.13.2-263-x86_64
+        let mut fields = stem.rsplitn(4, '-');
+        let (_arch, pkgrel, pkgver, pkgname) =
+            (fields.next()?, fields.next()?, fields.next()?, fields.next()?);
+        if pkgname.ends_with("-headers") {
+            return None;
+        }
+        let (_, flavor) = pkgname.split_once('-')?;
+        return Some(format!("{}-{}-{}", pkgver, pkgrel, flavor));
+    }
+    // linux-image-<release>_<version>_<arch>.deb
+    let release = name.strip_prefix("linux-image-")?.split('_').next()?;
+    (name.ends_with(".deb") && !release.ends_with("-dbg")).then(|| release.to_string())
+}
+
+/// Terminal emulators to try, with the flag that precedes the command to run.
+const TERMINALS: &[(&str, &str)] = &[
+    ("x-terminal-emulator", "-e"),
+    ("konsole", "-e"),
+    ("gnome-terminal", "--"),
+    ("xfce4-terminal", "-x"),
+    ("alacritty", "-e"),
+    ("kitty", "--"),
+    ("foot", "--"),
+    ("xterm", "-e"),
+];
+
+/// Run `command_line` in `work_dir` inside an external terminal emulator, for
+/// steps that need a real TTY (menuconfig/nconfig, sudo prompts).
+///
+/// `$TERMINAL` is tried first. The terminal stays open after the command
+/// exits. Returns the name of the terminal that was launched.
+pub fn launch_in_terminal(work_dir: &Path, command_line: &str) -> Result<String, String> {
+    // Capture the exit code before `echo` resets `$?`; double quotes so it expands
+    let script = format!(
+        "{}; rc=$?; echo; read -r -p \"Finished (exit $rc). Press Enter to close…\" _",
+        command_line
+    );
+
+    let env_terminal = std::env::var("TERMINAL").ok().filter(|t| !t.is_empty());
+    let candidates = env_terminal
+        .as_deref()
+        .map(|t| (t, "-e"))
+        .into_iter()
+        .chain(TERMINALS.iter().copied());
+
+    for (terminal, exec_flag) in candidates {
+        let spawned = Command::new(terminal)
+            .arg(exec_flag)
+            .args(["bash", "-c", &script])
+            .current_dir(work_dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        if spawned.is_ok() {
+            return Ok(terminal.to_string());
+        }
+    }
+    Err("No terminal emulator found (set $TERMINAL)".to_string())
+}
+
+/// Start the build. With `use_pty` the process runs under a pseudo-terminal
+/// so it behaves interactively; otherwise stdio is piped. `install` only
+/// and `keep_sources` only apply to makepkg (see [`build_command_line`]).
+/// `makeflags` replaces the inherited `MAKEFLAGS` (see [`make_flags`]).
+/// Output is also written to `log_file` (see [`build_log_path`]).
+#[allow(clippy::too_many_arguments)]
+pub fn start_build(
+    work_dir: PathBuf,
+    tx: Sender<BuildMsg>,
+    use_makepkg: bool,
+    install: bool,
+    keep_sources: bool,
+    use_pty: bool,
+    makeflags: Option<&str>,
+    mirror: Option<&Path>,
+    log_file: Option<&Path>,
+) -> BuildHandle {
+    // Use makepkg for Arch-based distros, install.sh for others
+    let mut command = if use_makepkg {
+        let mut c = Command::new("makepkg");
+        c.arg(if install { "-si" } else { "-s" });
+        if keep_sources {
+            c.arg("--noextract");
+        }
+        c
+    } else {
+        let script = work_dir.join("install.sh");
+        match ensure_executable(&script) {
+            Ok(false) => {}
+            Ok(true) => {
+                log::info!("Set the execute bit on {}", script.display());
+                let _ = tx.send(BuildMsg::Line(
+                    "==> install.sh was not executable; set its execute bit".to_string(),
+                ));
+            }
+            Err(e) => log::warn!("Cannot make {} executable: {}", script.display(), e),
+        }
+        let mut c = Command::new("./install.sh");
+        c.arg("install");
+        c
+    };
+    if let Some(flags) = makeflags {
+        command.env("MAKEFLAGS", flags);
+    }
+    command.current_dir(&work_dir);
+    spawn_streaming(command, tx, use_pty, mirror, log_file)
+}
+
+/// File in `dir` for the output of a build of `version` starting now, e.g.
+/// `20261016-153000-6.14.2.log`; names sort oldest first
+pub fn build_log_path(dir: &Path, version: Option<&str>) -> PathBuf {
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    match version.map(version::strip_v).filter(|v| !v.is_empty()) {
+        Some(v) => dir.join(format!("{}-{}.log", stamp, v.replace('/', "_"))),
+        None => dir.join(format!("{}.log", stamp)),
+    }
+}
+
+/// Delete all but the `keep` newest build logs in `dir` and return the
+/// deleted paths. `keep == 0` keeps everything.
+pub fn prune_build_logs(dir: &Path, keep: usize) -> Vec<PathBuf> {
+    if keep == 0 {
+        return Vec::new();
+    }
+    let mut logs: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.is_file() && p.extension().is_some_and(|e| e == "log"))
+                .collect()
+        })
+        .unwrap_or_default();
+    logs.sort();
+    let excess = logs.len().saturating_sub(keep);
+    logs.into_iter()
+        .take(excess)
+        .filter(|path| match std::fs::remove_file(path) {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!("Cannot delete old build log {}: {}", path.display(), e);
+                false
+            }
+        })
+        .collect()
+}
+
+/// Add the execute bits matching the read bits of `script` if it has none.
+/// Returns whether the mode was changed; a missing file is left to the
+/// spawn error.
+fn ensure_executable(script: &Path) -> std::io::Result<bool> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = match std::fs::metadata(script) {
+        Ok(meta) if meta.is_file() => meta.permissions(),
+        Ok(_) => return Ok(false),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    let mode = permissions.mode();
+    if mode & 0o111 != 0 {
+        return Ok(false);
+    }
+    // r-- becomes r-x for each of user, group and other
+    permissions.set_mode(mode | (mode & 0o444) >> 2);
+    std::fs::set_permissions(script, permissions)?;
+    Ok(true)
+}
+
+/// Install the packages from a previous `makepkg` run without recompiling.
+pub fn start_install(
+    work_dir: PathBuf,
+    tx: Sender<BuildMsg>,
+    use_pty: bool,
+    mirror: Option<&Path>,
+) -> BuildHandle {
+    let mut command = Command::new("makepkg");
+    command.args(["--install", "--needed"]).current_dir(&work_dir);
+    spawn_streaming(command, tx, use_pty, mirror, None)
+}
+
+/// Command that installs already built packages with the package tool of the
+/// distro that produced them: `sudo pacman -U` for `.pkg.tar.*`, `sudo dpkg -i`
+/// for `.deb` and `sudo rpm -Uvh` for `.rpm`.
+pub fn package_install_args(packages: &[PathBuf]) -> Result<Vec<String>, String> {
+    let tool = |path: &PathBuf| {
+        let name = path.file_name()?.to_str()?;
+        if name.contains(".pkg.tar") {
+            Some(["pacman", "-U"])
+        } else if name.ends_with(".deb") {
+            Some(["dpkg", "-i"])
+        } else if name.ends_with(".rpm") {
+            Some(["rpm", "-Uvh"])
+        } else {
+            None
+        }
+    };
+    let first = packages.first().ok_or("No built packages")?;
+    let command = tool(first)
+        .ok_or_else(|| format!("Unknown package type: {}", first.display()))?;
+    if let Some(other) = packages.iter().find(|p| tool(p) != Some(command)) {
+        return Err(format!("Mixed package types: {}", other.display()));
+    }
+    let mut args = vec!["sudo".to_string()];
+    args.extend(command.iter().map(|s| s.to_string()));
+    args.extend(packages.iter().map(|p| p.display().to_string()));
+    Ok(args)
+}
+
+/// Split a user-configured hook command into program and arguments, using
+/// shell quoting rules (no expansion or pipes).
+pub fn parse_command(command_line: &str) -> Result<Vec<String>, String> {
+    let args = shell_words::split(command_line).map_err(|e| e.to_string())?;
+    if args.is_empty() {
+        return Err("Command is empty".to_string());
+    }
+    Ok(args)
+}
+
+/// Run a hook command in `work_dir`, streaming like a build.
+pub fn start_command(
+    args: &[String],
+    work_dir: PathBuf,
+    tx: Sender<BuildMsg>,
+    use_pty: bool,
+    mirror: Option<&Path>,
+) -> BuildHandle {
+    let mut command = Command::new(&args[0]);
+    command.args(&args[1..]).current_dir(&work_dir);
+    spawn_streaming(command, tx, use_pty, mirror, None)
+}
 
-    thread::spawn(move || {
-        // Use makepkg for Arch-based distros, install.sh for others
-        let result = if use_makepkg {
-            Command::new("makepkg")
-                .arg("-si")
-                .current_dir(&work_dir)
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()
+/// Missing packages reported by makepkg/pacman, collected from build output.
+#[derive(Default)]
+pub struct MissingDeps {
+    /// Package names, without version constraints, in report order
+    pub packages: Vec<String>,
+    /// makepkg gave up resolving dependencies
+    pub failed: bool,
+    // Inside makepkg's "Missing dependencies:" list
+    in_list: bool,
+}
+
+impl MissingDeps {
+    /// Feed one line of build output.
+    pub fn feed(&mut self, line: &str) {
+        let line = line.trim();
+        if let Some(pkg) = line.strip_prefix("error: target not found:") {
+            self.add(pkg);
+        } else if line.starts_with("==> Missing dependencies:") {
+            self.in_list = true;
+        } else if let (true, Some(pkg)) = (self.in_list, line.strip_prefix("->")) {
+            self.add(pkg);
         } else {
-            Command::new("./install.sh")
-                .arg("install")
-                .current_dir(&work_dir)
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()
+            self.in_list = false;
+            if line.contains("Could not resolve all dependencies")
+                || line.contains("failed to install missing dependencies")
+            {
+                self.failed = true;
+            }
+        }
+    }
+
+    fn add(&mut self, entry: &str) {
+        let name = entry
+            .trim()
+            .split(['<', '>', '='])
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        if !name.is_empty() && !self.packages.contains(&name) {
+            self.packages.push(name);
+        }
+    }
+
+    /// `sudo pacman -S --needed ...` for the collected packages
+    pub fn install_command(&self) -> String {
+        format!("sudo pacman -S --needed {}", self.packages.join(" "))
+    }
+}
+
+/// Packages (`*.pkg.tar.*` excluding signatures, `*.deb`, `*.rpm`) in
+/// `work_dir` and its `DEBS`/`RPMS` output dirs modified at or after `since`,
+/// i.e. the ones produced by the last build.
+pub fn find_artifacts(work_dir: &Path, since: SystemTime) -> Vec<PathBuf> {
+    let dirs = [work_dir.to_path_buf(), work_dir.join("DEBS"), work_dir.join("RPMS")];
+    let mut packages: Vec<PathBuf> = dirs
+        .iter()
+        .flat_map(|dir| find_built_packages(dir, since))
+        .collect();
+    packages.sort();
+    packages
+}
+
+fn find_built_packages(dir: &Path, since: SystemTime) -> Vec<PathBuf> {
+    let mut packages: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| {
+                    let name = e.file_name().to_string_lossy().to_string();
+                    (name.contains(".pkg.tar") && !name.ends_with(".sig"))
+                        || name.ends_with(".deb")
+                        || name.ends_with(".rpm")
+                })
+                .filter(|e| {
+                    e.metadata()
+                        .and_then(|m| m.modified())
+                        .map(|t| t >= since)
+                        .unwrap_or(false)
+                })
+                .map(|e| e.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    packages.sort();
+    packages
+}
+
+/// Open the output mirror at `path`: connect to a listening Unix socket, or
+/// write to a FIFO (created if nothing exists there). Writes never block, so
+/// a slow or missing reader can't stall the build.
+pub fn open_mirror(path: &Path) -> Result<Box<dyn Write + Send>, String> {
+    use std::os::unix::fs::{FileTypeExt, OpenOptionsExt};
+    use std::os::unix::net::UnixStream;
+
+    let open_fifo = || {
+        std::fs::OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path)
+            .map_err(|e| match e.raw_os_error() {
+                Some(libc::ENXIO) => format!("nothing is reading {}", path.display()),
+                _ => format!("cannot open {}: {}", path.display(), e),
+            })
+    };
+    match std::fs::metadata(path) {
+        Ok(meta) if meta.file_type().is_socket() => {
+            let stream = UnixStream::connect(path)
+                .map_err(|e| format!("cannot connect to {}: {}", path.display(), e))?;
+            stream.set_nonblocking(true).map_err(|e| e.to_string())?;
+            Ok(Box::new(stream))
+        }
+        Ok(meta) if meta.file_type().is_fifo() => Ok(Box::new(open_fifo()?)),
+        Ok(_) => Err(format!("{} is not a FIFO or socket", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            use std::os::unix::ffi::OsStrExt;
+            let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())
+                .map_err(|e| e.to_string())?;
+            // SAFETY: c_path is a valid NUL-terminated path
+            if unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) } != 0 {
+                return Err(format!(
+                    "cannot create FIFO {}: {}",
+                    path.display(),
+                    std::io::Error::last_os_error()
+                ));
+            }
+            Ok(Box::new(open_fifo()?))
+        }
+        Err(e) => Err(format!("cannot access {}: {}", path.display(), e)),
+    }
+}
+
+/// Spawn `command`, streaming output lines and the exit status to `tx` from
+/// a background thread. Lines are also copied to the mirror at `mirror`
+/// when it can be opened, and to the file `log_file` (created with its
+/// parent dirs).
+fn spawn_streaming(
+    command: Command,
+    tx: Sender<BuildMsg>,
+    use_pty: bool,
+    mirror: Option<&Path>,
+    log_file: Option<&Path>,
+) -> BuildHandle {
+    log::info!(
+        "Spawning {:?} {:?} in {} (pty: {})",
+        command.get_program(),
+        command.get_args().collect::<Vec<_>>(),
+        command
+            .get_current_dir()
+            .map(|d| d.display().to_string())
+            .unwrap_or_default(),
+        use_pty
+    );
+    let mirror = mirror.and_then(|path| match open_mirror(path) {
+        Ok(writer) => Some(writer),
+        Err(e) => {
+            log::warn!("Build output mirror unavailable: {}", e);
+            let _ = tx.send(BuildMsg::Line(format!("==> Output mirror unavailable: {}", e)));
+            None
+        }
+    });
+    let log_file = log_file.and_then(|path| {
+        let file = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::File::create(path));
+        match file {
+            Ok(file) => Some(Box::new(std::io::LineWriter::new(file)) as Box<dyn Write + Send>),
+            Err(e) => {
+                log::warn!("Cannot create build log {}: {}", path.display(), e);
+                let _ = tx.send(BuildMsg::Line(format!(
+                    "==> WARNING: Cannot write the build log {}: {}",
+                    path.display(),
+                    e
+                )));
+                None
+            }
+        }
+    });
+    let stdin_handle: InputWriter = Arc::new(Mutex::new(None));
+    let stdin_clone = stdin_handle.clone();
+    let report = Reporter {
+        tx,
+        mirror: Arc::new(Mutex::new(mirror)),
+        log_file: Arc::new(Mutex::new(log_file)),
+        warnings: Arc::new(AtomicUsize::new(0)),
+        errors: Arc::new(AtomicUsize::new(0)),
+        work_dir: command.get_current_dir().map(Path::to_path_buf),
+        started: SystemTime::now(),
+        started_at: Instant::now(),
+        target: Arc::new(AtomicI32::new(0)),
+    };
+    let target = report.target.clone();
+
+    if use_pty {
+        thread::spawn(move || run_pty(command, report, stdin_clone));
+    } else {
+        thread::spawn(move || run_piped(command, report, stdin_clone));
+    }
+
+    BuildHandle {
+        stdin: stdin_handle,
+        target,
+    }
+}
+
+fn run_pty(mut command: Command, report: Reporter, stdin_clone: InputWriter) {
+    let (child, mut master) = match pty::spawn(&mut command, 160, 48) {
+        Ok(pair) => pair,
+        Err(e) => {
+            log::error!("Build spawn failed: {}", e);
+            report.spawn_error(spawn_failure(&command, &e));
+            return;
+        }
+    };
+    // pty::spawn makes the child a session leader, so signal its group
+    report.target.store(-(child.id() as i32), Ordering::SeqCst);
+
+    // Input goes to the same master end the output is read from
+    if let Ok(writer) = master.try_clone() {
+        if let Ok(mut guard) = stdin_clone.lock() {
+            *guard = Some(Box::new(writer));
+        }
+    }
+
+    let mut pending: Vec<u8> = Vec::new();
+    let mut buffer = [0u8; 4096];
+    loop {
+        // EIO means every slave fd is closed, i.e. the process tree exited
+        let n = match master.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
         };
+        pending.extend_from_slice(&buffer[..n]);
 
-        match result {
-            Ok(mut child) => {
-                // Store stdin handle for interactive input
-                if let Some(stdin) = child.stdin.take() {
-                    if let Ok(mut guard) = stdin_clone.lock() {
-                        *guard = Some(stdin);
-                    }
-                }
+        while let Some(pos) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=pos).collect();
+            report.line(terminal_line(&line));
+        }
 
-                let stdout = child.stdout.take();
-                let stderr = child.stderr.take();
-
-                // Spawn thread for stdout
-                let tx_stdout = tx.clone();
-                let stdout_handle = stdout.map(|out| {
-                    thread::spawn(move || {
-                        let reader = BufReader::new(out);
-                        for line in reader.lines().map_while(Result::ok) {
-                            let _ = tx_stdout.send(BuildMsg::Line(line));
-                        }
-                    })
-                });
-
-                // Spawn thread for stderr
-                let tx_stderr = tx.clone();
-                let stderr_handle = stderr.map(|err| {
-                    thread::spawn(move || {
-                        let reader = BufReader::new(err);
-                        for line in reader.lines().map_while(Result::ok) {
-                            let _ = tx_stderr.send(BuildMsg::Line(line));
-                        }
-                    })
-                });
-
-                // Wait for output threads
-                if let Some(h) = stdout_handle {
-                    let _ = h.join();
-                }
-                if let Some(h) = stderr_handle {
-                    let _ = h.join();
-                }
+        // Prompts don't end in a newline; flush them so the user sees them
+        let partial = terminal_line(&pending);
+        let trimmed = partial.trim_end();
+        if trimmed.ends_with(':') || trimmed.ends_with('?') || trimmed.ends_with(']') {
+            report.line(partial);
+            pending.clear();
+        }
+    }
+    if !pending.is_empty() {
+        report.line(terminal_line(&pending));
+    }
 
-                // Clear stdin handle
+    if let Ok(mut guard) = stdin_clone.lock() {
+        *guard = None;
+    }
+    wait_and_report(child, &report);
+}
+
+/// Turn raw terminal bytes into a display line: drop escapes, and keep only
+/// the last carriage-return-separated segment (progress bars redraw in place).
+fn terminal_line(bytes: &[u8]) -> String {
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.trim_end_matches(['\n', '\r']);
+    let last = text.rsplit('\r').next().unwrap_or_default();
+    pty::strip_ansi(last)
+}
+
+fn wait_and_report(mut child: Child, report: &Reporter) {
+    let status = child.wait();
+    report.target.store(0, Ordering::SeqCst);
+    match status {
+        Ok(status) => {
+            let code = status.code().unwrap_or(-1);
+            log::info!("Build process exited with code {}", code);
+            report.finish(code);
+        }
+        Err(e) => {
+            log::error!("Waiting for build process failed: {}", e);
+            report.spawn_error(e.to_string());
+        }
+    }
+}
+
+fn run_piped(mut command: Command, report: Reporter, stdin_clone: InputWriter) {
+    use std::os::unix::process::CommandExt;
+    // Own process group, so kill() reaches the compilers make starts
+    let result = command
+        .process_group(0)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+
+    match result {
+        Ok(mut child) => {
+            report.target.store(-(child.id() as i32), Ordering::SeqCst);
+
+            // Store stdin handle for interactive input
+            if let Some(stdin) = child.stdin.take() {
                 if let Ok(mut guard) = stdin_clone.lock() {
-                    *guard = None;
+                    *guard = Some(Box::new(stdin));
                 }
+            }
+
+            let stdout = child.stdout.take();
+            let stderr = child.stderr.take();
 
-                // Wait for process to exit
-                match child.wait() {
-                    Ok(status) => {
-                        let code = status.code().unwrap_or(-1);
-                        let _ = tx.send(BuildMsg::Exit(code));
+            // Spawn thread for stdout
+            let report_stdout = report.clone();
+            let stdout_handle = stdout.map(|out| {
+                thread::spawn(move || {
+                    let reader = BufReader::new(out);
+                    for line in reader.lines().map_while(Result::ok) {
+                        report_stdout.line(line);
                     }
-                    Err(e) => {
-                        let _ = tx.send(BuildMsg::SpawnError(e.to_string()));
+                })
+            });
+
+            // Spawn thread for stderr
+            let report_stderr = report.clone();
+            let stderr_handle = stderr.map(|err| {
+                thread::spawn(move || {
+                    let reader = BufReader::new(err);
+                    for line in reader.lines().map_while(Result::ok) {
+                        report_stderr.line(line);
                     }
-                }
+                })
+            });
+
+            // Wait for output threads
+            if let Some(h) = stdout_handle {
+                let _ = h.join();
             }
-            Err(e) => {
-                let _ = tx.send(BuildMsg::SpawnError(e.to_string()));
+            if let Some(h) = stderr_handle {
+                let _ = h.join();
             }
+
+            // Clear stdin handle
+            if let Ok(mut guard) = stdin_clone.lock() {
+                *guard = None;
+            }
+
+            // Wait for process to exit
+            wait_and_report(child, &report);
         }
-    });
+        Err(e) => {
+            log::error!("Build spawn failed: {}", e);
+            report.spawn_error(spawn_failure(&command, &e));
+        }
+    }
+}
+
+/// Why `command` could not be started, with a hint for the usual causes:
+/// the tool isn't installed, or install.sh is missing or not executable.
+pub fn spawn_failure(command: &Command, e: &std::io::Error) -> String {
+    let program = command.get_program().to_string_lossy();
+    let name = program.rsplit('/').next().unwrap_or_default();
+    match (e.kind(), name) {
+        (std::io::ErrorKind::NotFound, "makepkg") => {
+            "makepkg not found. Are you on Arch? Install base-devel \
+             (sudo pacman -S --needed base-devel), or set _distro for install.sh."
+                .to_string()
+        }
+        (std::io::ErrorKind::NotFound, "install.sh") => {
+            "install.sh missing. Is this a valid linux-tkg clone?".to_string()
+        }
+        (std::io::ErrorKind::NotFound, _) => {
+            format!("{} not found. Is it installed and on PATH?", program)
+        }
+        (std::io::ErrorKind::PermissionDenied, _) => {
+            format!("{} is not executable. Run: chmod +x {}", program, program)
+        }
+        _ => format!("Failed to start {}: {}", program, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawn_failures_name_the_likely_cause() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("install.sh");
+        std::fs::write(&script, "#!/bin/sh\n").unwrap();
+        let mut command = Command::new("./install.sh");
+        command.current_dir(dir.path());
+        let e = command.spawn().err().unwrap();
+        assert!(spawn_failure(&command, &e).contains("chmod +x"));
+
+        std::fs::remove_file(&script).unwrap();
+        let e = command.spawn().err().unwrap();
+        assert!(spawn_failure(&command, &e).contains("valid linux-tkg clone"));
+
+        let e = std::io::Error::from(std::io::ErrorKind::NotFound);
+        assert!(spawn_failure(&Command::new("makepkg"), &e).contains("base-devel"));
+    }
+
+    #[test]
+    fn kill_stops_the_whole_process_group() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut command = Command::new("sh");
+        command.args(["-c", "sleep 30 & echo $!; wait"]);
+        let handle = spawn_streaming(command, tx, false, None, None);
+        let timeout = Duration::from_secs(5);
+        let Ok(BuildMsg::Line(pid)) = rx.recv_timeout(timeout) else {
+            panic!("no pid printed");
+        };
+
+        handle.kill().unwrap();
+        assert!(matches!(rx.recv_timeout(timeout), Ok(BuildMsg::Exit(_))));
+        // The orphaned sleep is gone, or a zombie if nothing reaps it here
+        let stat = format!("/proc/{}/stat", pid.trim());
+        let deadline = Instant::now() + timeout;
+        let running = || {
+            std::fs::read_to_string(&stat).is_ok_and(|s| !s.contains(") Z "))
+        };
+        while running() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(50));
+        }
+        assert!(!running(), "sleep {} survived", pid);
+    }
+
+    #[test]
+    fn output_is_logged_and_old_logs_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let logs = dir.path().join("build-logs");
+        let log_file = build_log_path(&logs, Some("v6.14.2"));
+        assert!(log_file.to_string_lossy().ends_with("-6.14.2.log"));
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo compiling"]);
+        let _handle = spawn_streaming(command, tx, false, None, Some(&log_file));
+        while !matches!(rx.recv_timeout(Duration::from_secs(5)), Ok(BuildMsg::Exit(_))) {}
+        let logged = std::fs::read_to_string(&log_file).unwrap();
+        assert!(logged.starts_with("compiling\n==> Finished: exit code 0"), "{}", logged);
+
+        for old in ["20240101-000000-6.1.log", "20250101-000000-6.6.log"] {
+            std::fs::write(logs.join(old), "").unwrap();
+        }
+        let pruned = prune_build_logs(&logs, 2);
+        assert_eq!(pruned, vec![logs.join("20240101-000000-6.1.log")]);
+        assert!(log_file.exists());
+    }
+
+    #[test]
+    fn install_script_gets_execute_bit_once() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("install.sh");
+        std::fs::write(&script, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o644)).unwrap();
 
-    BuildHandle { stdin: stdin_handle }
+        assert!(ensure_executable(&script).unwrap());
+        let mode = std::fs::metadata(&script).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+        assert!(!ensure_executable(&script).unwrap());
+        assert!(!ensure_executable(&dir.path().join("missing.sh")).unwrap());
+    }
+
+    #[test]
+    fn kernel_release_from_config_and_packages() {
+        let config = |key: &str| {
+            match key {
+                "_version" => Some("v6.14-rc3"),
+                "_cpusched" => Some("eevdf"),
+                "_compiler" => Some("llvm"),
+                _ => None,
+            }
+            .map(String::from)
+        };
+        assert_eq!(
+            expected_release(config, Some("263")).as_deref(),
+            Some("6.14.0-rc3-263-tkg-eevdf-llvm")
+        );
+        let local = |key: &str| match key {
+            "_version" => Some("v6.13.2".to_string()),
+            "_kernel_localversion" => Some("mine".to_string()),
+            _ => None,
+        };
+        assert_eq!(expected_release(local, None).as_deref(), Some("6.13.2-mine"));
+        assert_eq!(expected_release(|_| None, None), None);
+
+        let release = |name: &str| package_release(Path::new(name));
+        assert_eq!(
+            release("linux613-tkg-eevdf-6.13.2-263-x86_64.pkg.tar.zst").as_deref(),
+            Some("6.13.2-263-tkg-eevdf")
+        );
+        assert_eq!(release("linux613-tkg-eevdf-headers-6.13.2-263-x86_64.pkg.tar.zst"), None);
+        assert_eq!(
+            release("linux-image-6.13.2-tkg-eevdf_6.13.2-tkg-eevdf-1_amd64.deb").as_deref(),
+            Some("6.13.2-tkg-eevdf")
+        );
+        assert_eq!(release("linux-headers-6.13.2-tkg-eevdf_1_amd64.deb"), None);
+    }
+
+    #[test]
+    fn missing_deps_from_makepkg_output() {
+        let output = "\
+==> Checking buildtime dependencies...
+==> Installing missing dependencies...
+error: target not found: pahole
+==> ERROR: 'pacman' failed to install missing dependencies.
+==> Missing dependencies:
+  -> pahole
+  -> bc>=1.07
+==> ERROR: Could not resolve all dependencies.";
+        let mut deps = MissingDeps::default();
+        for line in output.lines() {
+            deps.feed(line);
+        }
+        assert!(deps.failed);
+        assert_eq!(deps.packages, vec!["pahole", "bc"]);
+        assert_eq!(deps.install_command(), "sudo pacman -S --needed pahole bc");
+    }
+
+    #[test]
+    fn build_result_summary() {
+        let result = BuildResult {
+            exit_code: 0,
+            duration: Duration::from_secs(3723),
+            artifacts: vec![PathBuf::from("linux-tkg-6.12.1-1-x86_64.pkg.tar.zst")],
+            warnings: 4,
+            errors: 0,
+        };
+        assert_eq!(
+            result.summary(),
+            "exit code 0 in 1h 02m 03s, 4 warnings, 0 errors, 1 packages"
+        );
+        assert_eq!(classify_line("==> Starting build()"), LineKind::Stage);
+        assert_eq!(
+            classify_line("==> ERROR: A failure occurred in build()."),
+            LineKind::Error
+        );
+        assert_eq!(classify_line("==> WARNING: Skipping verification"), LineKind::Warning);
+        assert_eq!(classify_line("  -> Error: missing patch"), LineKind::Error);
+        assert_eq!(classify_line("  -> Applying patch 0001"), LineKind::Normal);
+        assert_eq!(classify_line("make: *** [all] Error 2 FAILED"), LineKind::Error);
+        assert_eq!(
+            classify_line("cc1: warning: write failed: No space left on device"),
+            LineKind::Error
+        );
+    }
+
+    #[test]
+    fn hook_commands_use_shell_quoting() {
+        assert_eq!(
+            parse_command("sudo mkinitcpio -P --config '/etc/my conf'").unwrap(),
+            vec!["sudo", "mkinitcpio", "-P", "--config", "/etc/my conf"]
+        );
+        assert!(parse_command("   ").is_err());
+        assert!(parse_command("echo 'unterminated").is_err());
+    }
+
+    #[test]
+    fn package_installer_follows_package_type() {
+        let debs = [
+            PathBuf::from("/b/DEBS/linux-image-6.13.1-tkg_amd64.deb"),
+            PathBuf::from("/b/DEBS/linux-headers-6.13.1-tkg_amd64.deb"),
+        ];
+        assert_eq!(
+            package_install_args(&debs).unwrap(),
+            vec![
+                "sudo",
+                "dpkg",
+                "-i",
+                "/b/DEBS/linux-image-6.13.1-tkg_amd64.deb",
+                "/b/DEBS/linux-headers-6.13.1-tkg_amd64.deb",
+            ]
+        );
+        let pkg = [PathBuf::from("linux-tkg-6.13.1-1-x86_64.pkg.tar.zst")];
+        assert_eq!(package_install_args(&pkg).unwrap()[..3], ["sudo", "pacman", "-U"]);
+        assert!(package_install_args(&[]).is_err());
+        assert!(package_install_args(&[pkg[0].clone(), debs[0].clone()]).is_err());
+    }
+
+    #[test]
+    fn keep_sources_needs_a_previous_makepkg_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(build_command_line(true, false, true), "makepkg -s --noextract");
+        assert!(keep_sources_problem(dir.path(), false).is_some());
+        assert!(keep_sources_problem(dir.path(), true).unwrap().contains("No src/"));
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        assert_eq!(keep_sources_problem(dir.path(), true), None);
+
+        let warning = tkg_tree_warning(dir.path()).unwrap();
+        assert!(warning.contains("customization.cfg or linux-tkg-config"), "{}", warning);
+        std::fs::write(dir.path().join("customization.cfg"), "").unwrap();
+        std::fs::create_dir(dir.path().join("linux-tkg-config")).unwrap();
+        assert_eq!(tkg_tree_warning(dir.path()), None);
+    }
+
+    #[test]
+    fn clean_build_removes_only_build_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name);
+        std::fs::create_dir_all(path("src/linux-6.13")).unwrap();
+        std::fs::create_dir(path("pkg")).unwrap();
+        std::fs::create_dir(path("linux-src-git")).unwrap();
+        for file in ["PKGBUILD", "customization.cfg", "linux-tkg-6.13.1-1-x86_64.pkg.tar.zst"] {
+            std::fs::write(path(file), "").unwrap();
+        }
+
+        let targets = clean_targets(dir.path());
+        assert_eq!(
+            targets,
+            vec![path("src"), path("pkg"), path("linux-tkg-6.13.1-1-x86_64.pkg.tar.zst")]
+        );
+        clean_build_dir(&targets).unwrap();
+        assert!(clean_targets(dir.path()).is_empty());
+        assert!(path("linux-src-git").is_dir() && path("PKGBUILD").exists());
+    }
+
+    #[test]
+    fn makeflags_from_jobs_and_load_limit() {
+        assert_eq!(make_flags(0, None), None);
+        assert_eq!(make_flags(8, None).as_deref(), Some("-j8"));
+        assert_eq!(make_flags(8, Some(6.5)).as_deref(), Some("-j8 -l6.5"));
+        assert_eq!(make_flags(0, Some(4.0)).as_deref(), Some("-l4"));
+
+        assert_eq!(parse_load_limit(" "), Ok(None));
+        assert_eq!(parse_load_limit("2.5"), Ok(Some(2.5)));
+        assert!(parse_load_limit("0").is_err());
+        assert!(parse_load_limit("-1").is_err());
+        assert!(parse_load_limit("inf").is_err());
+        assert!(parse_load_limit("high").is_err());
+    }
+
+    #[test]
+    fn mirror_accepts_sockets_and_fifos_only() {
+        use std::io::Read;
+        use std::os::unix::net::UnixListener;
+
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("build.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        let mut writer = open_mirror(&socket).unwrap();
+        writeln!(writer, "==> Building").unwrap();
+        drop(writer);
+        let mut received = String::new();
+        listener.accept().unwrap().0.read_to_string(&mut received).unwrap();
+        assert_eq!(received, "==> Building\n");
+
+        // A new FIFO without a reader is created but can't be written yet
+        let fifo = dir.path().join("build.fifo");
+        let err = open_mirror(&fifo).err().unwrap();
+        assert!(err.contains("nothing is reading"), "{}", err);
+        assert!(fifo.exists());
+
+        let file = dir.path().join("plain.log");
+        std::fs::write(&file, "").unwrap();
+        assert!(open_mirror(&file).is_err());
+    }
 }
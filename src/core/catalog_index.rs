@@ -0,0 +1,156 @@
+use crate::core::http_client;
+use crate::core::patch_manager::check_file_name;
+use crate::data::catalog::{self, CatalogEntryOwned, CatalogIndex};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Cache files of the index at `url`, so switching URLs never serves another
+/// index's entries or ETag: (index, etag).
+fn cache_files(url: &str, cache_dir: &Path) -> (PathBuf, PathBuf) {
+    let key = format!("{:x}", Sha256::digest(url.as_bytes()));
+    let stem = format!("catalog_index-{}", &key[..16]);
+    (
+        cache_dir.join(format!("{}.json", stem)),
+        cache_dir.join(format!("{}.etag", stem)),
+    )
+}
+
+/// Drop entries whose file names would escape the userpatches folder
+fn safe_entries(entries: Vec<CatalogEntryOwned>) -> Vec<CatalogEntryOwned> {
+    entries
+        .into_iter()
+        .filter(|entry| {
+            let unsafe_name = std::iter::once(&entry.filename_template)
+                .chain(entry.files.iter().map(|f| &f.filename_template))
+                .find_map(|name| check_file_name(name).err());
+            if let Some(e) = &unsafe_name {
+                log::warn!("Skipping catalog entry {}: {}", entry.id, e);
+            }
+            unsafe_name.is_none()
+        })
+        .collect()
+}
+
+/// Outcome of a remote catalog refresh
+pub enum CatalogRefresh {
+    /// Fetched a new index with this many entries
+    Updated(usize),
+    /// Server reported no change; the cached index (this many entries) is in use
+    NotModified(usize),
+    /// Fetch failed; fell back to the cached index (entry count, reason)
+    Cached(usize, String),
+    /// Fetch failed and there is no cache; only the built-in catalog is in use
+    Error(String),
+}
+
+/// Fetch the catalog index at `url` and merge it into the catalog.
+///
+/// The last good index is cached in `cache_dir` together with its ETag, so
+/// unchanged indexes aren't re-downloaded and offline starts still see the
+/// remote entries. Blocking; call from a worker thread.
+pub fn refresh(url: &str, cache_dir: &Path) -> CatalogRefresh {
+    let cached = load_cached(url, cache_dir);
+    if let Some(index) = &cached {
+        catalog::set_remote_entries(index.entries.clone());
+    } else {
+        catalog::set_remote_entries(Vec::new());
+    }
+
+    match fetch(url, cache_dir, cached.is_some()) {
+        Ok(Some(index)) => {
+            let count = index.entries.len();
+            catalog::set_remote_entries(index.entries);
+            CatalogRefresh::Updated(count)
+        }
+        Ok(None) => CatalogRefresh::NotModified(catalog::remote_entry_count()),
+        Err(e) => match cached {
+            Some(index) => CatalogRefresh::Cached(index.entries.len(), e),
+            None => CatalogRefresh::Error(e),
+        },
+    }
+}
+
+/// Returns `Ok(None)` when the server answers 304 Not Modified.
+fn fetch(url: &str, cache_dir: &Path, have_cache: bool) -> Result<Option<CatalogIndex>, String> {
+    let (index_file, etag_file) = cache_files(url, cache_dir);
+    let mut request = http_client::agent().get(url);
+    let etag = fs::read_to_string(&etag_file).ok();
+    if let (true, Some(etag)) = (have_cache, &etag) {
+        request = request.set("If-None-Match", etag.trim());
+    }
+
+    let response = request.call().map_err(|e| e.to_string())?;
+    if response.status() == 304 {
+        return Ok(None);
+    }
+
+    let new_etag = response.header("ETag").map(|s| s.to_string());
+    let body = response.into_string().map_err(|e| e.to_string())?;
+    let mut index: CatalogIndex =
+        serde_json::from_str(&body).map_err(|e| format!("Invalid catalog index: {}", e))?;
+    index.entries = safe_entries(index.entries);
+
+    fs::create_dir_all(cache_dir).map_err(|e| e.to_string())?;
+    fs::write(&index_file, &body).map_err(|e| e.to_string())?;
+    match new_etag {
+        Some(tag) => fs::write(&etag_file, tag).map_err(|e| e.to_string())?,
+        None => {
+            let _ = fs::remove_file(&etag_file);
+        }
+    }
+
+    Ok(Some(index))
+}
+
+fn load_cached(url: &str, cache_dir: &Path) -> Option<CatalogIndex> {
+    let content = fs::read_to_string(cache_files(url, cache_dir).0).ok()?;
+    let mut index: CatalogIndex = serde_json::from_str(&content).ok()?;
+    index.entries = safe_entries(index.entries);
+    Some(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::catalog::CatalogFile;
+
+    fn entry(id: &str, filename: &str) -> CatalogEntryOwned {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": id,
+            "description": "",
+            "url_template": "https://example.com/{series}.patch",
+            "filename_template": filename,
+            "supported_series": [],
+            "tags": [],
+            "category": "misc",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn unsafe_file_names_are_dropped() {
+        let mut extra = entry("extra", "ok-{series}.patch");
+        extra.files.push(CatalogFile {
+            url_template: "https://example.com/b.patch".to_string(),
+            filename_template: "../../.bashrc".to_string(),
+        });
+        let kept = safe_entries(vec![
+            entry("good", "bore-{series}.patch"),
+            entry("escape", "../{series}.patch"),
+            entry("hidden", ".{series}.patch"),
+            extra,
+        ]);
+        let ids: Vec<&str> = kept.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["good"]);
+    }
+
+    #[test]
+    fn cache_is_per_url() {
+        let dir = Path::new("/cache");
+        let a = cache_files("https://a/index.json", dir);
+        assert_eq!(a, cache_files("https://a/index.json", dir));
+        assert_ne!(a, cache_files("https://b/index.json", dir));
+    }
+}
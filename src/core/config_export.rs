@@ -0,0 +1,151 @@
+//! Sharing a tuning: the `customization.cfg` values written to a standalone
+//! file (JSON, or `key="value"` lines when the name ends in `.cfg`) and read
+//! back from either form.
+
+use crate::core::config_manager::ConfigManager;
+use crate::core::presets::EXCLUDED_KEYS;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+#[derive(Serialize, Deserialize)]
+struct ConfigExport {
+    /// tkg-gui version that wrote the file
+    tkg_gui: String,
+    values: BTreeMap<String, String>,
+}
+
+/// What [`import_config`] read
+pub struct ImportedConfig {
+    /// Values for keys `known` accepted
+    pub values: BTreeMap<String, String>,
+    /// Keys that were skipped, sorted
+    pub unknown: Vec<String>,
+}
+
+fn is_cfg(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "cfg")
+}
+
+/// Write `values`, except [`EXCLUDED_KEYS`], to `path`. Returns the number
+/// of options written.
+pub fn export_config(values: &HashMap<String, String>, path: &Path) -> Result<usize, String> {
+    let values: BTreeMap<String, String> = values
+        .iter()
+        .filter(|(key, _)| !EXCLUDED_KEYS.contains(&key.as_str()))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    let count = values.len();
+    let content = if is_cfg(path) {
+        // `key="value"` lines have no escaping; such a value would end early
+        let unquotable: Vec<&str> = values
+            .iter()
+            .filter(|(_, value)| value.contains(['"', '\n']))
+            .map(|(key, _)| key.as_str())
+            .collect();
+        if !unquotable.is_empty() {
+            return Err(format!(
+                "{} can't be written as customization.cfg lines (quote or line break in \
+                 the value); export as .json instead",
+                unquotable.join(", ")
+            ));
+        }
+        values
+            .iter()
+            .map(|(key, value)| format!("{}=\"{}\"\n", key, value))
+            .collect()
+    } else {
+        let export = ConfigExport {
+            tkg_gui: env!("CARGO_PKG_VERSION").to_string(),
+            values,
+        };
+        serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?
+    };
+    fs::write(path, content).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(count)
+}
+
+/// Read a file written by [`export_config`], or any `customization.cfg`.
+/// Keys `known` rejects (e.g. options of a newer linux-tkg) are left out
+/// and listed in `unknown`; [`EXCLUDED_KEYS`] are dropped.
+pub fn import_config(path: &Path, known: impl Fn(&str) -> bool) -> Result<ImportedConfig, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let all: HashMap<String, String> = match serde_json::from_str::<ConfigExport>(&content) {
+        Ok(export) => export.values.into_iter().collect(),
+        Err(_) if is_cfg(path) || !content.trim_start().starts_with('{') => {
+            ConfigManager::parse(&content, path).get_all_options()
+        }
+        Err(e) => return Err(format!("{} is not a tkg-gui config export: {}", path.display(), e)),
+    };
+    if all.is_empty() {
+        return Err(format!("{} has no linux-tkg options", path.display()));
+    }
+    let mut imported = ImportedConfig {
+        values: BTreeMap::new(),
+        unknown: Vec::new(),
+    };
+    for (key, value) in all {
+        if EXCLUDED_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        if known(&key) {
+            imported.values.insert(key, value);
+        } else {
+            imported.unknown.push(key);
+        }
+    }
+    imported.unknown.sort();
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_skips_unknown_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let values = HashMap::from([
+            ("_cpusched".to_string(), "bore".to_string()),
+            ("_from_newer_tkg".to_string(), "1".to_string()),
+        ]);
+        for name in ["tuning.json", "tuning.cfg"] {
+            let path = dir.path().join(name);
+            assert_eq!(export_config(&values, &path).unwrap(), 2);
+            let imported = import_config(&path, |key| key == "_cpusched").unwrap();
+            assert_eq!(imported.values.get("_cpusched").map(String::as_str), Some("bore"));
+            assert_eq!(imported.unknown, vec!["_from_newer_tkg"]);
+        }
+    }
+
+    #[test]
+    fn version_is_never_shared() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tuning.json");
+        let values = HashMap::from([
+            ("_cpusched".to_string(), "bore".to_string()),
+            ("_version".to_string(), "v6.12.1".to_string()),
+        ]);
+        assert_eq!(export_config(&values, &path).unwrap(), 1);
+
+        let cfg = dir.path().join("customization.cfg");
+        fs::write(&cfg, "_cpusched=\"eevdf\"\n_version=\"v6.6.1\"\n").unwrap();
+        let imported = import_config(&cfg, |_| true).unwrap();
+        assert_eq!(imported.values.keys().collect::<Vec<_>>(), vec!["_cpusched"]);
+        assert!(imported.unknown.is_empty());
+    }
+
+    #[test]
+    fn cfg_export_refuses_quotes() {
+        let dir = tempfile::tempdir().unwrap();
+        let values = HashMap::from([("_custom_commandline".to_string(), "a=\"b\"".to_string())]);
+        let err = export_config(&values, &dir.path().join("tuning.cfg")).unwrap_err();
+        assert!(err.contains("_custom_commandline"), "{}", err);
+        // JSON escapes it
+        let path = dir.path().join("tuning.json");
+        export_config(&values, &path).unwrap();
+        let imported = import_config(&path, |_| true).unwrap();
+        assert_eq!(imported.values["_custom_commandline"], "a=\"b\"");
+    }
+}
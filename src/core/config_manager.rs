@@ -6,7 +6,14 @@ use std::path::Path;
 #[derive(Clone, Debug)]
 pub enum Line {
     Comment(String),
-    Assignment { key: String, value: String, raw: String },
+    Assignment {
+        key: String,
+        value: String,
+        raw: String,
+        /// Trailing `# comment` with the whitespace before it, re-appended
+        /// when the value is changed
+        comment: Option<String>,
+    },
     Empty,
 }
 
@@ -17,8 +24,14 @@ pub struct ConfigManager {
 
 impl ConfigManager {
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let content = fs::read_to_string(path.as_ref()).map_err(|e| e.to_string())?;
+        Ok(Self::parse(&content, path))
+    }
+
+    /// Parse config content without touching the filesystem. `path` is only
+    /// remembered as the target for a later `save()`.
+    pub fn parse<P: AsRef<Path>>(content: &str, path: P) -> Self {
         let path = path.as_ref().to_path_buf();
-        let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
         let re = Regex::new(r#"^(_\w+)\s*=\s*["']?([^"'#\n]*)["']?"#).unwrap();
 
         let lines: Vec<Line> = content
@@ -34,6 +47,7 @@ impl ConfigManager {
                         key: caps[1].to_string(),
                         value: caps[2].trim().to_string(),
                         raw: line.to_string(),
+                        comment: trailing_comment(line, caps.get(0).map_or(0, |m| m.end())),
                     }
                 } else {
                     Line::Comment(line.to_string())
@@ -41,7 +55,7 @@ impl ConfigManager {
             })
             .collect();
 
-        Ok(Self { lines, path })
+        Self { lines, path }
     }
 
     pub fn get_option(&self, key: &str) -> Option<String> {
@@ -61,11 +75,12 @@ impl ConfigManager {
                 key: k,
                 value: v,
                 raw,
+                comment,
             } = line
             {
                 if k == key {
                     *v = value.to_string();
-                    *raw = format!("{}=\"{}\"", k, value);
+                    *raw = format!("{}=\"{}\"{}", k, value, comment.as_deref().unwrap_or(""));
                     return;
                 }
             }
@@ -75,6 +90,7 @@ impl ConfigManager {
             key: key.to_string(),
             value: value.to_string(),
             raw: format!("{}=\"{}\"", key, value),
+            comment: None,
         });
     }
 
@@ -88,7 +104,8 @@ impl ConfigManager {
         map
     }
 
-    pub fn save(&self) -> Result<(), String> {
+    /// Render the config back to file content, one line per parsed line.
+    pub fn to_content(&self) -> String {
         let content: String = self
             .lines
             .iter()
@@ -100,6 +117,155 @@ impl ConfigManager {
             .collect::<Vec<_>>()
             .join("\n");
 
-        fs::write(&self.path, content + "\n").map_err(|e| e.to_string())
+        content + "\n"
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        match fs::write(&self.path, self.to_content()) {
+            Ok(()) => {
+                log::info!("Saved {}", self.path.display());
+                Ok(())
+            }
+            Err(e) => {
+                log::error!("Saving {} failed: {}", self.path.display(), e);
+                Err(e.to_string())
+            }
+        }
+    }
+}
+
+/// `# comment` after the assignment that ends at `end`, with the whitespace
+/// that separates it from the value. None if anything else follows.
+fn trailing_comment(line: &str, end: usize) -> Option<String> {
+    let hash = end + line[end..].find('#')?;
+    if !line[end..hash].trim().is_empty() {
+        return None;
+    }
+    let value_end = line[..hash].trim_end().len();
+    Some(line[value_end..].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"# linux-tkg config file
+
+# Set to "true" to skip prompts
+_NUKR="true"
+
+#### MISC OPTIONS ####
+_distro="Arch"
+_version='v6.13'
+_cpusched="pds" # pds, bmq, bore, cfs, eevdf
+_modprobeddb="false"
+_custom_commandline=(intel_pstate=passive split_lock_detect=off)
+  _indented="yes"
+not an assignment
+_empty=""
+"#;
+
+    fn sample() -> ConfigManager {
+        ConfigManager::parse(SAMPLE, "customization.cfg")
+    }
+
+    #[test]
+    fn unedited_round_trip_is_byte_identical() {
+        assert_eq!(sample().to_content(), SAMPLE);
+    }
+
+    #[test]
+    fn reads_quoted_unquoted_and_single_quoted_values() {
+        let cfg = sample();
+        assert_eq!(cfg.get_option("_distro").as_deref(), Some("Arch"));
+        assert_eq!(cfg.get_option("_version").as_deref(), Some("v6.13"));
+        assert_eq!(cfg.get_option("_cpusched").as_deref(), Some("pds"));
+        assert_eq!(cfg.get_option("_empty").as_deref(), Some(""));
+        assert_eq!(cfg.get_option("_missing"), None);
+    }
+
+    #[test]
+    fn indented_and_garbage_lines_are_kept_verbatim() {
+        let cfg = sample();
+        assert_eq!(cfg.get_option("_indented"), None);
+        let out = cfg.to_content();
+        assert!(out.contains("\n  _indented=\"yes\"\n"));
+        assert!(out.contains("\nnot an assignment\n"));
+    }
+
+    #[test]
+    fn set_option_only_touches_the_edited_line() {
+        let mut cfg = sample();
+        cfg.set_option("_distro", "Ubuntu");
+        let out = cfg.to_content();
+
+        let before: Vec<&str> = SAMPLE.lines().collect();
+        let after: Vec<&str> = out.lines().collect();
+        assert_eq!(before.len(), after.len());
+        for (old, new) in before.iter().zip(&after) {
+            if old.starts_with("_distro=") {
+                assert_eq!(*new, "_distro=\"Ubuntu\"");
+            } else {
+                assert_eq!(old, new);
+            }
+        }
+    }
+
+    #[test]
+    fn set_option_appends_unknown_keys() {
+        let mut cfg = sample();
+        cfg.set_option("_new_key", "value");
+        assert_eq!(cfg.get_option("_new_key").as_deref(), Some("value"));
+        assert!(cfg.to_content().ends_with("_new_key=\"value\"\n"));
+    }
+
+    #[test]
+    fn array_values_survive_when_untouched() {
+        let cfg = sample();
+        assert!(cfg
+            .to_content()
+            .contains("_custom_commandline=(intel_pstate=passive split_lock_detect=off)\n"));
+    }
+
+    #[test]
+    fn get_all_options_lists_every_assignment() {
+        let opts = sample().get_all_options();
+        assert_eq!(opts.len(), 7);
+        assert_eq!(opts.get("_NUKR").map(String::as_str), Some("true"));
+    }
+
+    #[test]
+    fn inline_comments_survive_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("customization.cfg");
+        fs::write(&path, "_cpusched=\"pds\"   # scheduler\n_timer_freq=500 # Hz\n").unwrap();
+
+        let mut cfg = ConfigManager::load(&path).unwrap();
+        cfg.set_option("_cpusched", "bore");
+        cfg.set_option("_timer_freq", "1000");
+        cfg.save().unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "_cpusched=\"bore\"   # scheduler\n_timer_freq=\"1000\" # Hz\n"
+        );
+        let reloaded = ConfigManager::load(&path).unwrap();
+        assert_eq!(reloaded.get_option("_cpusched").as_deref(), Some("bore"));
+    }
+
+    #[test]
+    fn save_and_reload_preserves_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("customization.cfg");
+        fs::write(&path, SAMPLE).unwrap();
+
+        let mut cfg = ConfigManager::load(&path).unwrap();
+        cfg.set_option("_cpusched", "bore");
+        cfg.save().unwrap();
+
+        let reloaded = ConfigManager::load(&path).unwrap();
+        assert_eq!(reloaded.get_option("_cpusched").as_deref(), Some("bore"));
+        assert_eq!(reloaded.get_option("_version").as_deref(), Some("v6.13"));
+        assert_eq!(reloaded.get_all_options().len(), 7);
     }
 }
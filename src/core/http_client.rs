@@ -1,3 +1,4 @@
+use std::path::Path;
 use std::sync::{Arc, OnceLock};
 
 /// Returns a shared ureq Agent configured with a native-tls backend.
@@ -17,3 +18,111 @@ pub fn agent() -> &'static ureq::Agent {
             .build()
     })
 }
+
+/// Describe a failed request for the user: the HTTP status with the final
+/// URL and a few telling response headers, or the transport error kind
+/// (DNS, TLS, connection...).
+pub fn describe_error(error: ureq::Error) -> String {
+    match error {
+        ureq::Error::Status(code, response) => {
+            let mut text = format!(
+                "HTTP {} {} from {}",
+                code,
+                response.status_text(),
+                response.get_url()
+            );
+            let headers: Vec<String> = ["Content-Type", "Server", "Retry-After"]
+                .iter()
+                .filter_map(|name| response.header(name).map(|v| format!("{}: {}", name, v)))
+                .collect();
+            if !headers.is_empty() {
+                text.push_str(&format!(" ({})", headers.join(", ")));
+            }
+            text
+        }
+        ureq::Error::Transport(transport) => {
+            let mut text = transport.kind().to_string();
+            if let Some(url) = transport.url() {
+                text.push_str(&format!(" for {}", url));
+            }
+            if let Some(message) = transport.message() {
+                text.push_str(&format!(": {}", message));
+            }
+            text
+        }
+    }
+}
+
+/// External tool for [`download_command`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Downloader {
+    Curl,
+    Wget,
+}
+
+impl Downloader {
+    pub const ALL: [Downloader; 2] = [Downloader::Curl, Downloader::Wget];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Downloader::Curl => "curl",
+            Downloader::Wget => "wget",
+        }
+    }
+}
+
+/// Shell command that downloads `url` to `dest` the way the app would, for
+/// users who fetch outside it. With `decompress`, a `.xz`/`.gz` `dest` is
+/// piped through the decompressor and saved without that extension, like
+/// patch downloads.
+pub fn download_command(tool: Downloader, url: &str, dest: &Path, decompress: bool) -> String {
+    let dest = dest.to_string_lossy();
+    let decompressor = if !decompress {
+        None
+    } else if let Some(out) = dest.strip_suffix(".xz") {
+        Some(("xz -d", out))
+    } else {
+        dest.strip_suffix(".gz").map(|out| ("gzip -d", out))
+    };
+    let url = shell_words::quote(url);
+    match decompressor {
+        None => {
+            let dest = shell_words::quote(&dest);
+            match tool {
+                Downloader::Curl => format!("curl -fL -o {} {}", dest, url),
+                Downloader::Wget => format!("wget -O {} {}", dest, url),
+            }
+        }
+        Some((program, out)) => {
+            let out = shell_words::quote(out);
+            match tool {
+                Downloader::Curl => format!("curl -fL {} | {} > {}", url, program, out),
+                Downloader::Wget => format!("wget -O - {} | {} > {}", url, program, out),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn download_commands_quote_and_decompress() {
+        let url = "https://example.com/a patch.xz";
+        let dest = Path::new("/tmp/user patches/a.patch.xz");
+        assert_eq!(
+            download_command(Downloader::Curl, url, dest, false),
+            "curl -fL -o '/tmp/user patches/a.patch.xz' 'https://example.com/a patch.xz'"
+        );
+        assert_eq!(
+            download_command(Downloader::Wget, url, dest, true),
+            "wget -O - 'https://example.com/a patch.xz' | xz -d > '/tmp/user patches/a.patch'"
+        );
+        let plain = Path::new("/tmp/a.patch");
+        assert_eq!(
+            download_command(Downloader::Curl, "https://e.org/a.patch", plain, true),
+            "curl -fL -o /tmp/a.patch https://e.org/a.patch"
+        );
+    }
+}
@@ -1,7 +1,11 @@
-use crate::core::http_client;
+use crate::core::{http_client, version};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
 
 /// Progress update sent during download/extraction
 #[derive(Clone, Debug)]
@@ -12,87 +16,413 @@ pub enum DownloadProgress {
     Downloading(u64),
     /// Download complete, starting extraction
     Extracting,
+    /// Checking the download against kernel.org's published checksum, or a
+    /// previous download before reusing it
+    Verifying,
     /// Extraction complete, path to extracted folder
     Complete(PathBuf),
     /// Error occurred
     Error(String),
+    /// Download was cancelled and the partial file removed
+    Cancelled,
 }
 
 /// Result of a download operation
 pub enum DownloadResult {
     Success,
     Error,
+    Cancelled,
 }
 
+/// Where kernel.org publishes mainline release candidates; they never reach
+/// the stable CDN
+const MAINLINE_TARBALL_URL: &str = "https://git.kernel.org/torvalds/t";
+
 /// Get the download URL for a kernel version
 /// e.g., "6.19.2" -> "https://cdn.kernel.org/pub/linux/kernel/v6.x/linux-6.19.2.tar.xz"
+/// (2.6.x.y releases live under "v2.6", release candidates on git.kernel.org)
 pub fn get_download_url(version: &str) -> String {
-    let version = version.trim_start_matches('v');
-    let major = version.split('.').next().unwrap_or("6");
+    if version::is_rc(version) {
+        return format!("{}/{}", MAINLINE_TARBALL_URL, tarball_name(version));
+    }
+    let version = version::strip_v(version);
+    let mut parts = version.split('.');
+    let major = parts.next().unwrap_or("6");
+    let dir = if major == "2" {
+        format!("v2.{}", parts.next().unwrap_or("6"))
+    } else {
+        format!("v{}.x", major)
+    };
     format!(
-        "https://cdn.kernel.org/pub/linux/kernel/v{}.x/linux-{}.tar.xz",
-        major, version
+        "https://cdn.kernel.org/pub/linux/kernel/{}/linux-{}.tar.xz",
+        dir, version
     )
 }
 
+/// `sha256sums.asc` of the CDN directory `url` points into
+fn checksums_url(url: &str) -> Option<String> {
+    url.rsplit_once('/').map(|(dir, _)| format!("{}/sha256sums.asc", dir))
+}
+
+/// SHA-256 listed for `filename` in a kernel.org `sha256sums.asc`; the PGP
+/// armor around the `<hash>  <file>` lines is skipped
+fn find_checksum(sums: &str, filename: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let (hash, name) = (fields.next()?, fields.next()?);
+        let is_hash = hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit());
+        (is_hash && name == filename).then(|| hash.to_ascii_lowercase())
+    })
+}
+
+/// Compare the SHA-256 of the file downloaded from `url` with the one
+/// kernel.org publishes next to it. The signature of the list isn't
+/// checked, and an unreachable list is only logged so a flaky request
+/// doesn't throw the download away.
+fn check_published_checksum(url: &str, sha256: &str) -> Result<(), String> {
+    if url.starts_with(MAINLINE_TARBALL_URL) {
+        log::info!("No checksum list is published for {}; not verified", url);
+        return Ok(());
+    }
+    let (Some(sums_url), Some((_, filename))) = (checksums_url(url), url.rsplit_once('/')) else {
+        return Ok(());
+    };
+    let sums = match http_client::agent().get(&sums_url).call() {
+        Ok(response) => response.into_string().map_err(|e| e.to_string()),
+        Err(e) => Err(http_client::describe_error(e)),
+    };
+    let sums = match sums {
+        Ok(sums) => sums,
+        Err(e) => {
+            log::warn!("Cannot fetch {}; {} not verified: {}", sums_url, filename, e);
+            return Ok(());
+        }
+    };
+    match find_checksum(&sums, filename) {
+        Some(expected) if expected == sha256 => Ok(()),
+        Some(expected) => Err(format!(
+            "SHA-256 mismatch for {}: kernel.org lists {}, the download is {}",
+            filename, expected, sha256
+        )),
+        None => Err(format!("{} is not listed in {}", filename, sums_url)),
+    }
+}
+
+/// Size and SHA-256 of a downloaded tarball, stored as `linux-<version>.json`
+/// next to the extracted sources so a later reuse can be verified.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DownloadRecord {
+    pub version: String,
+    pub url: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Where the tarball of `version` is downloaded to
+pub fn tarball_path(dest_dir: &Path, version: &str) -> PathBuf {
+    dest_dir.join(tarball_name(version))
+}
+
+/// `linux-<version>.tar.xz`, or `.tar.gz` for release candidates, which
+/// git.kernel.org only serves gzipped
+pub fn tarball_name(version: &str) -> String {
+    let ext = if version::is_rc(version) { "gz" } else { "xz" };
+    format!("linux-{}.tar.{}", version::strip_v(version), ext)
+}
+
+fn record_path(dest_dir: &Path, version: &str) -> PathBuf {
+    dest_dir.join(format!("linux-{}.json", version))
+}
+
+/// Whether `version` was fully downloaded into `dest_dir` before.
+pub fn has_download_record(dest_dir: &Path, version: &str) -> bool {
+    record_path(dest_dir, version::strip_v(version)).is_file()
+}
+
 /// Download and extract kernel sources
 ///
+/// With `keep_tarball` the tarball is kept next to the extracted tree,
+/// together with a [`DownloadRecord`], so [`reuse_kernel`] can verify it
+/// later and re-extract without downloading again. Otherwise it is deleted
+/// after extraction and no record is written.
+///
 /// # Arguments
 /// * `version` - Kernel version (e.g., "6.19.2" or "v6.19.2")
 /// * `dest_dir` - Destination directory for extracted sources
+/// * `verify` - Sanity-check the extracted tree before reporting success
+/// * `keep_tarball` - Keep the `.tar.xz` for later re-extraction
+/// * `cancel` - Set to abort the download; checked between reads
 /// * `tx` - Channel sender for progress updates
 pub fn download_kernel(
     version: &str,
     dest_dir: &Path,
+    verify: bool,
+    keep_tarball: bool,
+    cancel: &AtomicBool,
     tx: std::sync::mpsc::Sender<DownloadProgress>,
 ) -> DownloadResult {
     let url = get_download_url(version);
-    let version = version.trim_start_matches('v');
+    let version = version::strip_v(version);
+    log::info!("Downloading kernel {} from {}", version, url);
 
     // Create destination directory if it doesn't exist
     if let Err(e) = fs::create_dir_all(dest_dir) {
         let msg = format!("Failed to create destination directory: {}", e);
+        log::error!("Kernel {}: {}", version, msg);
         let _ = tx.send(DownloadProgress::Error(msg));
         return DownloadResult::Error;
     }
 
-    let tarball_path = dest_dir.join(format!("linux-{}.tar.xz", version));
+    let tarball_path = tarball_path(dest_dir, version);
+    // A stale record must not vouch for the file being replaced
+    let _ = fs::remove_file(record_path(dest_dir, version));
 
     // Download the tarball
-    match download_file(&url, &tarball_path, &tx) {
-        Ok(()) => {}
+    let (size, sha256) = match download_file(&url, &tarball_path, cancel, &tx) {
+        Ok(_) if cancel.load(Ordering::Relaxed) => {
+            let _ = fs::remove_file(&tarball_path);
+            log::info!("Kernel {} download cancelled", version);
+            let _ = tx.send(DownloadProgress::Cancelled);
+            return DownloadResult::Cancelled;
+        }
+        Ok(digest) => digest,
         Err(e) => {
+            let _ = fs::remove_file(&tarball_path);
+            log::error!("Kernel {}: {}", version, e);
             let _ = tx.send(DownloadProgress::Error(e));
             return DownloadResult::Error;
         }
+    };
+
+    let _ = tx.send(DownloadProgress::Verifying);
+    if let Err(e) = check_published_checksum(&url, &sha256) {
+        let _ = fs::remove_file(&tarball_path);
+        log::error!("Kernel {}: {}", version, e);
+        let _ = tx.send(DownloadProgress::Error(e));
+        return DownloadResult::Error;
     }
 
     // Extract the tarball
     let _ = tx.send(DownloadProgress::Extracting);
-    match extract_tarball(&tarball_path, dest_dir) {
+    let extracted = extract_tarball(&tarball_path, dest_dir).and_then(|path| {
+        if verify {
+            verify_kernel_tree(&path, version)?;
+        }
+        Ok(path)
+    });
+
+    // The tarball passed its checksum, so it is kept (or deleted) as the
+    // setting says even if the tree turned out bad; reuse re-extracts it
+    if keep_tarball {
+        // Written after the download is complete, so a record only vouches
+        // for full tarballs
+        let record = DownloadRecord {
+            version: version.to_string(),
+            url,
+            size,
+            sha256,
+        };
+        if let Err(e) = write_record(dest_dir, &record) {
+            log::warn!("Kernel {}: could not write download record: {}", version, e);
+        }
+    } else if let Err(e) = fs::remove_file(&tarball_path) {
+        log::warn!("Kernel {}: could not delete the tarball: {}", version, e);
+    }
+
+    match extracted {
         Ok(extracted_path) => {
-            // Clean up tarball after successful extraction
-            let _ = fs::remove_file(&tarball_path);
+            log::info!("Kernel {} extracted to {}", version, extracted_path.display());
             let _ = tx.send(DownloadProgress::Complete(extracted_path));
             DownloadResult::Success
         }
         Err(e) => {
+            log::error!("Kernel {}: {}", version, e);
             let _ = tx.send(DownloadProgress::Error(e));
             DownloadResult::Error
         }
     }
 }
 
-/// Download a file with progress updates
+/// Reuse a previous download of `version` after checking the kept tarball
+/// against its [`DownloadRecord`] and sanity-checking the extracted tree.
+/// Re-extracts from the tarball if the tree was removed.
+pub fn reuse_kernel(
+    version: &str,
+    dest_dir: &Path,
+    tx: std::sync::mpsc::Sender<DownloadProgress>,
+) -> DownloadResult {
+    let version = version::strip_v(version);
+    let _ = tx.send(DownloadProgress::Verifying);
+    match verify_reuse(version, dest_dir) {
+        Ok(path) => {
+            log::info!("Reusing verified kernel {} at {}", version, path.display());
+            let _ = tx.send(DownloadProgress::Complete(path));
+            DownloadResult::Success
+        }
+        Err(e) => {
+            log::warn!("Kernel {} reuse check failed: {}", version, e);
+            let _ = tx.send(DownloadProgress::Error(format!("Reuse check failed: {}", e)));
+            DownloadResult::Error
+        }
+    }
+}
+
+fn verify_reuse(version: &str, dest_dir: &Path) -> Result<PathBuf, String> {
+    let content = fs::read_to_string(record_path(dest_dir, version))
+        .map_err(|e| format!("No download record: {}", e))?;
+    let record: DownloadRecord =
+        serde_json::from_str(&content).map_err(|e| format!("Invalid download record: {}", e))?;
+
+    let tarball = tarball_path(dest_dir, version);
+    let size = fs::metadata(&tarball)
+        .map_err(|e| format!("Tarball {} missing: {}", tarball.display(), e))?
+        .len();
+    if size != record.size {
+        return Err(format!(
+            "Tarball is {} but {} was downloaded (partial or modified file)",
+            format_bytes(size),
+            format_bytes(record.size)
+        ));
+    }
+    let mut hasher = Sha256::new();
+    std::io::copy(
+        &mut File::open(&tarball).map_err(|e| e.to_string())?,
+        &mut hasher,
+    )
+    .map_err(|e| format!("Failed to read tarball: {}", e))?;
+    if format!("{:x}", hasher.finalize()) != record.sha256 {
+        return Err("Tarball checksum does not match the original download".to_string());
+    }
+
+    let tree = dest_dir.join(format!("linux-{}", version));
+    let tree = if tree.is_dir() {
+        tree
+    } else {
+        extract_tarball(&tarball, dest_dir)?
+    };
+    verify_kernel_tree(&tree, version)?;
+    Ok(tree)
+}
+
+fn write_record(dest_dir: &Path, record: &DownloadRecord) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(record).map_err(|e| e.to_string())?;
+    fs::write(record_path(dest_dir, &record.version), content).map_err(|e| e.to_string())
+}
+
+/// A kernel version kept in the download directory
+#[derive(Clone, Debug)]
+pub struct CachedKernel {
+    pub version: String,
+    /// When it was downloaded (record, tree or tarball mtime)
+    pub modified: SystemTime,
+    /// Size of the kept `.tar.xz`, if there is one
+    pub tarball_size: Option<u64>,
+}
+
+/// Kernels in `dest_dir` (extracted trees or kept tarballs), newest first.
+pub fn cached_kernels(dest_dir: &Path) -> Vec<CachedKernel> {
+    let Ok(entries) = fs::read_dir(dest_dir) else {
+        return Vec::new();
+    };
+    let mut versions: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            let rest = name.strip_prefix("linux-")?;
+            if e.path().is_dir() {
+                Some(rest.to_string())
+            } else {
+                let version = rest.strip_suffix(".tar.xz").or(rest.strip_suffix(".tar.gz"))?;
+                Some(version.to_string())
+            }
+        })
+        // Only kernel versions, not e.g. a linux-tkg clone in the same folder
+        .filter(|version| !version::numbers(version).is_empty())
+        .collect();
+    versions.sort();
+    versions.dedup();
+
+    let mut kernels: Vec<CachedKernel> = versions
+        .into_iter()
+        .map(|version| {
+            let modified = [
+                record_path(dest_dir, &version),
+                dest_dir.join(format!("linux-{}", version)),
+                tarball_path(dest_dir, &version),
+            ]
+            .iter()
+            .find_map(|p| fs::metadata(p).and_then(|m| m.modified()).ok())
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+            let tarball_size = fs::metadata(tarball_path(dest_dir, &version))
+                .ok()
+                .map(|m| m.len());
+            CachedKernel {
+                version,
+                modified,
+                tarball_size,
+            }
+        })
+        .collect();
+    kernels.sort_by_key(|k| std::cmp::Reverse(k.modified));
+    kernels
+}
+
+/// Kernels beyond the `keep` most recent, never including `protect` (the
+/// version just downloaded). `keep == 0` keeps everything.
+pub fn prune_candidates(dest_dir: &Path, keep: usize, protect: &str) -> Vec<CachedKernel> {
+    if keep == 0 {
+        return Vec::new();
+    }
+    let protect = version::strip_v(protect);
+    let kernels = cached_kernels(dest_dir);
+    let kept_others = keep.saturating_sub(1);
+    kernels
+        .into_iter()
+        .filter(|k| k.version != protect)
+        .skip(kept_others)
+        .collect()
+}
+
+/// Delete the extracted tree, tarball and record of `version`.
+pub fn remove_cached_kernel(dest_dir: &Path, version: &str) -> Result<(), String> {
+    let tree = dest_dir.join(format!("linux-{}", version));
+    if tree.is_dir() {
+        fs::remove_dir_all(&tree).map_err(|e| format!("{}: {}", tree.display(), e))?;
+    }
+    for file in [tarball_path(dest_dir, version), record_path(dest_dir, version)] {
+        if file.is_file() {
+            fs::remove_file(&file).map_err(|e| format!("{}: {}", file.display(), e))?;
+        }
+    }
+    log::info!("Pruned cached kernel {} from {}", version, dest_dir.display());
+    Ok(())
+}
+
+/// Delete the kept tarball of `version` and its record, leaving the
+/// extracted tree. The version can no longer be reused without downloading.
+pub fn remove_tarball(dest_dir: &Path, version: &str) -> Result<(), String> {
+    for file in [tarball_path(dest_dir, version), record_path(dest_dir, version)] {
+        if file.is_file() {
+            fs::remove_file(&file).map_err(|e| format!("{}: {}", file.display(), e))?;
+        }
+    }
+    log::info!("Deleted the kept tarball of kernel {}", version);
+    Ok(())
+}
+
+/// Download a file with progress updates, hashing it as it is written.
+/// Returns the size and SHA-256, or returns early (leaving a partial file)
+/// once `cancel` is set.
 fn download_file(
     url: &str,
     dest: &Path,
+    cancel: &AtomicBool,
     tx: &std::sync::mpsc::Sender<DownloadProgress>,
-) -> Result<(), String> {
+) -> Result<(u64, String), String> {
     let response = http_client::agent()
         .get(url)
         .call()
-        .map_err(|e| format!("Failed to download: {}", e))?;
+        .map_err(|e| format!("Failed to download: {}", http_client::describe_error(e)))?;
 
     let total_size = response
         .header("Content-Length")
@@ -104,10 +434,11 @@ fn download_file(
     let mut file = File::create(dest)
         .map_err(|e| format!("Failed to create file: {}", e))?;
 
+    let mut hasher = Sha256::new();
     let mut downloaded: u64 = 0;
     let mut buffer = [0u8; 8192];
 
-    loop {
+    while !cancel.load(Ordering::Relaxed) {
         let bytes_read = reader
             .read(&mut buffer)
             .map_err(|e| format!("Failed to read: {}", e))?;
@@ -118,21 +449,25 @@ fn download_file(
 
         file.write_all(&buffer[..bytes_read])
             .map_err(|e| format!("Failed to write: {}", e))?;
+        hasher.update(&buffer[..bytes_read]);
 
         downloaded += bytes_read as u64;
         let _ = tx.send(DownloadProgress::Downloading(downloaded));
     }
 
-    Ok(())
+    Ok((downloaded, format!("{:x}", hasher.finalize())))
 }
 
-/// Extract a .tar.xz tarball
+/// Extract a .tar.xz or .tar.gz tarball
 fn extract_tarball(tarball: &Path, dest_dir: &Path) -> Result<PathBuf, String> {
     let file = File::open(tarball)
         .map_err(|e| format!("Failed to open tarball: {}", e))?;
 
-    // Decompress XZ
-    let decompressor = xz2::read::XzDecoder::new(file);
+    let decompressor: Box<dyn Read> = if tarball.extension().is_some_and(|e| e == "gz") {
+        Box::new(flate2::read::GzDecoder::new(file))
+    } else {
+        Box::new(xz2::read::XzDecoder::new(file))
+    };
 
     // Extract tar
     let mut archive = tar::Archive::new(decompressor);
@@ -165,6 +500,55 @@ fn extract_tarball(tarball: &Path, dest_dir: &Path) -> Result<PathBuf, String> {
     }
 }
 
+/// Check that an extracted kernel tree looks complete and matches `version`.
+///
+/// Catches truncated tarballs before they fail cryptically during the build.
+pub fn verify_kernel_tree(dir: &Path, version: &str) -> Result<(), String> {
+    for required in ["Makefile", "Kconfig", "kernel"] {
+        if !dir.join(required).exists() {
+            return Err(format!(
+                "Extracted tree at {} is incomplete: missing {}",
+                dir.display(),
+                required
+            ));
+        }
+    }
+
+    let makefile = fs::read_to_string(dir.join("Makefile"))
+        .map_err(|e| format!("Failed to read Makefile: {}", e))?;
+    let found = makefile_version(&makefile)
+        .ok_or_else(|| "Makefile has no VERSION/PATCHLEVEL".to_string())?;
+
+    let mut expected = version::numbers(version);
+    expected.resize(3, 0);
+
+    if found != (expected[0], expected[1], expected[2]) {
+        return Err(format!(
+            "Extracted tree is version {}.{}.{}, expected {}",
+            found.0,
+            found.1,
+            found.2,
+            version::strip_v(version)
+        ));
+    }
+    Ok(())
+}
+
+/// Parse `(VERSION, PATCHLEVEL, SUBLEVEL)` from a kernel top-level Makefile.
+fn makefile_version(makefile: &str) -> Option<(u32, u32, u32)> {
+    let field = |name: &str| -> Option<u32> {
+        makefile.lines().find_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            if key.trim() == name {
+                value.trim().parse().ok()
+            } else {
+                None
+            }
+        })
+    };
+    Some((field("VERSION")?, field("PATCHLEVEL")?, field("SUBLEVEL").unwrap_or(0)))
+}
+
 /// Format bytes as human-readable string
 pub fn format_bytes(bytes: u64) -> String {
     const KB: u64 = 1024;
@@ -181,3 +565,105 @@ pub fn format_bytes(bytes: u64) -> String {
         format!("{} B", bytes)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_makefile_version_header() {
+        let makefile = "# SPDX-License-Identifier: GPL-2.0\nVERSION = 6\nPATCHLEVEL = 13\nSUBLEVEL = 2\nEXTRAVERSION =\nNAME = Baby Opossum Posse\n";
+        assert_eq!(makefile_version(makefile), Some((6, 13, 2)));
+        assert_eq!(makefile_version("VERSION = 6\n"), None);
+    }
+
+    #[test]
+    fn checksum_found_in_signed_list() {
+        let hash = "a".repeat(64);
+        let sums = format!(
+            "-----BEGIN PGP SIGNED MESSAGE-----\nHash: SHA256\n\n{}  linux-6.19.2.tar.gz\n\
+             {}  linux-6.19.2.tar.xz\n-----BEGIN PGP SIGNATURE-----\n",
+            "b".repeat(64),
+            hash.to_uppercase()
+        );
+        assert_eq!(find_checksum(&sums, "linux-6.19.2.tar.xz"), Some(hash));
+        assert_eq!(find_checksum(&sums, "linux-6.19.tar.xz"), None);
+        assert_eq!(
+            checksums_url(&get_download_url("6.19.2")).as_deref(),
+            Some("https://cdn.kernel.org/pub/linux/kernel/v6.x/sha256sums.asc")
+        );
+    }
+
+    #[test]
+    fn download_url_handles_deep_and_four_part_versions() {
+        assert_eq!(
+            get_download_url("v6.1.123"),
+            "https://cdn.kernel.org/pub/linux/kernel/v6.x/linux-6.1.123.tar.xz"
+        );
+        assert_eq!(
+            get_download_url("v2.6.32.71"),
+            "https://cdn.kernel.org/pub/linux/kernel/v2.6/linux-2.6.32.71.tar.xz"
+        );
+        assert_eq!(
+            get_download_url("v6.14-rc3"),
+            "https://git.kernel.org/torvalds/t/linux-6.14-rc3.tar.gz"
+        );
+    }
+
+    #[test]
+    fn prune_keeps_newest_and_protected() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        for (i, version) in ["6.11.1", "6.12.1", "6.12.2"].iter().enumerate() {
+            let tree = dir.join(format!("linux-{}", version));
+            fs::create_dir(&tree).unwrap();
+            fs::write(tarball_path(dir, version), "").unwrap();
+            let record = File::create(record_path(dir, version)).unwrap();
+            let downloaded = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(i as u64 * 60);
+            record.set_modified(downloaded).unwrap();
+        }
+        fs::create_dir(dir.join("unrelated")).unwrap();
+
+        let newest: Vec<String> = cached_kernels(dir).into_iter().map(|k| k.version).collect();
+        assert_eq!(newest, vec!["6.12.2", "6.12.1", "6.11.1"]);
+
+        // The protected (just downloaded) version counts towards `keep`
+        let prune: Vec<String> = prune_candidates(dir, 2, "v6.11.1")
+            .into_iter()
+            .map(|k| k.version)
+            .collect();
+        assert_eq!(prune, vec!["6.12.1"]);
+        assert!(prune_candidates(dir, 0, "6.11.1").is_empty());
+
+        remove_cached_kernel(dir, "6.12.1").unwrap();
+        assert!(!dir.join("linux-6.12.1").exists());
+        assert!(!tarball_path(dir, "6.12.1").exists());
+        assert_eq!(cached_kernels(dir).len(), 2);
+
+        // Dropping only the tarball keeps the tree listed, without a size
+        remove_tarball(dir, "6.12.2").unwrap();
+        assert!(dir.join("linux-6.12.2").is_dir());
+        assert!(!has_download_record(dir, "6.12.2"));
+        let kernels = cached_kernels(dir);
+        assert_eq!(kernels.len(), 2);
+        assert!(kernels.iter().all(|k| (k.version == "6.12.2") == k.tarball_size.is_none()));
+    }
+
+    #[test]
+    fn cache_ignores_non_kernel_linux_dirs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        for name in ["linux-6.12.1", "linux-6.12.2", "linux-tkg", "linux-firmware"] {
+            fs::create_dir(dir.join(name)).unwrap();
+        }
+        fs::write(dir.join("linux-notes.tar.xz"), "").unwrap();
+
+        let mut cached: Vec<String> = cached_kernels(dir).into_iter().map(|k| k.version).collect();
+        cached.sort();
+        assert_eq!(cached, vec!["6.12.1", "6.12.2"]);
+        let prune: Vec<String> =
+            prune_candidates(dir, 1, "6.12.2").into_iter().map(|k| k.version).collect();
+        assert_eq!(prune, vec!["6.12.1"]);
+        assert!(dir.join("linux-tkg").is_dir());
+    }
+}
@@ -1,6 +1,7 @@
-use crate::core::http_client;
+use crate::core::{http_client, version};
 use regex::Regex;
 use scraper::{Html, Selector};
+use std::collections::HashSet;
 
 const KERNEL_TAGS_URL: &str =
     "https://git.kernel.org/pub/scm/linux/kernel/git/stable/linux.git/refs/tags";
@@ -11,6 +12,8 @@ const KERNEL_BASE_URL: &str =
 pub struct VersionInfo {
     pub version: String,
     pub date: Option<String>,
+    /// Tag annotation message, fetched lazily from the tag page
+    pub annotation: Option<String>,
 }
 
 pub enum FetchResult {
@@ -18,6 +21,12 @@ pub enum FetchResult {
     Error(String),
 }
 
+pub enum AnnotationResult {
+    /// Version and its annotation (`None` if the tag has no message)
+    Done(String, Option<String>),
+    Error(String, String),
+}
+
 pub enum ShortlogResult {
     Done(Vec<CommitInfo>),
     Error(String),
@@ -50,7 +59,8 @@ fn fetch_tags_inner() -> Result<Vec<VersionInfo>, String> {
     let row_selector = Selector::parse("tr").map_err(|e| format!("{:?}", e))?;
     let link_selector = Selector::parse("a").map_err(|e| format!("{:?}", e))?;
     let date_selector = Selector::parse("td:nth-child(3)").map_err(|e| format!("{:?}", e))?;
-    let version_re = Regex::new(r"^v\d+\.\d+(\.\d+)?$").unwrap();
+    // Releases and mainline -rcN; up to four components for old 2.6.x.y stables
+    let version_re = Regex::new(r"^v\d+\.\d+((\.\d+){0,2}|-rc\d+)$").unwrap();
 
     let mut versions: Vec<VersionInfo> = Vec::new();
 
@@ -66,13 +76,14 @@ fn fetch_tags_inner() -> Result<Vec<VersionInfo>, String> {
                 versions.push(VersionInfo {
                     version: text,
                     date,
+                    annotation: None,
                 });
             }
         }
     }
 
     // Sort by version number, newest first
-    versions.sort_by(|a, b| compare_versions(&b.version, &a.version));
+    versions.sort_by(|a, b| version::compare(&b.version, &a.version));
     versions.dedup_by(|a, b| a.version == b.version);
 
     Ok(versions)
@@ -142,28 +153,86 @@ fn fetch_shortlog_inner(from_version: &str, to_version: &str) -> Result<Vec<Comm
     Ok(commits)
 }
 
-/// Get the previous version in the same series (e.g., v6.13.1 -> v6.13)
-pub fn get_previous_version(version: &str, all_versions: &[VersionInfo]) -> Option<String> {
+/// Fetch the annotation message of a tag from its cgit tag page
+pub fn fetch_annotation(version: &str) -> AnnotationResult {
+    match fetch_annotation_inner(version) {
+        Ok(annotation) => AnnotationResult::Done(version.to_string(), annotation),
+        Err(e) => AnnotationResult::Error(version.to_string(), e),
+    }
+}
+
+fn fetch_annotation_inner(version: &str) -> Result<Option<String>, String> {
+    let url = format!("{}/tag/?h={}", KERNEL_BASE_URL, version);
+    let response = http_client::agent().get(&url).call().map_err(|e| e.to_string())?;
+    let body = response.into_string().map_err(|e| e.to_string())?;
+    let document = Html::parse_document(&body);
+
+    // cgit tag page: <div class='commit-subject'>...</div><div class='commit-msg'>...</div>
+    let subject_selector = Selector::parse("div.commit-subject").map_err(|e| format!("{:?}", e))?;
+    let msg_selector = Selector::parse("div.commit-msg").map_err(|e| format!("{:?}", e))?;
+
+    let mut parts: Vec<String> = Vec::new();
+    for selector in [&subject_selector, &msg_selector] {
+        if let Some(el) = document.select(selector).next() {
+            let text = el.text().collect::<String>();
+            // Drop the detached PGP signature that stable tags carry
+            let text = text
+                .split("-----BEGIN PGP SIGNATURE-----")
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+            if !text.is_empty() {
+                parts.push(text);
+            }
+        }
+    }
+
+    if parts.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(parts.join("\n\n")))
+    }
+}
+
+/// Newest version of each major.minor series, from a list sorted newest first.
+/// Release candidates only count with `include_rc`.
+pub fn latest_per_series(versions: &[VersionInfo], include_rc: bool) -> HashSet<String> {
+    let mut seen_series = HashSet::new();
+    versions
+        .iter()
+        .filter(|v| include_rc || !version::is_rc(&v.version))
+        .filter(|v| seen_series.insert(version::series(&v.version)))
+        .map(|v| v.version.clone())
+        .collect()
+}
+
+/// Get the previous version in the same series (e.g., v6.13.1 -> v6.13),
+/// skipping release candidates unless `include_rc`
+pub fn get_previous_version(
+    version: &str,
+    all_versions: &[VersionInfo],
+    include_rc: bool,
+) -> Option<String> {
     let idx = all_versions.iter().position(|v| v.version == version)?;
     
     // Get major.minor of current version
-    let current_parts: Vec<&str> = version.trim_start_matches('v').split('.').collect();
+    let current_parts = version::numbers(version);
     if current_parts.len() < 2 {
         return None;
     }
-    let current_major_minor = format!("{}.{}", current_parts[0], current_parts[1]);
-    
+    let current_major_minor = version::series(version);
+
     // Look for previous version in same series
     for v in all_versions.iter().skip(idx + 1) {
-        let parts: Vec<&str> = v.version.trim_start_matches('v').split('.').collect();
-        if parts.len() >= 2 {
-            let major_minor = format!("{}.{}", parts[0], parts[1]);
-            if major_minor == current_major_minor {
-                return Some(v.version.clone());
-            }
+        if (include_rc || !version::is_rc(&v.version))
+            && version::numbers(&v.version).len() >= 2
+            && version::series(&v.version) == current_major_minor
+        {
+            return Some(v.version.clone());
         }
     }
-    
+
     // If no previous in same series, return the base version (e.g., v6.13)
     if current_parts.len() > 2 {
         let base = format!("v{}.{}", current_parts[0], current_parts[1]);
@@ -175,14 +244,46 @@ pub fn get_previous_version(version: &str, all_versions: &[VersionInfo]) -> Opti
     None
 }
 
-fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
-    let parse = |s: &str| -> Vec<u32> {
-        s.trim_start_matches('v')
-            .split('.')
-            .filter_map(|p| p.parse().ok())
-            .collect()
-    };
-    let va = parse(a);
-    let vb = parse(b);
-    va.cmp(&vb)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(version: &str) -> VersionInfo {
+        VersionInfo {
+            version: version.to_string(),
+            date: None,
+            annotation: None,
+        }
+    }
+
+    #[test]
+    fn latest_per_series_picks_newest_point_release() {
+        let versions: Vec<VersionInfo> =
+            ["v6.3-rc2", "v6.2", "v6.2-rc7", "v6.1.123", "v6.1.10", "v6.1", "v5.15.178"]
+                .into_iter()
+                .map(info)
+                .collect();
+        let latest = latest_per_series(&versions, false);
+        assert_eq!(latest.len(), 3);
+        assert!(latest.contains("v6.2"));
+        assert!(latest.contains("v6.1.123"));
+        assert!(latest.contains("v5.15.178"));
+        let latest = latest_per_series(&versions, true);
+        assert_eq!(latest.len(), 4);
+        assert!(latest.contains("v6.3-rc2"));
+    }
+
+    #[test]
+    fn previous_version_stays_in_series() {
+        let versions: Vec<VersionInfo> =
+            ["v6.2", "v6.2-rc7", "v6.1.123", "v6.1.10", "v6.1.9", "v6.1"]
+                .into_iter()
+                .map(info)
+                .collect();
+        let previous = |v| get_previous_version(v, &versions, false);
+        assert_eq!(previous("v6.1.123").as_deref(), Some("v6.1.10"));
+        assert_eq!(previous("v6.1.9").as_deref(), Some("v6.1"));
+        assert_eq!(previous("v6.2"), None);
+        assert_eq!(get_previous_version("v6.2", &versions, true).as_deref(), Some("v6.2-rc7"));
+    }
 }
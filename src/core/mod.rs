@@ -1,9 +1,19 @@
+pub mod app_log;
+pub mod bug_report;
 pub mod build_manager;
+pub mod catalog_index;
+pub mod config_export;
 pub mod config_manager;
 pub mod http_client;
 pub mod kernel_downloader;
 pub mod kernel_fetcher;
+pub mod patch_bundle;
 pub mod patch_manager;
 pub mod patch_registry;
+pub mod presets;
+pub mod pty;
 pub mod repo_manager;
+pub mod system;
+pub mod task;
+pub mod version;
 pub mod work_dir;
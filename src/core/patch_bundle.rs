@@ -0,0 +1,231 @@
+//! Portable patch sets: the patch files of one userpatches dir plus their
+//! registry entries in a single `.tar.gz`, so a setup can be copied to
+//! another machine.
+//!
+//! Layout: `manifest.json` ([`Manifest`]) followed by `patches/<name>` for
+//! every file, named as on disk (so `.disabled` patches stay disabled).
+
+use crate::core::patch_manager::{check_file_name, PatchEntry};
+use crate::core::patch_registry::PatchMeta;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+const MANIFEST: &str = "manifest.json";
+const PATCHES_DIR: &str = "patches/";
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    kernel_series: String,
+    /// On-disk file name -> SHA-256 of its contents at export time
+    files: HashMap<String, String>,
+    /// Registry entries of the exported files
+    registry: Vec<PatchMeta>,
+}
+
+/// What [`import_bundle`] wrote
+pub struct ImportedBundle {
+    /// Series the bundle was exported from
+    pub kernel_series: String,
+    pub files: Vec<String>,
+    /// Registry entries to merge, still carrying the exported series
+    pub registry: Vec<PatchMeta>,
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// Write `patches` and the registry entries that describe them to `dest`.
+/// Returns the number of files exported.
+pub fn export_bundle(
+    patches: &[PatchEntry],
+    registry: &[&PatchMeta],
+    kernel_series: &str,
+    dest: &Path,
+) -> Result<usize, String> {
+    if patches.is_empty() {
+        return Err("No patches to export".to_string());
+    }
+
+    let mut contents = Vec::new();
+    for patch in patches {
+        let data = fs::read(&patch.path)
+            .map_err(|e| format!("Failed to read {}: {}", patch.path.display(), e))?;
+        contents.push((patch.name.clone(), data));
+    }
+
+    let on_disk = |filename: &str| {
+        patches
+            .iter()
+            .any(|p| p.name == filename || p.name == format!("{}.disabled", filename))
+    };
+    let manifest = Manifest {
+        kernel_series: kernel_series.to_string(),
+        files: contents
+            .iter()
+            .map(|(name, data)| (name.clone(), sha256_hex(data)))
+            .collect(),
+        registry: registry
+            .iter()
+            .filter(|m| on_disk(&m.filename))
+            .map(|m| (*m).clone())
+            .collect(),
+    };
+    let manifest = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+
+    let file =
+        File::create(dest).map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    let mut append = |name: &str, data: &[u8]| {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, name, data)
+            .map_err(|e| format!("Failed to write {}: {}", name, e))
+    };
+    append(MANIFEST, &manifest)?;
+    for (name, data) in &contents {
+        append(&format!("{}{}", PATCHES_DIR, name), data)?;
+    }
+    builder
+        .into_inner()
+        .and_then(|gz| gz.finish())
+        .map_err(|e| format!("Failed to finish {}: {}", dest.display(), e))?;
+    Ok(contents.len())
+}
+
+/// Unpack a bundle into `patch_dir` after checking every file against the
+/// manifest's SHA-256. Nothing is written if any check fails. Existing files
+/// with the same name are replaced.
+pub fn import_bundle(archive: &Path, patch_dir: &Path) -> Result<ImportedBundle, String> {
+    let file =
+        File::open(archive).map_err(|e| format!("Failed to open {}: {}", archive.display(), e))?;
+    let mut tar = tar::Archive::new(GzDecoder::new(file));
+
+    let mut manifest: Option<Manifest> = None;
+    let mut files: Vec<(String, Vec<u8>)> = Vec::new();
+    for entry in tar.entries().map_err(|e| format!("Not a patch bundle: {}", e))? {
+        let mut entry = entry.map_err(|e| format!("Corrupt bundle: {}", e))?;
+        let path = entry
+            .path()
+            .map_err(|e| e.to_string())?
+            .to_string_lossy()
+            .to_string();
+        let mut data = Vec::new();
+        entry
+            .read_to_end(&mut data)
+            .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        if path == MANIFEST {
+            manifest = Some(
+                serde_json::from_slice(&data).map_err(|e| format!("Invalid manifest: {}", e))?,
+            );
+        } else if let Some(name) = path.strip_prefix(PATCHES_DIR) {
+            if check_file_name(name).is_err() {
+                return Err(format!("Unexpected file in bundle: {}", path));
+            }
+            files.push((name.to_string(), data));
+        }
+    }
+    let manifest = manifest.ok_or("Not a patch bundle: manifest.json is missing")?;
+
+    for (name, data) in &files {
+        match manifest.files.get(name) {
+            Some(expected) if *expected == sha256_hex(data) => {}
+            Some(_) => return Err(format!("SHA-256 mismatch for {}", name)),
+            None => return Err(format!("{} is not listed in the manifest", name)),
+        }
+    }
+    if let Some(missing) = manifest.files.keys().find(|n| !files.iter().any(|(f, _)| f == *n)) {
+        return Err(format!("{} is listed but missing from the bundle", missing));
+    }
+
+    fs::create_dir_all(patch_dir)
+        .map_err(|e| format!("Failed to create {}: {}", patch_dir.display(), e))?;
+    for (name, data) in &files {
+        fs::write(patch_dir.join(name), data)
+            .map_err(|e| format!("Failed to write {}: {}", name, e))?;
+    }
+
+    Ok(ImportedBundle {
+        kernel_series: manifest.kernel_series,
+        files: files.into_iter().map(|(name, _)| name).collect(),
+        registry: manifest.registry,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::patch_manager::list_patches;
+    use crate::core::patch_registry::UpdateStatus;
+    use chrono::Utc;
+
+    #[test]
+    fn bundle_round_trips_files_and_registry() {
+        let src = tempfile::tempdir().unwrap();
+        fs::write(src.path().join("a.patch"), "diff a\n").unwrap();
+        fs::write(src.path().join("b.mypatch.disabled"), "diff b\n").unwrap();
+        let meta = PatchMeta {
+            filename: "a.patch".to_string(),
+            kernel_series: "6.13".to_string(),
+            source_url: Some("https://example.com/a.patch".to_string()),
+            catalog_id: None,
+            sha256: sha256_hex(b"diff a\n"),
+            downloaded_at: Utc::now(),
+            etag: None,
+            last_modified: None,
+            update_status: UpdateStatus::UpToDate,
+        };
+
+        let bundle = src.path().join("set.tar.gz");
+        let patches = list_patches(src.path());
+        assert_eq!(export_bundle(&patches, &[&meta], "6.13", &bundle).unwrap(), 2);
+
+        let dest = tempfile::tempdir().unwrap();
+        let imported = import_bundle(&bundle, dest.path()).unwrap();
+        assert_eq!(imported.kernel_series, "6.13");
+        assert_eq!(imported.registry.len(), 1);
+        let names: Vec<_> = list_patches(dest.path()).into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["a.patch", "b.mypatch.disabled"]);
+        assert_eq!(fs::read_to_string(dest.path().join("a.patch")).unwrap(), "diff a\n");
+    }
+
+    #[test]
+    fn import_rejects_tampered_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = Manifest {
+            kernel_series: "6.13".to_string(),
+            files: HashMap::from([("a.patch".to_string(), sha256_hex(b"original"))]),
+            registry: Vec::new(),
+        };
+        let bundle = dir.path().join("set.tar.gz");
+        let mut builder = tar::Builder::new(GzEncoder::new(
+            File::create(&bundle).unwrap(),
+            Compression::default(),
+        ));
+        for (name, data) in [
+            (MANIFEST, serde_json::to_vec(&manifest).unwrap()),
+            ("patches/a.patch", b"tampered".to_vec()),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_cksum();
+            builder.append_data(&mut header, name, &data[..]).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let dest = dir.path().join("out");
+        let err = import_bundle(&bundle, &dest).err().unwrap();
+        assert!(err.contains("SHA-256 mismatch"), "{}", err);
+        assert!(!dest.exists());
+    }
+}
@@ -1,4 +1,5 @@
 use crate::core::http_client;
+use crate::core::task::CancelToken;
 use flate2::read::GzDecoder;
 use sha2::{Digest, Sha256};
 use std::fs;
@@ -27,9 +28,111 @@ pub enum DownloadResult {
     Error(String),
 }
 
+/// One patch of a batch download
+#[derive(Clone, Debug)]
+pub struct BatchItem {
+    pub name: String,
+    pub url: String,
+    pub dest_path: PathBuf,
+    /// Pinned SHA-256 the download must match
+    pub sha256: Option<String>,
+}
+
+/// Progress of [`download_batch`]
+pub enum BatchProgress {
+    /// Index into the batch and the result for that item
+    Item(usize, DownloadResult),
+    /// All items attempted, or stopped early after a failure or cancel
+    Finished { stopped_early: bool },
+}
+
+/// Download `items` in order. With `stop_on_failure` the remaining items are
+/// skipped after the first error; once `cancel` is set they are skipped
+/// after the current one.
+pub fn download_batch(
+    items: &[BatchItem],
+    stop_on_failure: bool,
+    cancel: &CancelToken,
+    tx: &std::sync::mpsc::Sender<BatchProgress>,
+) {
+    for (i, item) in items.iter().enumerate() {
+        if cancel.is_cancelled() {
+            log::warn!("Batch download cancelled before {}", item.name);
+            let _ = tx.send(BatchProgress::Finished { stopped_early: true });
+            return;
+        }
+        let result = download_patch(&item.url, &item.dest_path, item.sha256.as_deref());
+        let failed = matches!(result, DownloadResult::Error(_));
+        let _ = tx.send(BatchProgress::Item(i, result));
+        if failed && stop_on_failure {
+            if i + 1 < items.len() {
+                log::warn!("Batch download stopped after {} failed", item.name);
+            }
+            let _ = tx.send(BatchProgress::Finished {
+                stopped_early: i + 1 < items.len(),
+            });
+            return;
+        }
+    }
+    let _ = tx.send(BatchProgress::Finished {
+        stopped_early: false,
+    });
+}
+
+/// Userpatches directory for `kernel_series`. Tries the known layouts, then
+/// any existing `linux*-tkg-userpatches` dir; falls back to the top level.
 pub fn get_patch_dir(linux_tkg_path: &Path, kernel_series: &str) -> PathBuf {
-    // e.g. linux6.13-tkg-userpatches inside the linux-tkg clone
-    linux_tkg_path.join(format!("linux{}-tkg-userpatches", kernel_series))
+    detect_patch_dir(linux_tkg_path, kernel_series)
+        .unwrap_or_else(|| linux_tkg_path.join(userpatches_name(kernel_series)))
+}
+
+fn userpatches_name(kernel_series: &str) -> String {
+    // e.g. linux6.13-tkg-userpatches
+    format!("linux{}-tkg-userpatches", kernel_series)
+}
+
+/// Locate an existing userpatches directory in the clone. Returns `None`
+/// when no layout can be recognised.
+pub fn detect_patch_dir(linux_tkg_path: &Path, kernel_series: &str) -> Option<PathBuf> {
+    let name = userpatches_name(kernel_series);
+    let candidates = [
+        linux_tkg_path.join(&name),
+        linux_tkg_path.join("submodules").join("linux-tkg").join(&name),
+    ];
+    if let Some(dir) = candidates.iter().find(|d| d.is_dir()) {
+        return Some(dir.clone());
+    }
+
+    // Another series' userpatches dir tells us where this one belongs
+    let found = find_userpatches_dirs(linux_tkg_path, 2);
+    found
+        .iter()
+        .find(|d| d.file_name().is_some_and(|n| n == name.as_str()))
+        .or_else(|| found.first())
+        .and_then(|d| d.parent())
+        .map(|parent| parent.join(&name))
+}
+
+fn find_userpatches_dirs(dir: &Path, depth: usize) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return found;
+    };
+    let mut subdirs: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    subdirs.sort();
+    for path in subdirs {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        if name.starts_with("linux") && name.ends_with("-tkg-userpatches") {
+            found.push(path);
+        } else if depth > 0 && !name.starts_with('.') {
+            found.extend(find_userpatches_dirs(&path, depth - 1));
+        }
+    }
+    found
 }
 
 pub fn list_patches(patch_dir: &Path) -> Vec<PatchEntry> {
@@ -61,28 +164,25 @@ pub fn list_patches(patch_dir: &Path) -> Vec<PatchEntry> {
 }
 
 pub fn toggle_patch(patch: &mut PatchEntry) -> Result<(), String> {
-    let new_path = if patch.enabled {
-        // Disable: add .disabled suffix
-        patch.path.with_extension(
-            patch
-                .path
-                .extension()
-                .map(|e| format!("{}.disabled", e.to_string_lossy()))
-                .unwrap_or_else(|| "disabled".to_string()),
-        )
+    let name = patch
+        .path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Not a file: {}", patch.path.display()))?;
+    // Work on the literal suffix: extensions mangle names like 0001-a.b.patch
+    let new_name = if patch.enabled {
+        format!("{}.disabled", name)
     } else {
-        // Enable: remove .disabled suffix
-        let name = patch.path.to_string_lossy();
-        PathBuf::from(name.trim_end_matches(".disabled"))
+        name.strip_suffix(".disabled")
+            .ok_or_else(|| format!("{} has no .disabled suffix", name))?
+            .to_string()
     };
+    let new_path = patch.path.with_file_name(&new_name);
 
     fs::rename(&patch.path, &new_path).map_err(|e| e.to_string())?;
-    patch.path = new_path.clone();
-    patch.name = new_path
-        .file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_default();
-    patch.enabled = !patch.enabled;
+    patch.path = new_path;
+    patch.enabled = !new_name.ends_with(".disabled");
+    patch.name = new_name;
     Ok(())
 }
 
@@ -90,63 +190,83 @@ pub fn delete_patch(patch: &PatchEntry) -> Result<(), String> {
     fs::remove_file(&patch.path).map_err(|e| e.to_string())
 }
 
-pub fn download_patch(url: &str, dest_path: &Path) -> DownloadResult {
-    match download_patch_inner(url, dest_path) {
-        Ok(info) => DownloadResult::Done(info),
-        Err(e) => DownloadResult::Error(e),
+/// Check that `name` is a plain file name that stays inside the folder it
+/// is joined onto. Catalog and bundle names come from untrusted sources.
+pub fn check_file_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.contains('/') || name.contains("..") || name.starts_with('.') {
+        Err(format!("Refusing unsafe file name \"{}\"", name))
+    } else {
+        Ok(())
+    }
+}
+
+/// Download a patch to `dest_path`, decompressing `.xz`/`.gz`. With
+/// `expected_sha256` the decompressed content must match it, or nothing is
+/// written.
+pub fn download_patch(
+    url: &str,
+    dest_path: &Path,
+    expected_sha256: Option<&str>,
+) -> DownloadResult {
+    match download_patch_inner(url, dest_path, expected_sha256) {
+        Ok(info) => {
+            log::info!("Downloaded patch {} to {}", url, info.path.display());
+            DownloadResult::Done(info)
+        }
+        Err(e) => {
+            log::error!("Patch download from {} failed: {}", url, e);
+            DownloadResult::Error(e)
+        }
     }
 }
 
-fn download_patch_inner(url: &str, dest_path: &Path) -> Result<DownloadInfo, String> {
+fn download_patch_inner(
+    url: &str,
+    dest_path: &Path,
+    expected_sha256: Option<&str>,
+) -> Result<DownloadInfo, String> {
+    let name = dest_path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    check_file_name(&name)?;
+    if dest_path.components().any(|c| c == std::path::Component::ParentDir) {
+        return Err(format!("Refusing unsafe path {}", dest_path.display()));
+    }
     // Ensure parent directory exists
     if let Some(parent) = dest_path.parent() {
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
 
-    let response = http_client::agent().get(url).call().map_err(|e| e.to_string())?;
+    let response = http_client::agent()
+        .get(url)
+        .call()
+        .map_err(http_client::describe_error)?;
     
     // Capture HTTP headers for update tracking
     let etag = response.header("ETag").map(|s| s.to_string());
     let last_modified = response.header("Last-Modified").map(|s| s.to_string());
     
-    let mut reader = response.into_reader();
+    let mut compressed = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut compressed)
+        .map_err(|e| e.to_string())?;
 
     // Check if file needs decompression based on extension
     let dest_str = dest_path.to_string_lossy();
-    
-    let (final_path, content) = if dest_str.ends_with(".xz") {
-        // Decompress XZ and save without .xz extension
-        let final_path = PathBuf::from(dest_str.trim_end_matches(".xz"));
-        let mut compressed_data = Vec::new();
-        reader.read_to_end(&mut compressed_data).map_err(|e| e.to_string())?;
-        
-        let mut decoder = XzDecoder::new(&compressed_data[..]);
-        let mut decompressed = Vec::new();
-        decoder.read_to_end(&mut decompressed).map_err(|e| format!("XZ decompression failed: {}", e))?;
-        
-        (final_path, decompressed)
-    } else if dest_str.ends_with(".gz") {
-        // Decompress GZ and save without .gz extension
-        let final_path = PathBuf::from(dest_str.trim_end_matches(".gz"));
-        let mut compressed_data = Vec::new();
-        reader.read_to_end(&mut compressed_data).map_err(|e| e.to_string())?;
-        
-        let mut decoder = GzDecoder::new(&compressed_data[..]);
-        let mut decompressed = Vec::new();
-        decoder.read_to_end(&mut decompressed).map_err(|e| format!("GZ decompression failed: {}", e))?;
-        
-        (final_path, decompressed)
-    } else {
-        // No compression, read directly
-        let mut content = Vec::new();
-        reader.read_to_end(&mut content).map_err(|e| e.to_string())?;
-        (dest_path.to_path_buf(), content)
-    };
-    
+    let final_path = PathBuf::from(
+        dest_str
+            .strip_suffix(".xz")
+            .or_else(|| dest_str.strip_suffix(".gz"))
+            .unwrap_or(&dest_str),
+    );
+    let content = decompress_by_extension(&dest_str, compressed)?;
+
     // Compute SHA-256 hash
     let mut hasher = Sha256::new();
     hasher.update(&content);
     let sha256 = format!("{:x}", hasher.finalize());
+    if let Some(expected) = expected_sha256 {
+        check_sha256(expected, &sha256)?;
+    }
     
     // Write file
     fs::write(&final_path, &content).map_err(|e| e.to_string())?;
@@ -159,9 +279,286 @@ fn download_patch_inner(url: &str, dest_path: &Path) -> Result<DownloadInfo, Str
     })
 }
 
+/// Compare a download's digest with the catalog's pinned one
+fn check_sha256(expected: &str, actual: &str) -> Result<(), String> {
+    if expected.trim().eq_ignore_ascii_case(actual) {
+        Ok(())
+    } else {
+        Err(format!(
+            "SHA-256 mismatch: the catalog pins {} but the download is {}. Not saved: the \
+             file changed upstream or was tampered with.",
+            expected.trim(),
+            actual
+        ))
+    }
+}
+
+/// Decompress `data` according to the `.xz`/`.gz` extension of `name`;
+/// anything else is returned unchanged.
+fn decompress_by_extension(name: &str, data: Vec<u8>) -> Result<Vec<u8>, String> {
+    let mut decompressed = Vec::new();
+    if name.ends_with(".xz") {
+        XzDecoder::new(&data[..])
+            .read_to_end(&mut decompressed)
+            .map_err(|e| format!("XZ decompression failed: {}", e))?;
+    } else if name.ends_with(".gz") {
+        GzDecoder::new(&data[..])
+            .read_to_end(&mut decompressed)
+            .map_err(|e| format!("GZ decompression failed: {}", e))?;
+    } else {
+        return Ok(data);
+    }
+    Ok(decompressed)
+}
+
+/// Download a patch into memory (decompressing if needed) without touching
+/// the on-disk copy.
+pub fn fetch_patch_text(url: &str) -> Result<String, String> {
+    let response = http_client::agent()
+        .get(url)
+        .call()
+        .map_err(http_client::describe_error)?;
+    let mut data = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut data)
+        .map_err(|e| e.to_string())?;
+    let name = url.split(['?', '#']).next().unwrap_or(url);
+    let content = decompress_by_extension(name, data)?;
+    Ok(String::from_utf8_lossy(&content).into_owned())
+}
+
+/// One line of a line-based diff
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiffLine {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+/// Above this many LCS cells the changed region is shown as a plain
+/// remove/add block instead of a minimal diff.
+const MAX_DIFF_CELLS: usize = 16_000_000;
+
+/// Line-based diff from `old` to `new` (longest common subsequence after
+/// trimming the shared prefix and suffix).
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+
+    let mut out: Vec<DiffLine> = a[..prefix]
+        .iter()
+        .map(|l| DiffLine::Same(l.to_string()))
+        .collect();
+
+    let (n, m) = (a_mid.len(), b_mid.len());
+    if n * m > MAX_DIFF_CELLS {
+        out.extend(a_mid.iter().map(|l| DiffLine::Removed(l.to_string())));
+        out.extend(b_mid.iter().map(|l| DiffLine::Added(l.to_string())));
+    } else {
+        // lcs[i][j] = LCS length of a_mid[i..] and b_mid[j..]
+        let width = m + 1;
+        let mut lcs = vec![0u32; (n + 1) * width];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i * width + j] = if a_mid[i] == b_mid[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && a_mid[i] == b_mid[j] {
+                out.push(DiffLine::Same(a_mid[i].to_string()));
+                i += 1;
+                j += 1;
+            } else if j < m && (i == n || lcs[i * width + j + 1] >= lcs[(i + 1) * width + j]) {
+                out.push(DiffLine::Added(b_mid[j].to_string()));
+                j += 1;
+            } else {
+                out.push(DiffLine::Removed(a_mid[i].to_string()));
+                i += 1;
+            }
+        }
+    }
+
+    out.extend(
+        a[a.len() - suffix..]
+            .iter()
+            .map(|l| DiffLine::Same(l.to_string())),
+    );
+    out
+}
+
+/// Whether `text` looks like a downloadable http(s) URL
+pub fn is_patch_url(text: &str) -> bool {
+    let text = text.trim();
+    let rest = text
+        .strip_prefix("https://")
+        .or_else(|| text.strip_prefix("http://"))
+        .unwrap_or_default();
+    !rest.is_empty() && !text.contains(char::is_whitespace)
+}
+
 pub fn extract_filename_from_url(url: &str) -> String {
     url.rsplit('/')
         .next()
         .unwrap_or("patch.patch")
         .to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_lines_marks_added_and_removed() {
+        let old = "a\nb\nc\nd\n";
+        let new = "a\nc\nx\nd\n";
+        assert_eq!(
+            diff_lines(old, new),
+            vec![
+                DiffLine::Same("a".to_string()),
+                DiffLine::Removed("b".to_string()),
+                DiffLine::Same("c".to_string()),
+                DiffLine::Added("x".to_string()),
+                DiffLine::Same("d".to_string()),
+            ]
+        );
+        assert!(diff_lines(old, old)
+            .iter()
+            .all(|l| matches!(l, DiffLine::Same(_))));
+    }
+
+    #[test]
+    fn check_file_name_rejects_path_escapes() {
+        assert!(check_file_name("0001-bore.patch").is_ok());
+        for name in ["", "../evil.patch", "a/b.patch", "..", ".hidden.patch", "x..patch"] {
+            assert!(check_file_name(name).is_err(), "{:?} accepted", name);
+        }
+    }
+
+    #[test]
+    fn is_patch_url_accepts_only_http_urls() {
+        assert!(is_patch_url(" https://example.com/a.patch\n"));
+        assert!(is_patch_url("http://example.com/a.patch"));
+        assert!(!is_patch_url("https://"));
+        assert!(!is_patch_url("ftp://example.com/a.patch"));
+        assert!(!is_patch_url("see https://example.com/a.patch"));
+    }
+
+    #[test]
+    fn detects_submodule_userpatches_layout() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_path_buf();
+        let nested = root.join("submodules").join("linux-tkg");
+        fs::create_dir_all(nested.join("linux6.6-tkg-userpatches")).unwrap();
+
+        // Exact series found via the known submodule layout
+        assert_eq!(
+            get_patch_dir(&root, "6.6"),
+            nested.join("linux6.6-tkg-userpatches")
+        );
+        // Missing series goes next to the existing one
+        assert_eq!(
+            get_patch_dir(&root, "6.12"),
+            nested.join("linux6.12-tkg-userpatches")
+        );
+        // Nothing recognisable: top level
+        fs::remove_dir_all(root.join("submodules")).unwrap();
+        assert_eq!(detect_patch_dir(&root, "6.12"), None);
+        assert_eq!(get_patch_dir(&root, "6.12"), root.join("linux6.12-tkg-userpatches"));
+    }
+
+    fn write_patch(dir: &Path, name: &str) -> PatchEntry {
+        fs::write(dir.join(name), "--- a\n+++ b\n").unwrap();
+        list_patches(dir)
+            .into_iter()
+            .find(|p| p.name == name)
+            .expect("patch listed")
+    }
+
+    #[test]
+    fn toggle_round_trips_dotted_names() {
+        let tmp = tempfile::tempdir().unwrap();
+        for name in ["0001-foo.bar.patch", "fix.v2.mypatch", "plain.patch"] {
+            let mut patch = write_patch(tmp.path(), name);
+            assert!(patch.enabled);
+
+            toggle_patch(&mut patch).unwrap();
+            assert!(!patch.enabled);
+            assert_eq!(patch.name, format!("{}.disabled", name));
+            assert!(tmp.path().join(&patch.name).is_file());
+            assert!(!tmp.path().join(name).exists());
+
+            toggle_patch(&mut patch).unwrap();
+            assert!(patch.enabled);
+            assert_eq!(patch.name, name);
+            assert_eq!(patch.path, tmp.path().join(name));
+        }
+    }
+
+    #[test]
+    fn enable_strips_only_one_disabled_suffix() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join("fix.v2.mypatch.disabled"), "").unwrap();
+        let mut patch = list_patches(tmp.path()).remove(0);
+        toggle_patch(&mut patch).unwrap();
+        assert_eq!(patch.name, "fix.v2.mypatch");
+        assert!(tmp.path().join("fix.v2.mypatch").is_file());
+
+        let mut odd = PatchEntry {
+            name: "x.patch.disabled.disabled".to_string(),
+            enabled: false,
+            path: tmp.path().join("x.patch.disabled.disabled"),
+        };
+        fs::write(&odd.path, "").unwrap();
+        toggle_patch(&mut odd).unwrap();
+        assert_eq!(odd.name, "x.patch.disabled");
+        assert!(!odd.enabled);
+        assert!(odd.path.is_file());
+    }
+
+    #[test]
+    fn list_patches_reports_enabled_state() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_patch(tmp.path(), "a.patch");
+        fs::write(tmp.path().join("b.mypatch.disabled"), "").unwrap();
+        fs::write(tmp.path().join("notes.txt"), "").unwrap();
+
+        let listed: Vec<(String, bool)> = list_patches(tmp.path())
+            .into_iter()
+            .map(|p| (p.name, p.enabled))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                ("a.patch".to_string(), true),
+                ("b.mypatch.disabled".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn delete_removes_the_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let patch = write_patch(tmp.path(), "0001-foo.bar.patch");
+        delete_patch(&patch).unwrap();
+        assert!(!patch.path.exists());
+        assert!(list_patches(tmp.path()).is_empty());
+        // Deleting again reports the missing file
+        assert!(delete_patch(&patch).is_err());
+    }
+}
@@ -12,6 +12,9 @@ pub enum UpdateStatus {
     Unknown,
     UpToDate,
     Stale,
+    /// The server sends neither ETag nor Last-Modified, so there is nothing
+    /// to compare against
+    NoValidators,
     CheckError(String),
 }
 
@@ -90,12 +93,42 @@ impl PatchRegistry {
             meta.update_status = status;
         }
     }
+
+    /// Reconcile entries for `series` with the patch files found on disk.
+    ///
+    /// A file counts as present if it is listed under its registered name or
+    /// with a `.disabled` suffix. Entries without a matching file are dropped,
+    /// except those with a source URL, which are kept and returned so the UI
+    /// can offer a re-download.
+    pub fn reconcile(&mut self, series: &str, on_disk: &[String]) -> Vec<PatchMeta> {
+        let is_present = |filename: &str| {
+            on_disk
+                .iter()
+                .any(|name| name == filename || *name == format!("{}.disabled", filename))
+        };
+
+        let mut missing = Vec::new();
+        self.patches.retain(|_, meta| {
+            if meta.kernel_series != series || is_present(&meta.filename) {
+                return true;
+            }
+            if meta.source_url.is_some() {
+                missing.push(meta.clone());
+                true
+            } else {
+                false
+            }
+        });
+        missing.sort_by(|a, b| a.filename.cmp(&b.filename));
+        missing
+    }
 }
 
 /// Result of an update check
 pub enum UpdateCheckResult {
     UpToDate { key: String },
     Stale { key: String },
+    NoValidators { key: String },
     Error { key: String, reason: String },
     NoUrl { key: String },
 }
@@ -115,29 +148,16 @@ pub fn check_update(meta: PatchMeta, tx: Sender<UpdateCheckResult>) {
 
         match result {
             Ok(response) => {
-                let new_etag = response.header("ETag").map(|s| s.to_string());
-                let new_last_modified = response.header("Last-Modified").map(|s| s.to_string());
-
-                // Check if headers changed
-                let etag_changed = match (&meta.etag, &new_etag) {
-                    (Some(old), Some(new)) => old != new,
-                    (None, Some(_)) => true,
-                    _ => false,
-                };
-
-                let modified_changed = match (&meta.last_modified, &new_last_modified) {
-                    (Some(old), Some(new)) => old != new,
-                    (None, Some(_)) => true,
-                    _ => false,
-                };
-
-                if etag_changed || modified_changed {
-                    let _ = tx.send(UpdateCheckResult::Stale {
-                        key,
-                    });
-                } else {
-                    let _ = tx.send(UpdateCheckResult::UpToDate { key });
-                }
+                let status = compare_validators(
+                    &meta,
+                    response.header("ETag"),
+                    response.header("Last-Modified"),
+                );
+                let _ = tx.send(match status {
+                    UpdateStatus::Stale => UpdateCheckResult::Stale { key },
+                    UpdateStatus::NoValidators => UpdateCheckResult::NoValidators { key },
+                    _ => UpdateCheckResult::UpToDate { key },
+                });
             }
             Err(e) => {
                 let _ = tx.send(UpdateCheckResult::Error {
@@ -148,3 +168,83 @@ pub fn check_update(meta: PatchMeta, tx: Sender<UpdateCheckResult>) {
         }
     });
 }
+
+/// Compare the stored validators with the ones the server sent now. Without
+/// either header there is nothing to compare, which is not the same as
+/// "unchanged".
+fn compare_validators(
+    meta: &PatchMeta,
+    new_etag: Option<&str>,
+    new_last_modified: Option<&str>,
+) -> UpdateStatus {
+    if new_etag.is_none() && new_last_modified.is_none() {
+        return UpdateStatus::NoValidators;
+    }
+
+    let changed = |old: &Option<String>, new: Option<&str>| match (old, new) {
+        (Some(old), Some(new)) => old != new,
+        (None, Some(_)) => true,
+        _ => false,
+    };
+
+    if changed(&meta.etag, new_etag) || changed(&meta.last_modified, new_last_modified) {
+        UpdateStatus::Stale
+    } else {
+        UpdateStatus::UpToDate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(series: &str, filename: &str, url: Option<&str>) -> PatchMeta {
+        PatchMeta {
+            filename: filename.to_string(),
+            kernel_series: series.to_string(),
+            source_url: url.map(|u| u.to_string()),
+            catalog_id: None,
+            sha256: String::new(),
+            downloaded_at: Utc::now(),
+            etag: None,
+            last_modified: None,
+            update_status: UpdateStatus::Unknown,
+        }
+    }
+
+    #[test]
+    fn reconcile_drops_or_reports_missing_files() {
+        let mut registry = PatchRegistry::default();
+        registry.record_download(meta("6.13", "kept.patch", None));
+        registry.record_download(meta("6.13", "disabled.patch", None));
+        registry.record_download(meta("6.13", "gone-local.patch", None));
+        registry.record_download(meta("6.13", "gone-remote.patch", Some("https://x/p.patch")));
+        registry.record_download(meta("6.12", "other-series.patch", None));
+
+        let on_disk = vec!["kept.patch".to_string(), "disabled.patch.disabled".to_string()];
+        let missing = registry.reconcile("6.13", &on_disk);
+
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].filename, "gone-remote.patch");
+        assert!(registry.get("6.13", "kept.patch").is_some());
+        assert!(registry.get("6.13", "disabled.patch").is_some());
+        assert!(registry.get("6.13", "gone-local.patch").is_none());
+        assert!(registry.get("6.13", "gone-remote.patch").is_some());
+        assert!(registry.get("6.12", "other-series.patch").is_some());
+    }
+
+    #[test]
+    fn missing_validators_are_not_reported_as_up_to_date() {
+        let mut m = meta("6.13", "a.patch", Some("https://example.com/a.patch"));
+        assert_eq!(compare_validators(&m, None, None), UpdateStatus::NoValidators);
+
+        m.etag = Some("\"abc\"".to_string());
+        assert_eq!(compare_validators(&m, None, None), UpdateStatus::NoValidators);
+        assert_eq!(compare_validators(&m, Some("\"abc\""), None), UpdateStatus::UpToDate);
+        assert_eq!(compare_validators(&m, Some("\"def\""), None), UpdateStatus::Stale);
+        assert_eq!(
+            compare_validators(&m, Some("\"abc\""), Some("Tue, 01 Jul 2025 00:00:00 GMT")),
+            UpdateStatus::Stale
+        );
+    }
+}
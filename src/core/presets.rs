@@ -0,0 +1,101 @@
+//! Named snapshots of `customization.cfg` values ("gaming", "server", ...)
+//! kept in `config_presets.json` in the app config dir.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+const FILE_NAME: &str = "config_presets.json";
+
+/// Options left out of presets and config exports: switching tuning
+/// shouldn't change the kernel
+pub const EXCLUDED_KEYS: &[&str] = &["_version"];
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Presets {
+    /// Preset name -> option values
+    pub presets: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Presets {
+    /// Load the saved presets; none saved yet is an empty set. A file that
+    /// doesn't parse is renamed to `config_presets.json.bak`, so the next save
+    /// can't overwrite it, and the error says so.
+    pub fn load(config_dir: &Path) -> Result<Self, String> {
+        let path = config_dir.join(FILE_NAME);
+        let Ok(content) = fs::read_to_string(&path) else {
+            return Ok(Self::default());
+        };
+        serde_json::from_str(&content).map_err(|e| {
+            let backup = path.with_extension("json.bak");
+            log::error!("Unreadable presets file {}: {}", path.display(), e);
+            match fs::rename(&path, &backup) {
+                Ok(()) => format!("Presets file unreadable ({}); moved to {}", e, backup.display()),
+                Err(rename) => format!("Presets file unreadable ({}); {}", e, rename),
+            }
+        })
+    }
+
+    pub fn save(&self, config_dir: &Path) -> Result<(), String> {
+        fs::create_dir_all(config_dir).map_err(|e| e.to_string())?;
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(config_dir.join(FILE_NAME), content).map_err(|e| e.to_string())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&BTreeMap<String, String>> {
+        self.presets.get(name)
+    }
+
+    /// Store `values` under `name`, replacing a preset of the same name
+    pub fn insert(&mut self, name: &str, values: &HashMap<String, String>) {
+        let values = values
+            .iter()
+            .filter(|(key, _)| !EXCLUDED_KEYS.contains(&key.as_str()))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        self.presets.insert(name.to_string(), values);
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.presets.remove(name).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_round_trip_without_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let values = HashMap::from([
+            ("_cpusched".to_string(), "bore".to_string()),
+            ("_version".to_string(), "v6.13.2".to_string()),
+        ]);
+        let mut presets = Presets::default();
+        presets.insert("gaming", &values);
+        presets.save(dir.path()).unwrap();
+
+        let mut loaded = Presets::load(dir.path()).unwrap();
+        let gaming = loaded.get("gaming").unwrap();
+        assert_eq!(gaming.get("_cpusched").map(String::as_str), Some("bore"));
+        assert!(!gaming.contains_key("_version"));
+        assert!(loaded.remove("gaming"));
+        assert!(loaded.get("gaming").is_none());
+    }
+
+    #[test]
+    fn unreadable_file_is_kept_aside() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(FILE_NAME), "{ not json").unwrap();
+
+        assert!(Presets::load(dir.path()).is_err());
+        let backup = dir.path().join("config_presets.json.bak");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "{ not json");
+        // Saving after the error starts a new file and leaves the backup alone
+        Presets::default().save(dir.path()).unwrap();
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "{ not json");
+        assert!(Presets::load(dir.path()).unwrap().presets.is_empty());
+    }
+}
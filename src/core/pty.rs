@@ -0,0 +1,149 @@
+use std::fs::File;
+use std::os::fd::{FromRawFd, OwnedFd};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+
+/// Spawn `command` attached to a new pseudo-terminal.
+///
+/// The child gets the slave end as stdin/stdout/stderr and controlling
+/// terminal, so it behaves as if run interactively (prompts, sudo, colors).
+/// Returns the child and the master end, which is used both to read the
+/// child's output and to write input to it.
+/// Errors keep the `io::ErrorKind` of the failure so callers can explain it.
+pub fn spawn(command: &mut Command, cols: u16, rows: u16) -> std::io::Result<(Child, File)> {
+    let mut master_fd: libc::c_int = -1;
+    let mut slave_fd: libc::c_int = -1;
+    let size = libc::winsize {
+        ws_row: rows,
+        ws_col: cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+
+    // SAFETY: out-pointers are valid for the duration of the call; name and
+    // termios are optional and passed as null.
+    let rc = unsafe {
+        libc::openpty(
+            &mut master_fd,
+            &mut slave_fd,
+            std::ptr::null_mut(),
+            std::ptr::null(),
+            &size,
+        )
+    };
+    if rc != 0 {
+        let e = std::io::Error::last_os_error();
+        return Err(std::io::Error::new(
+            e.kind(),
+            format!("Failed to open pseudo-terminal: {}", e),
+        ));
+    }
+
+    // SAFETY: openpty succeeded, so both descriptors are open and owned by us.
+    let master = unsafe { File::from_raw_fd(master_fd) };
+    let slave = unsafe { OwnedFd::from_raw_fd(slave_fd) };
+
+    let stdio = |fd: &OwnedFd| -> std::io::Result<Stdio> {
+        fd.try_clone().map(Stdio::from).map_err(|e| {
+            std::io::Error::new(e.kind(), format!("Failed to duplicate pty: {}", e))
+        })
+    };
+    command.stdin(stdio(&slave)?).stdout(stdio(&slave)?).stderr(Stdio::from(slave));
+    if std::env::var_os("TERM").is_none() {
+        command.env("TERM", "xterm-256color");
+    }
+
+    // SAFETY: only async-signal-safe calls between fork and exec.
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setsid() == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            if libc::ioctl(0, libc::TIOCSCTTY, 0) == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    let child = command.spawn();
+
+    // Drop our copies of the slave end so reads on the master see EOF/EIO
+    // once the child (and anything it spawned) exits.
+    command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+
+    Ok((child?, master))
+}
+
+/// Remove ANSI escape sequences (CSI such as colors, OSC such as titles).
+pub fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters then a final byte in @..~
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: terminated by BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            // Two-byte escape (e.g. ESC ( B charset selection)
+            Some('(') | Some(')') => {
+                chars.next();
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_colors_titles_and_charset_escapes() {
+        assert_eq!(strip_ansi("\x1b[1;32m==>\x1b[0m Done"), "==> Done");
+        assert_eq!(strip_ansi("\x1b]0;title\x07text"), "text");
+        assert_eq!(strip_ansi("\x1b(Bplain"), "plain");
+        assert_eq!(strip_ansi("no escapes"), "no escapes");
+    }
+
+    #[test]
+    fn child_sees_a_terminal() {
+        use std::io::Read;
+
+        let mut command = Command::new("sh");
+        command.args(["-c", "test -t 0 && test -t 1 && echo is-a-tty"]);
+        let (mut child, mut master) = spawn(&mut command, 80, 24).unwrap();
+
+        let mut output = Vec::new();
+        let mut buffer = [0u8; 256];
+        while let Ok(n) = master.read(&mut buffer) {
+            if n == 0 {
+                break;
+            }
+            output.extend_from_slice(&buffer[..n]);
+        }
+        assert!(child.wait().unwrap().success());
+        assert!(String::from_utf8_lossy(&output).contains("is-a-tty"));
+    }
+}
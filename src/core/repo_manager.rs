@@ -1,9 +1,24 @@
-use std::io::{BufRead, BufReader};
+use crate::core::task::{self, CancelToken};
+use crate::core::version;
+use std::io::{BufReader, Read};
+use std::os::unix::process::CommandExt;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
 use std::sync::mpsc::Sender;
+use std::sync::{Mutex, PoisonError};
 use std::thread;
 use std::path::Path;
+use std::time::SystemTime;
+
+/// Kernel series linux-tkg was known to support at the time of writing.
+/// Only used when the series can't be detected from a local clone.
+pub const FALLBACK_SUPPORTED_SERIES: &[&str] = &[
+    "5.4", "5.10", "5.15", "6.1", "6.6", "6.12", "6.13", "6.14", "6.15", "6.16",
+];
+
+/// Rough download size of the `--depth=1` linux-tkg clone, shown before
+/// cloning. Measured, not queried; the full history is several times larger.
+pub const LINUX_TKG_CLONE_BYTES: u64 = 40 * 1024 * 1024;
 
 pub enum CloneMsg {
     Line(String),
@@ -12,79 +27,215 @@ pub enum CloneMsg {
 }
 
 /// Clone https://github.com/Frogging-Family/linux-tkg into `dest`.
+/// Runs in a spawned thread and streams output via `tx`; `cancel` stops git.
+pub fn clone_linux_tkg(dest: PathBuf, tx: Sender<CloneMsg>, cancel: CancelToken) {
+    log::info!("Cloning linux-tkg into {}", dest.display());
+    thread::spawn(move || clone_into(&dest, &tx, &cancel));
+}
+
+/// Where [`reclone_linux_tkg`] moves the old clone at `dest`: a
+/// `linux-tkg.old` sibling, numbered when earlier ones are still there.
+pub fn reclone_backup_path(dest: &Path) -> PathBuf {
+    let name = dest.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    (1..)
+        .map(|n| match n {
+            1 => dest.with_file_name(format!("{}.old", name)),
+            n => dest.with_file_name(format!("{}.old-{}", name, n)),
+        })
+        .find(|path| !path.exists())
+        .expect("unbounded range")
+}
+
+/// Move a broken clone at `dest` aside (see [`reclone_backup_path`]), so
+/// edits to customization.cfg survive, and clone linux-tkg again.
 /// Runs in a spawned thread and streams output via `tx`.
-pub fn clone_linux_tkg(dest: PathBuf, tx: Sender<CloneMsg>) {
+pub fn reclone_linux_tkg(dest: PathBuf, tx: Sender<CloneMsg>, cancel: CancelToken) {
+    log::info!("Re-cloning linux-tkg into {}", dest.display());
     thread::spawn(move || {
-        // Ensure the parent directory exists
-        if let Some(parent) = dest.parent() {
-            if let Err(e) = std::fs::create_dir_all(parent) {
+        if dest.exists() {
+            let backup = reclone_backup_path(&dest);
+            let _ = tx.send(CloneMsg::Line(format!(
+                "Moving {} to {}",
+                dest.display(),
+                backup.display()
+            )));
+            if let Err(e) = std::fs::rename(&dest, &backup) {
                 let _ = tx.send(CloneMsg::SpawnError(format!(
-                    "Failed to create directory {}: {}",
-                    parent.display(),
+                    "Failed to move {} aside: {}",
+                    dest.display(),
                     e
                 )));
                 return;
             }
         }
+        clone_into(&dest, &tx, &cancel);
+    });
+}
 
-        let result = Command::new("git")
-            .args([
-                "clone",
-                "--depth=1",
-                "https://github.com/Frogging-Family/linux-tkg",
-            ])
-            .arg(&dest)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn();
+fn clone_into(dest: &Path, tx: &Sender<CloneMsg>, cancel: &CancelToken) {
+    // Ensure the parent directory exists
+    if let Some(parent) = dest.parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            let _ = tx.send(CloneMsg::SpawnError(format!(
+                "Failed to create directory {}: {}",
+                parent.display(),
+                e
+            )));
+            return;
+        }
+    }
 
-        match result {
-            Ok(mut child) => {
-                let stdout = child.stdout.take();
-                let stderr = child.stderr.take();
-
-                let tx_out = tx.clone();
-                let out_handle = stdout.map(|out| {
-                    thread::spawn(move || {
-                        for line in BufReader::new(out).lines().map_while(Result::ok) {
-                            let _ = tx_out.send(CloneMsg::Line(line));
-                        }
-                    })
-                });
-
-                let tx_err = tx.clone();
-                let err_handle = stderr.map(|err| {
-                    thread::spawn(move || {
-                        for line in BufReader::new(err).lines().map_while(Result::ok) {
-                            let _ = tx_err.send(CloneMsg::Line(line));
-                        }
-                    })
-                });
+    let mut command = Command::new("git");
+    command
+        .args([
+            "clone",
+            "--depth=1",
+            "https://github.com/Frogging-Family/linux-tkg",
+        ])
+        .arg(dest);
+    run_git(command, tx, cancel);
+}
 
-                if let Some(h) = out_handle {
-                    let _ = h.join();
+/// Check that the clone at `repo` is usable: `customization.cfg` exists,
+/// `git status` works and `git fsck` finds no damage. Runs in a spawned
+/// thread, streams git's output and ends with `Exit(0)` when healthy.
+pub fn verify_clone(repo: PathBuf, tx: Sender<CloneMsg>, cancel: CancelToken) {
+    log::info!("Verifying clone at {}", repo.display());
+    thread::spawn(move || {
+        let line = |text: String| {
+            let _ = tx.send(CloneMsg::Line(text));
+        };
+        let mut healthy = true;
+        if repo.join("customization.cfg").exists() {
+            line("✓ customization.cfg present".to_string());
+        } else {
+            line("✗ customization.cfg is missing".to_string());
+            healthy = false;
+        }
+
+        let checks: [&[&str]; 2] = [&["status", "--short"], &["fsck", "--no-progress"]];
+        for args in checks {
+            line(format!("==> git {}", args.join(" ")));
+            let mut command = Command::new("git");
+            command.args(args).current_dir(&repo);
+            match git_status(command, &tx, &cancel) {
+                Ok(0) => {}
+                Ok(code) => {
+                    line(format!("✗ git {} exited with code {}", args[0], code));
+                    healthy = false;
                 }
-                if let Some(h) = err_handle {
-                    let _ = h.join();
+                Err(e) => {
+                    let _ = tx.send(CloneMsg::SpawnError(e));
+                    return;
                 }
+            }
+        }
+        let _ = tx.send(CloneMsg::Exit(if healthy { 0 } else { 1 }));
+    });
+}
 
-                match child.wait() {
-                    Ok(status) => {
-                        let _ = tx.send(CloneMsg::Exit(status.code().unwrap_or(-1)));
-                    }
-                    Err(e) => {
-                        let _ = tx.send(CloneMsg::SpawnError(e.to_string()));
-                    }
+/// Whether `repo` is a shallow clone (e.g. from `--depth=1`), so tags and
+/// older history are missing.
+pub fn is_shallow(repo: &Path) -> bool {
+    repo.join(".git").join("shallow").exists()
+}
+
+/// Fetch the full history of a shallow clone with `git fetch --unshallow`.
+/// Runs in a spawned thread and streams progress via `tx`.
+pub fn unshallow(repo: PathBuf, tx: Sender<CloneMsg>, cancel: CancelToken) {
+    log::info!("Fetching full history for {}", repo.display());
+    thread::spawn(move || {
+        let mut command = Command::new("git");
+        command
+            .args(["fetch", "--unshallow", "--tags", "--progress"])
+            .current_dir(&repo);
+        run_git(command, &tx, &cancel);
+    });
+}
+
+/// Run git, streaming stdout and stderr as lines and ending with an
+/// `Exit` or `SpawnError` message.
+fn run_git(command: Command, tx: &Sender<CloneMsg>, cancel: &CancelToken) {
+    match git_status(command, tx, cancel) {
+        Ok(code) => {
+            let _ = tx.send(CloneMsg::Exit(code));
+        }
+        Err(e) => {
+            let _ = tx.send(CloneMsg::SpawnError(e));
+        }
+    }
+}
+
+/// Run git, streaming stdout and stderr as lines, and return its exit code.
+/// Git runs in its own process group so cancelling also stops the transport
+/// helpers it starts.
+fn git_status(
+    mut command: Command,
+    tx: &Sender<CloneMsg>,
+    cancel: &CancelToken,
+) -> Result<i32, String> {
+    let result = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .spawn();
+
+    match result {
+        Ok(mut child) => {
+            let stdout = child.stdout.take();
+            let stderr = child.stderr.take();
+
+            let tx_out = tx.clone();
+            let out_handle = stdout.map(|out| thread::spawn(move || stream_lines(out, &tx_out)));
+            let tx_err = tx.clone();
+            let err_handle = stderr.map(|err| thread::spawn(move || stream_lines(err, &tx_err)));
+
+            let status = task::wait_or_kill(&mut child, cancel);
+            if let Some(h) = out_handle {
+                let _ = h.join();
+            }
+            if let Some(h) = err_handle {
+                let _ = h.join();
+            }
+
+            match status {
+                Ok(status) => {
+                    let code = status.code().unwrap_or(-1);
+                    log::info!("git exited with code {}", code);
+                    Ok(code)
+                }
+                Err(e) => {
+                    log::error!("git failed: {}", e);
+                    Err(e.to_string())
                 }
             }
-            Err(e) => {
-                let _ = tx.send(CloneMsg::SpawnError(format!(
-                    "Failed to spawn git: {}",
-                    e
-                )));
+        }
+        Err(e) => {
+            log::error!("Failed to spawn git: {}", e);
+            Err(format!("Failed to spawn git: {}", e))
+        }
+    }
+}
+
+/// Forward output line by line. Git redraws progress with `\r`, so that
+/// ends a line too.
+fn stream_lines(reader: impl Read, tx: &Sender<CloneMsg>) {
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+    while let Ok(1) = reader.read(&mut byte) {
+        if byte[0] == b'\n' || byte[0] == b'\r' {
+            if !line.is_empty() {
+                let _ = tx.send(CloneMsg::Line(String::from_utf8_lossy(&line).into_owned()));
+                line.clear();
             }
+        } else {
+            line.push(byte[0]);
         }
-    });
+    }
+    if !line.is_empty() {
+        let _ = tx.send(CloneMsg::Line(String::from_utf8_lossy(&line).into_owned()));
+    }
 }
 
 /// Copy an existing linux-tkg directory into `dest`.
@@ -136,3 +287,148 @@ pub fn copy_linux_tkg(source: &Path, dest: &Path, tx: Sender<CloneMsg>) {
         }
     });
 }
+
+/// Last result of [`detect_supported_series`]. The tabs ask every frame;
+/// a pull that adds a series changes the folder's mtime.
+struct DetectedSeries {
+    linux_tkg_path: PathBuf,
+    /// mtime of `linux-tkg-config/`
+    modified: Option<SystemTime>,
+    series: Vec<String>,
+}
+
+static DETECTED_SERIES: Mutex<Option<DetectedSeries>> = Mutex::new(None);
+
+/// Kernel series supported by the linux-tkg clone at `linux_tkg_path`.
+///
+/// Detected from the per-series directories under `linux-tkg-config/`.
+/// Returns an empty list if the clone is missing or has no such directories.
+pub fn detect_supported_series(linux_tkg_path: &Path) -> Vec<String> {
+    let config_dir = linux_tkg_path.join("linux-tkg-config");
+    let modified = std::fs::metadata(&config_dir).and_then(|m| m.modified()).ok();
+    let mut cache = DETECTED_SERIES.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(cached) = cache.as_ref() {
+        if cached.linux_tkg_path == linux_tkg_path && cached.modified == modified {
+            return cached.series.clone();
+        }
+    }
+
+    let mut series: Vec<String> = std::fs::read_dir(&config_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().is_dir())
+                .filter_map(|e| e.file_name().to_str().map(|s| s.to_string()))
+                .filter(|name| version::is_series(name))
+                .collect()
+        })
+        .unwrap_or_default();
+    series.sort_by(|a, b| version::compare(a, b));
+    *cache = Some(DetectedSeries {
+        linux_tkg_path: linux_tkg_path.to_path_buf(),
+        modified,
+        series: series.clone(),
+    });
+    series
+}
+
+/// Resolve the supported series list: the user override if set, otherwise
+/// detection from the clone, otherwise the built-in fallback.
+pub fn supported_series(linux_tkg_path: &Path, overrides: &[String]) -> Vec<String> {
+    if !overrides.is_empty() {
+        let mut series = overrides.to_vec();
+        series.sort_by(|a, b| version::compare(a, b));
+        series.dedup();
+        return series;
+    }
+    let detected = detect_supported_series(linux_tkg_path);
+    if !detected.is_empty() {
+        return detected;
+    }
+    FALLBACK_SUPPORTED_SERIES.iter().map(|s| s.to_string()).collect()
+}
+
+/// Returns true if `version`'s series appears in `supported`.
+pub fn is_version_supported(version: &str, supported: &[String]) -> bool {
+    let series = version::series(version);
+    supported.contains(&series)
+}
+
+/// Oldest and newest series in `supported`, e.g. `("5.15", "6.14")`
+pub fn supported_range(supported: &[String]) -> Option<(&str, &str)> {
+    let oldest = supported.iter().min_by(|a, b| version::compare(a, b))?;
+    let newest = supported.iter().max_by(|a, b| version::compare(a, b))?;
+    Some((oldest, newest))
+}
+
+/// Why linux-tkg is unlikely to build `version`, or None when its series is
+/// supported. Points at updating linux-tkg when the kernel is newer than
+/// anything the clone knows about.
+pub fn unsupported_version_warning(version: &str, supported: &[String]) -> Option<String> {
+    if is_version_supported(version, supported) {
+        return None;
+    }
+    let series = version::series(version);
+    let Some((oldest, newest)) = supported_range(supported) else {
+        return Some(format!("linux-tkg lists no supported series for {}", series));
+    };
+    let advice = if version::compare(&series, newest).is_gt() {
+        "Update linux-tkg (git pull in the clone, or Re-clone on the Settings tab) to get \
+         support for newer kernels."
+    } else if version::compare(&series, oldest).is_lt() {
+        "Pick a newer kernel; current linux-tkg dropped this series."
+    } else {
+        "Pick a series linux-tkg lists; its scripts skip this one."
+    };
+    Some(format!(
+        "linux-tkg supports {}–{} but doesn't list {}. {}",
+        oldest, newest, series, advice
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newer_kernel_than_clone_suggests_update() {
+        let supported: Vec<String> =
+            ["6.9", "6.10", "5.15"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(supported_range(&supported), Some(("5.15", "6.10")));
+        assert_eq!(unsupported_version_warning("v6.10.3", &supported), None);
+
+        let warning = unsupported_version_warning("v6.11.1", &supported).unwrap();
+        assert!(warning.contains("5.15–6.10"), "{}", warning);
+        assert!(warning.contains("Update linux-tkg"), "{}", warning);
+        let warning = unsupported_version_warning("5.4.1", &supported).unwrap();
+        assert!(!warning.contains("Update linux-tkg"), "{}", warning);
+    }
+
+    #[test]
+    fn detected_series_follow_the_clone() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config_dir = tmp.path().join("linux-tkg-config");
+        for series in ["6.12", "6.9"] {
+            std::fs::create_dir_all(config_dir.join(series)).unwrap();
+        }
+        assert_eq!(detect_supported_series(tmp.path()), vec!["6.9", "6.12"]);
+
+        // A pull adding a series bumps the folder's mtime, dropping the cached list
+        std::fs::create_dir(config_dir.join("6.13")).unwrap();
+        let later = SystemTime::now() + std::time::Duration::from_secs(60);
+        std::fs::File::open(&config_dir).unwrap().set_modified(later).unwrap();
+        assert_eq!(detect_supported_series(tmp.path()), vec!["6.9", "6.12", "6.13"]);
+
+        let overrides = vec!["6.13".to_string(), "6.9".to_string(), "6.13".to_string()];
+        assert_eq!(supported_series(tmp.path(), &overrides), vec!["6.9", "6.13"]);
+    }
+
+    #[test]
+    fn reclone_backup_never_overwrites_an_earlier_one() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dest = tmp.path().join("linux-tkg");
+        assert_eq!(reclone_backup_path(&dest), tmp.path().join("linux-tkg.old"));
+        std::fs::create_dir(tmp.path().join("linux-tkg.old")).unwrap();
+        assert_eq!(reclone_backup_path(&dest), tmp.path().join("linux-tkg.old-2"));
+    }
+}
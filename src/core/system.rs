@@ -0,0 +1,93 @@
+use crate::core::build_manager;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Returns true if the app is running with an effective UID of 0 (root).
+pub fn is_root() -> bool {
+    // SAFETY: geteuid has no preconditions and cannot fail.
+    unsafe { libc::geteuid() == 0 }
+}
+
+/// Full path of `program` if it is an executable file on `$PATH`.
+pub fn find_in_path(program: &str) -> Option<std::path::PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(program))
+        .find(|p| {
+            p.metadata()
+                .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        })
+}
+
+/// Bytes available to unprivileged users on the filesystem holding `path`.
+pub fn free_space(path: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: c_path is NUL-terminated and stat is a valid out-pointer
+    unsafe {
+        let mut stat: libc::statvfs = std::mem::zeroed();
+        if libc::statvfs(c_path.as_ptr(), &mut stat) != 0 {
+            return None;
+        }
+        Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+    }
+}
+
+/// Current text on the system clipboard, if any.
+pub fn clipboard_text() -> Option<String> {
+    arboard::Clipboard::new().ok()?.get_text().ok()
+}
+
+/// Show `dir` in the file manager with `xdg-open`, creating it first.
+pub fn open_folder(dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    Command::new("xdg-open")
+        .arg(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to run xdg-open: {}", e))
+}
+
+/// Open `file` in the user's editor: `$VISUAL` directly (a GUI editor by
+/// convention), `$EDITOR` inside a terminal emulator, otherwise `xdg-open`.
+/// Returns a description of what was launched.
+pub fn open_in_editor(file: &Path) -> Result<String, String> {
+    let non_empty = |var: &str| std::env::var(var).ok().filter(|v| !v.trim().is_empty());
+
+    if let Some(visual) = non_empty("VISUAL") {
+        let mut parts = visual.split_whitespace();
+        let program = parts.next().unwrap_or_default();
+        if Command::new(program)
+            .args(parts)
+            .arg(file)
+            .stdin(Stdio::null())
+            .spawn()
+            .is_ok()
+        {
+            return Ok(program.to_string());
+        }
+    }
+
+    if let Some(editor) = non_empty("EDITOR") {
+        let dir = file.parent().unwrap_or(Path::new("."));
+        let quoted = format!("'{}'", file.display().to_string().replace('\'', "'\\''"));
+        if let Ok(terminal) =
+            build_manager::launch_in_terminal(dir, &format!("{} {}", editor, quoted))
+        {
+            return Ok(format!("{} in {}", editor, terminal));
+        }
+    }
+
+    Command::new("xdg-open")
+        .arg(file)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| "xdg-open".to_string())
+        .map_err(|e| format!("Failed to open editor: {}", e))
+}
@@ -0,0 +1,66 @@
+//! Cooperative cancellation for background tasks. Tabs keep a token per
+//! running task; "Stop All" cancels every live one.
+
+use std::process::{Child, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Shared flag a worker thread polls to learn it should stop
+#[derive(Clone, Default, Debug)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// The underlying flag, for functions that poll an `AtomicBool`
+    pub fn flag(&self) -> &AtomicBool {
+        &self.0
+    }
+}
+
+/// Wait for `child`, terminating it once `cancel` is set. A child started
+/// with `process_group(0)` is stopped together with any helpers it spawned
+/// (e.g. git's transport processes).
+pub fn wait_or_kill(child: &mut Child, cancel: &CancelToken) -> std::io::Result<ExitStatus> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if cancel.is_cancelled() {
+            log::warn!("Stopping cancelled process {}", child.id());
+            // SAFETY: kill has no memory-safety preconditions
+            if unsafe { libc::kill(-(child.id() as i32), libc::SIGTERM) } != 0 {
+                let _ = child.kill();
+            }
+            return child.wait();
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::CommandExt;
+    use std::process::Command;
+    use std::time::Instant;
+
+    #[test]
+    fn cancelled_child_is_stopped() {
+        let mut child = Command::new("sleep").arg("30").process_group(0).spawn().unwrap();
+        let cancel = CancelToken::default();
+        cancel.cancel();
+        let started = Instant::now();
+        let status = wait_or_kill(&mut child, &cancel).unwrap();
+        assert!(!status.success());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
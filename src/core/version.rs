@@ -0,0 +1,118 @@
+//! Kernel version strings as used by kernel.org tags and linux-tkg
+//! (`v6.13.1`, `6.14-rc3`, `v2.6.32.71`).
+
+use std::cmp::Ordering;
+
+/// The version without surrounding whitespace or a leading `v`
+pub fn strip_v(version: &str) -> &str {
+    version.trim().trim_start_matches('v')
+}
+
+/// The version with a single leading `v`, as linux-tkg's `_version` expects
+pub fn with_v(version: &str) -> String {
+    format!("v{}", strip_v(version))
+}
+
+/// Split a version into `(numeric components, pre-release suffix)`, e.g.
+/// "v6.1.123" -> ([6, 1, 123], None) and "v6.14-rc3" -> ([6, 14], Some("rc3")).
+pub fn parts(version: &str) -> (Vec<u32>, Option<&str>) {
+    let stripped = strip_v(version);
+    let (numbers, suffix) = match stripped.split_once('-') {
+        Some((numbers, suffix)) => (numbers, Some(suffix)),
+        None => (stripped, None),
+    };
+    let numbers = numbers
+        .split('.')
+        .map_while(|p| p.parse().ok())
+        .collect();
+    (numbers, suffix)
+}
+
+/// Numeric components only, e.g. "v6.14-rc3" -> [6, 14]
+pub fn numbers(version: &str) -> Vec<u32> {
+    parts(version).0
+}
+
+/// Extract the major.minor series from a version string (e.g., "v6.13.1" -> "6.13",
+/// "v6.14-rc3" -> "6.14")
+pub fn series(version: &str) -> String {
+    let numbers = numbers(version);
+    if numbers.len() >= 2 {
+        format!("{}.{}", numbers[0], numbers[1])
+    } else {
+        strip_v(version).to_string()
+    }
+}
+
+/// Whether `name` is a bare series such as "6.12"
+pub fn is_series(name: &str) -> bool {
+    let parts: Vec<&str> = name.split('.').collect();
+    parts.len() == 2 && parts.iter().all(|p| p.parse::<u32>().is_ok())
+}
+
+/// Whether `version` is a mainline release candidate such as "v6.14-rc3"
+pub fn is_rc(version: &str) -> bool {
+    parts(version).1.is_some_and(|suffix| suffix.starts_with("rc"))
+}
+
+/// Numeric version ordering: "6.1.9" < "6.1.10" < "6.2-rc1" < "6.2" < "6.2.1".
+pub fn compare(a: &str, b: &str) -> Ordering {
+    let (na, sa) = parts(a);
+    let (nb, sb) = parts(b);
+    na.cmp(&nb).then_with(|| match (sa, sb) {
+        // A pre-release comes before the release itself
+        (None, None) => Ordering::Equal,
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(x), Some(y)) => {
+            let rc = |s: &str| s.trim_start_matches("rc").parse::<u32>().ok();
+            match (rc(x), rc(y)) {
+                (Some(x), Some(y)) => x.cmp(&y),
+                _ => x.cmp(y),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_lts_deep_points_numerically() {
+        let mut versions = vec![
+            "v6.1.9", "v6.1.123", "v6.1.10", "v6.1", "v6.2-rc1", "v6.2", "v5.15.178", "v2.6.32.71",
+        ];
+        versions.sort_by(|a, b| compare(a, b));
+        assert_eq!(
+            versions,
+            vec![
+                "v2.6.32.71", "v5.15.178", "v6.1", "v6.1.9", "v6.1.10", "v6.1.123", "v6.2-rc1",
+                "v6.2",
+            ]
+        );
+        assert!(compare("v6.14-rc2", "v6.14-rc10").is_lt());
+        assert!(compare("v6.14-rc10", "v6.14").is_lt());
+    }
+
+    #[test]
+    fn series_ignores_patch_level_and_suffix() {
+        assert_eq!(series("v6.1.123"), "6.1");
+        assert_eq!(series("6.12"), "6.12");
+        assert_eq!(series("v6.14-rc3"), "6.14");
+        assert_eq!(series("v2.6.32.71"), "2.6");
+        assert!(is_series("6.12"));
+        assert!(!is_series("6.12.1"));
+        assert!(!is_series("linux-6.12"));
+    }
+
+    #[test]
+    fn v_prefix_is_normalised() {
+        assert_eq!(strip_v(" v6.13.1\n"), "6.13.1");
+        assert_eq!(with_v("6.13.1"), "v6.13.1");
+        assert_eq!(with_v("v6.13.1"), "v6.13.1");
+        assert_eq!(numbers("v6.14-rc3"), vec![6, 14]);
+        assert!(is_rc("v6.14-rc3"));
+        assert!(!is_rc("v6.14"));
+    }
+}
@@ -1,3 +1,5 @@
+use crate::core::kernel_downloader::format_bytes;
+use crate::core::system;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -22,7 +24,25 @@ impl WorkDir {
         Ok(Self { path, keep: false })
     }
 
-    /// Root path of the temporary working directory.
+    /// Create the working directory as `<base>/tkg-gui-<pid>`, for when the
+    /// temp dir is a tmpfs too small for a kernel build. The directory is
+    /// removed on exit, so an existing non-empty one is refused rather than
+    /// adopted. Fails unless it can be written to.
+    pub fn in_dir(base: &Path) -> Result<Self, String> {
+        let path = base.join(format!("tkg-gui-{}", std::process::id()));
+        if fs::read_dir(&path).is_ok_and(|mut entries| entries.next().is_some()) {
+            return Err(format!("Work dir {} already exists and is not empty", path.display()));
+        }
+        fs::create_dir_all(&path)
+            .map_err(|e| format!("Failed to create work dir {}: {}", path.display(), e))?;
+        let probe = path.join(".write-test");
+        fs::write(&probe, "")
+            .map_err(|e| format!("Work dir {} is not writable: {}", path.display(), e))?;
+        let _ = fs::remove_file(&probe);
+        Ok(Self { path, keep: false })
+    }
+
+    /// Root path of the working directory.
     pub fn root(&self) -> &Path {
         &self.path
     }
@@ -37,6 +57,37 @@ impl WorkDir {
         self.path.join("kernel-sources")
     }
 
+    /// Point `kernel-sources` inside the work dir at a persistent directory,
+    /// so downloads land outside the temp dir but the layout stays the same.
+    ///
+    /// Replaces an existing symlink; leaves a real (ephemeral) directory alone.
+    pub fn link_kernel_sources(&self, target: &Path) -> Result<(), String> {
+        fs::create_dir_all(target)
+            .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+        let link = self.kernel_sources();
+        if link.is_symlink() {
+            fs::remove_file(&link)
+                .map_err(|e| format!("Failed to replace {}: {}", link.display(), e))?;
+        } else if link.exists() {
+            return Ok(());
+        }
+        std::os::unix::fs::symlink(target, &link)
+            .map_err(|e| format!("Failed to link {}: {}", link.display(), e))
+    }
+
+    /// Remove the `kernel-sources` symlink so downloads go back into the work dir.
+    pub fn unlink_kernel_sources(&self) {
+        let link = self.kernel_sources();
+        if link.is_symlink() {
+            let _ = fs::remove_file(&link);
+        }
+    }
+
+    /// Bytes available on the filesystem holding the work directory.
+    pub fn available_space(&self) -> Option<u64> {
+        system::free_space(&self.path)
+    }
+
     /// If true, the work directory will be preserved when the app exits.
     pub fn set_keep(&mut self, keep: bool) {
         self.keep = keep;
@@ -59,6 +110,23 @@ impl WorkDir {
     }
 }
 
+/// Refuse to start a download or build into `path` when its filesystem has
+/// less than `min_free` bytes available. `path` may not exist yet; unknown
+/// free space doesn't block.
+pub fn ensure_free_space(path: &Path, min_free: u64) -> Result<(), String> {
+    let existing = path.ancestors().find(|p| p.exists()).unwrap_or(path);
+    match system::free_space(existing) {
+        Some(free) if free < min_free => Err(format!(
+            "Only {} free on the filesystem holding {}, {} needed. Free up space or lower \
+             the minimum in Settings → App Directories.",
+            format_bytes(free),
+            path.display(),
+            format_bytes(min_free)
+        )),
+        _ => Ok(()),
+    }
+}
+
 impl Drop for WorkDir {
     fn drop(&mut self) {
         if !self.keep && self.path.exists() {
@@ -66,3 +134,35 @@ impl Drop for WorkDir {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_work_dir_leaves_existing_folders_alone() {
+        let base = tempfile::tempdir().unwrap();
+        let existing = base.path().join("tkg-gui");
+        fs::create_dir(&existing).unwrap();
+        fs::write(existing.join("patch_registry.json"), "{}").unwrap();
+
+        let work_dir = WorkDir::in_dir(base.path()).unwrap();
+        let root = work_dir.root().to_path_buf();
+        assert_eq!(root, base.path().join(format!("tkg-gui-{}", std::process::id())));
+        fs::write(root.join("build.log"), "").unwrap();
+        assert!(WorkDir::in_dir(base.path()).is_err());
+        drop(work_dir);
+
+        assert!(!root.exists());
+        assert!(existing.join("patch_registry.json").exists());
+    }
+
+    #[test]
+    fn free_space_guard_checks_nearest_existing_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("not/yet/created");
+        assert!(ensure_free_space(&missing, 0).is_ok());
+        let err = ensure_free_space(&missing, u64::MAX).unwrap_err();
+        assert!(err.contains("not/yet/created"), "{}", err);
+    }
+}
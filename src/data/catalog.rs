@@ -1,4 +1,8 @@
-/// A catalog entry describing a well-known userpatch source
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// A built-in catalog entry describing a well-known userpatch source
 #[derive(Clone, Debug)]
 pub struct CatalogEntry {
     pub id: &'static str,
@@ -10,9 +14,97 @@ pub struct CatalogEntry {
     pub filename_template: &'static str,
     /// Supported kernel series (e.g., ["6.12", "6.13"])
     pub supported_series: &'static [&'static str],
+    /// Lowercase keywords for filtering (e.g., ["gaming", "vfio"])
+    pub tags: &'static [&'static str],
+    /// Group heading in the catalog list, one of [`CATEGORIES`]
+    pub category: &'static str,
+    /// Project homepage or repository the patch comes from
+    pub homepage: Option<&'static str>,
+    /// Who maintains the patch upstream
+    pub maintainer: Option<&'static str>,
+    /// Further files of a patch series as (url_template, filename_template)
+    pub files: &'static [(&'static str, &'static str)],
+    /// Expected SHA-256 of pinned files as (file name, hex digest); files
+    /// not listed, e.g. ones that follow a branch, aren't checked
+    pub sha256: &'static [(&'static str, &'static str)],
+}
+
+/// Owned catalog entry, used for both built-in and remote entries.
+///
+/// This is also the JSON schema of each element of a remote catalog index's
+/// `entries` array.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CatalogEntryOwned {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub url_template: String,
+    pub filename_template: String,
+    pub supported_series: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Group heading; entries without one are listed under "Other"
+    #[serde(default)]
+    pub category: String,
+    #[serde(default)]
+    pub homepage: Option<String>,
+    #[serde(default)]
+    pub maintainer: Option<String>,
+    /// Further files of a patch series, downloaded after the main one
+    #[serde(default)]
+    pub files: Vec<CatalogFile>,
+    /// Expected SHA-256 of the saved (decompressed) content, keyed by the
+    /// file name for a series, e.g. `{"misc-6.12-0001.patch": "ab12…"}`.
+    /// A download that doesn't match is refused.
+    #[serde(default)]
+    pub sha256: HashMap<String, String>,
+}
+
+/// One additional file of a multi-file catalog entry
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CatalogFile {
+    pub url_template: String,
+    pub filename_template: String,
+}
+
+/// Remote catalog index document: `{"version": 1, "entries": [...]}`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CatalogIndex {
+    pub version: u32,
+    pub entries: Vec<CatalogEntryOwned>,
+}
+
+impl From<&CatalogEntry> for CatalogEntryOwned {
+    fn from(entry: &CatalogEntry) -> Self {
+        Self {
+            id: entry.id.to_string(),
+            name: entry.name.to_string(),
+            description: entry.description.to_string(),
+            url_template: entry.url_template.to_string(),
+            filename_template: entry.filename_template.to_string(),
+            supported_series: entry.supported_series.iter().map(|s| s.to_string()).collect(),
+            tags: entry.tags.iter().map(|s| s.to_string()).collect(),
+            category: entry.category.to_string(),
+            homepage: entry.homepage.map(str::to_string),
+            maintainer: entry.maintainer.map(str::to_string),
+            files: entry
+                .files
+                .iter()
+                .map(|(url, filename)| CatalogFile {
+                    url_template: url.to_string(),
+                    filename_template: filename.to_string(),
+                })
+                .collect(),
+            sha256: entry
+                .sha256
+                .iter()
+                .map(|(filename, digest)| (filename.to_string(), digest.to_string()))
+                .collect(),
+        }
+    }
 }
 
-impl CatalogEntry {
+impl CatalogEntryOwned {
     /// Get the URL for a specific kernel series
     pub fn url_for_series(&self, series: &str) -> String {
         self.url_template.replace("{series}", series)
@@ -23,18 +115,115 @@ impl CatalogEntry {
         self.filename_template.replace("{series}", series)
     }
 
+    /// (url, filename) of every file for a series, the main file first
+    pub fn files_for_series(&self, series: &str) -> Vec<(String, String)> {
+        let mut files = vec![(self.url_for_series(series), self.filename_for_series(series))];
+        files.extend(self.files.iter().map(|f| {
+            (
+                f.url_template.replace("{series}", series),
+                f.filename_template.replace("{series}", series),
+            )
+        }));
+        files
+    }
+
+    /// Pinned SHA-256 of `filename`, or None for floating files
+    pub fn expected_sha256(&self, filename: &str) -> Option<&str> {
+        self.sha256.get(filename).map(String::as_str)
+    }
+
     /// Check if this entry supports the given kernel series
     pub fn supports_series(&self, series: &str) -> bool {
-        self.supported_series.contains(&series)
+        self.supported_series.iter().any(|s| s == series)
+    }
+
+    /// Check if the name, description, category or any tag contains `query`
+    /// (already lowercased)
+    pub fn matches_filter(&self, query: &str) -> bool {
+        query.is_empty()
+            || self.name.to_lowercase().contains(query)
+            || self.description.to_lowercase().contains(query)
+            || self.category.to_lowercase().contains(query)
+            || self.tags.iter().any(|t| t.contains(query))
+    }
+
+    /// Category heading, "Other" when unset
+    pub fn category_label(&self) -> &str {
+        match self.category.trim() {
+            "" => "Other",
+            category => category,
+        }
     }
 }
 
-/// Filter catalog to entries supporting the given kernel series
-pub fn catalog_for_series(series: &str) -> Vec<&'static CatalogEntry> {
-    CATALOG
+/// Catalog categories in display order; remote entries may add others,
+/// which are listed after these
+pub const CATEGORIES: &[&str] = &[
+    "Scheduler",
+    "Gaming",
+    "Networking",
+    "Power",
+    "Memory",
+    "CPU",
+    "Performance",
+    "Virtualization",
+    "Fixes",
+];
+
+/// Group entries by category: known categories in [`CATEGORIES`] order,
+/// then unknown ones alphabetically, then "Other". Entries are sorted by
+/// name within a group.
+pub fn group_by_category(
+    entries: Vec<CatalogEntryOwned>,
+) -> Vec<(String, Vec<CatalogEntryOwned>)> {
+    let rank = |category: &str| match CATEGORIES.iter().position(|c| *c == category) {
+        Some(i) => (0, i, String::new()),
+        None if category == "Other" => (2, 0, String::new()),
+        None => (1, 0, category.to_lowercase()),
+    };
+    let mut groups: Vec<(String, Vec<CatalogEntryOwned>)> = Vec::new();
+    for entry in entries {
+        let category = entry.category_label().to_string();
+        match groups.iter_mut().find(|(c, _)| *c == category) {
+            Some((_, group)) => group.push(entry),
+            None => groups.push((category, vec![entry])),
+        }
+    }
+    groups.sort_by_key(|(category, _)| rank(category));
+    for (_, group) in &mut groups {
+        group.sort_by_key(|e| e.name.to_lowercase());
+    }
+    groups
+}
+
+/// Entries fetched from the remote catalog index, if any
+static REMOTE_CATALOG: RwLock<Vec<CatalogEntryOwned>> = RwLock::new(Vec::new());
+
+/// Replace the remote entries merged into `catalog_for_series`.
+pub fn set_remote_entries(entries: Vec<CatalogEntryOwned>) {
+    if let Ok(mut remote) = REMOTE_CATALOG.write() {
+        *remote = entries;
+    }
+}
+
+/// Number of remote entries currently merged into the catalog.
+pub fn remote_entry_count() -> usize {
+    REMOTE_CATALOG.read().map(|r| r.len()).unwrap_or(0)
+}
+
+/// Filter catalog to entries supporting the given kernel series.
+///
+/// Remote entries replace built-in entries with the same id.
+pub fn catalog_for_series(series: &str) -> Vec<CatalogEntryOwned> {
+    let remote = REMOTE_CATALOG.read().map(|r| r.clone()).unwrap_or_default();
+    let mut entries: Vec<CatalogEntryOwned> = CATALOG
         .iter()
-        .filter(|e| e.supports_series(series))
-        .collect()
+        .filter(|e| !remote.iter().any(|r| r.id == e.id))
+        .map(CatalogEntryOwned::from)
+        .collect();
+    entries.extend(remote);
+    entries.retain(|e| e.supports_series(series));
+    entries
 }
 
 static CATALOG: &[CatalogEntry] = &[
@@ -45,6 +234,12 @@ static CATALOG: &[CatalogEntry] = &[
         url_template: "https://raw.githubusercontent.com/benbaker76/linux-acs-override/main/workspaces/{series}/acso.patch",
         filename_template: "acs-override-{series}.patch",
         supported_series: &["6.10", "6.11", "6.12", "6.13"],
+        tags: &["vfio", "virtualization"],
+        category: "Virtualization",
+        homepage: Some("https://github.com/benbaker76/linux-acs-override"),
+        maintainer: Some("benbaker76"),
+        files: &[],
+        sha256: &[],
     },
     CatalogEntry {
         id: "bbr3",
@@ -53,6 +248,12 @@ static CATALOG: &[CatalogEntry] = &[
         url_template: "https://raw.githubusercontent.com/CachyOS/kernel-patches/master/{series}/misc/0001-bbr3.patch",
         filename_template: "bbr3-{series}.patch",
         supported_series: &["6.11", "6.12", "6.13"],
+        tags: &["network", "performance"],
+        category: "Networking",
+        homepage: Some("https://github.com/CachyOS/kernel-patches"),
+        maintainer: Some("CachyOS"),
+        files: &[],
+        sha256: &[],
     },
     CatalogEntry {
         id: "cachy-fixes",
@@ -61,6 +262,12 @@ static CATALOG: &[CatalogEntry] = &[
         url_template: "https://raw.githubusercontent.com/CachyOS/kernel-patches/master/{series}/all/0001-cachyos-base-all.patch",
         filename_template: "cachy-fixes-{series}.patch",
         supported_series: &["6.11", "6.12", "6.13"],
+        tags: &["fixes", "performance"],
+        category: "Fixes",
+        homepage: Some("https://github.com/CachyOS/kernel-patches"),
+        maintainer: Some("CachyOS"),
+        files: &[],
+        sha256: &[],
     },
     CatalogEntry {
         id: "graysky-cpu",
@@ -69,6 +276,12 @@ static CATALOG: &[CatalogEntry] = &[
         url_template: "https://raw.githubusercontent.com/graysky2/kernel_compiler_patch/master/more-uarches-for-kernel-6.8-rc4%2B.patch",
         filename_template: "graysky-cpu-{series}.patch",
         supported_series: &["6.8", "6.9", "6.10", "6.11", "6.12", "6.13"],
+        tags: &["performance", "cpu"],
+        category: "CPU",
+        homepage: Some("https://github.com/graysky2/kernel_compiler_patch"),
+        maintainer: Some("graysky2"),
+        files: &[],
+        sha256: &[],
     },
     CatalogEntry {
         id: "futex-waitv",
@@ -77,6 +290,12 @@ static CATALOG: &[CatalogEntry] = &[
         url_template: "https://raw.githubusercontent.com/CachyOS/kernel-patches/master/{series}/misc/0001-futex-Add-entry-point-for-FUTEX_WAIT_MULTIPLE.patch",
         filename_template: "futex-waitv-{series}.patch",
         supported_series: &["6.10", "6.11"],
+        tags: &["gaming", "wine"],
+        category: "Gaming",
+        homepage: Some("https://github.com/CachyOS/kernel-patches"),
+        maintainer: Some("CachyOS"),
+        files: &[],
+        sha256: &[],
     },
     CatalogEntry {
         id: "zstd-upstream",
@@ -85,6 +304,12 @@ static CATALOG: &[CatalogEntry] = &[
         url_template: "https://raw.githubusercontent.com/CachyOS/kernel-patches/master/{series}/misc/0001-zstd.patch",
         filename_template: "zstd-upstream-{series}.patch",
         supported_series: &["6.11", "6.12", "6.13"],
+        tags: &["compression", "performance"],
+        category: "Performance",
+        homepage: Some("https://github.com/CachyOS/kernel-patches"),
+        maintainer: Some("CachyOS"),
+        files: &[],
+        sha256: &[],
     },
     CatalogEntry {
         id: "amd-pstate",
@@ -93,6 +318,12 @@ static CATALOG: &[CatalogEntry] = &[
         url_template: "https://raw.githubusercontent.com/CachyOS/kernel-patches/master/{series}/misc/0001-amd-pstate.patch",
         filename_template: "amd-pstate-{series}.patch",
         supported_series: &["6.11", "6.12", "6.13"],
+        tags: &["cpu", "power", "amd"],
+        category: "Power",
+        homepage: Some("https://github.com/CachyOS/kernel-patches"),
+        maintainer: Some("CachyOS"),
+        files: &[],
+        sha256: &[],
     },
     CatalogEntry {
         id: "le9",
@@ -101,5 +332,81 @@ static CATALOG: &[CatalogEntry] = &[
         url_template: "https://raw.githubusercontent.com/CachyOS/kernel-patches/master/{series}/misc/0001-mm-add-le9.patch",
         filename_template: "le9-{series}.patch",
         supported_series: &["6.10", "6.11", "6.12"],
+        tags: &["memory", "desktop"],
+        category: "Memory",
+        homepage: Some("https://github.com/CachyOS/kernel-patches"),
+        maintainer: Some("CachyOS"),
+        files: &[],
+        sha256: &[],
     },
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn series_entry_lists_every_file() {
+        let json = r#"{
+            "id": "misc", "name": "Misc", "description": "",
+            "url_template": "https://example.com/{series}/0001.patch",
+            "filename_template": "misc-{series}-0001.patch",
+            "supported_series": ["6.12"],
+            "files": [{
+                "url_template": "https://example.com/{series}/0002.patch",
+                "filename_template": "misc-{series}-0002.patch"
+            }],
+            "sha256": {"misc-6.12-0002.patch": "ab12"}
+        }"#;
+        let entry: CatalogEntryOwned = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            entry.files_for_series("6.12"),
+            vec![
+                (
+                    "https://example.com/6.12/0001.patch".to_string(),
+                    "misc-6.12-0001.patch".to_string()
+                ),
+                (
+                    "https://example.com/6.12/0002.patch".to_string(),
+                    "misc-6.12-0002.patch".to_string()
+                ),
+            ]
+        );
+        assert_eq!(entry.expected_sha256("misc-6.12-0002.patch"), Some("ab12"));
+        assert_eq!(entry.expected_sha256("misc-6.12-0001.patch"), None);
+        // Single-file entries from older indexes still parse
+        let single = CATALOG.iter().map(CatalogEntryOwned::from).next().unwrap();
+        assert_eq!(single.files_for_series("6.12").len(), 1);
+    }
+
+    #[test]
+    fn categories_group_in_display_order() {
+        let entry = |name: &str, category: &str| {
+            let mut e = CatalogEntryOwned::from(&CATALOG[0]);
+            e.name = name.to_string();
+            e.category = category.to_string();
+            e
+        };
+        let groups = group_by_category(vec![
+            entry("b", "Zram"),
+            entry("z", "Gaming"),
+            entry("x", ""),
+            entry("a", "Gaming"),
+            entry("s", "Scheduler"),
+        ]);
+        let order: Vec<(&str, Vec<&str>)> = groups
+            .iter()
+            .map(|(c, es)| (c.as_str(), es.iter().map(|e| e.name.as_str()).collect()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("Scheduler", vec!["s"]),
+                ("Gaming", vec!["a", "z"]),
+                ("Zram", vec!["b"]),
+                ("Other", vec!["x"]),
+            ]
+        );
+        assert!(CATALOG.iter().all(|e| CATEGORIES.contains(&e.category)));
+    }
+}
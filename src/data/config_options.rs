@@ -0,0 +1,534 @@
+//! customization.cfg options shown in the Config tab.
+//!
+//! The tab renders straight from [`SECTIONS`], so this table is also the
+//! schema used to spot options missing from an older config file.
+
+#[derive(Clone, Copy, Debug)]
+pub enum OptionKind {
+    /// Fixed choices as `(value, label)`
+    Combo(&'static [(&'static str, &'static str)]),
+    /// Boolean stored as `"true"`/`"false"`
+    Check,
+    /// Free-form text
+    Text,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ConfigOption {
+    pub key: &'static str,
+    pub label: &'static str,
+    pub kind: OptionKind,
+    /// Only shown with "Show advanced options"
+    pub advanced: bool,
+    /// `_cpusched` values this tunable applies to; empty for all
+    pub schedulers: &'static [&'static str],
+    /// Hover text explaining the option
+    pub help: Option<&'static str>,
+    /// Only some linux-tkg releases have this key: shown only when the file
+    /// sets it, and never reported as missing
+    pub if_present: bool,
+    /// Only shown while another key has this value, e.g. a level that only
+    /// means something for one compressor
+    pub shown_when: Option<(&'static str, &'static str)>,
+}
+
+impl ConfigOption {
+    const fn advanced(mut self) -> Self {
+        self.advanced = true;
+        self
+    }
+
+    const fn for_schedulers(mut self, schedulers: &'static [&'static str]) -> Self {
+        self.schedulers = schedulers;
+        self
+    }
+
+    const fn help(mut self, help: &'static str) -> Self {
+        self.help = Some(help);
+        self
+    }
+
+    const fn if_present(mut self) -> Self {
+        self.if_present = true;
+        self
+    }
+
+    const fn shown_when(mut self, key: &'static str, value: &'static str) -> Self {
+        self.shown_when = Some((key, value));
+        self
+    }
+
+    /// Whether the option applies with `cpusched` selected
+    pub fn applies_to_scheduler(&self, cpusched: &str) -> bool {
+        self.schedulers.is_empty() || self.schedulers.contains(&cpusched)
+    }
+
+    /// Whether the key or label contains `query`, which must be lowercase;
+    /// an empty query matches everything
+    pub fn matches(&self, query: &str) -> bool {
+        query.is_empty()
+            || self.key.to_lowercase().contains(query)
+            || self.label.to_lowercase().contains(query)
+    }
+
+    /// Value the tab displays when the key is absent from the file
+    pub fn default_value(&self) -> &'static str {
+        match self.kind {
+            OptionKind::Check => "false",
+            OptionKind::Combo(_) | OptionKind::Text => "",
+        }
+    }
+}
+
+pub struct OptionSection {
+    pub title: &'static str,
+    pub default_open: bool,
+    /// Whole section only shown with "Show advanced options"
+    pub advanced: bool,
+    pub options: &'static [ConfigOption],
+}
+
+const fn combo(
+    key: &'static str,
+    label: &'static str,
+    choices: &'static [(&'static str, &'static str)],
+) -> ConfigOption {
+    ConfigOption {
+        key,
+        label,
+        kind: OptionKind::Combo(choices),
+        advanced: false,
+        schedulers: &[],
+        help: None,
+        if_present: false,
+        shown_when: None,
+    }
+}
+
+const fn check(key: &'static str, label: &'static str) -> ConfigOption {
+    ConfigOption {
+        key,
+        label,
+        kind: OptionKind::Check,
+        advanced: false,
+        schedulers: &[],
+        help: None,
+        if_present: false,
+        shown_when: None,
+    }
+}
+
+const fn text(key: &'static str, label: &'static str) -> ConfigOption {
+    ConfigOption {
+        key,
+        label,
+        kind: OptionKind::Text,
+        advanced: false,
+        schedulers: &[],
+        help: None,
+        if_present: false,
+        shown_when: None,
+    }
+}
+
+/// Options whose value names a compressor that must be installed
+pub const COMPRESSOR_KEYS: &[&str] = &["_kernel_compression", "_module_compression"];
+
+/// The program a compressor choice needs at build time, if any
+pub fn compressor_program(value: &str) -> Option<&'static str> {
+    match value {
+        "gzip" => Some("gzip"),
+        "xz" => Some("xz"),
+        "lz4" => Some("lz4"),
+        "zstd" => Some("zstd"),
+        _ => None,
+    }
+}
+
+const ZSTD_LEVELS: &[(&str, &str)] = &[
+    ("1", "1 (fastest)"),
+    ("3", "3"),
+    ("6", "6"),
+    ("9", "9"),
+    ("12", "12"),
+    ("15", "15"),
+    ("19", "19 (smallest)"),
+];
+
+/// Every option the Config tab knows about
+pub fn all_options() -> impl Iterator<Item = &'static ConfigOption> {
+    SECTIONS.iter().flat_map(|s| s.options.iter())
+}
+
+/// How an option appears in the build summary
+enum Chip {
+    /// The combo choice's label, e.g. "Thin LTO"
+    Label,
+    /// The raw value, e.g. "znver4"
+    Value,
+    /// "name: label", e.g. "TCP: BBR"
+    Named(&'static str),
+    /// Fixed text while the checkbox is on
+    Flag(&'static str),
+}
+
+/// The most impactful options, in summary order
+static SUMMARY: &[(&str, Chip)] = &[
+    ("_cpusched", Chip::Label),
+    ("_timer_freq", Chip::Label),
+    ("_tickless", Chip::Named("tickless")),
+    ("_processor_opt", Chip::Value),
+    ("_compiler", Chip::Label),
+    ("_lto_mode", Chip::Label),
+    ("_default_cpu_gov", Chip::Named("governor")),
+    ("_tcp_cong_alg", Chip::Named("TCP")),
+    ("_fsync_backport", Chip::Flag("fsync")),
+    ("_ntsync", Chip::Flag("NTSync")),
+    ("_mglru", Chip::Flag("MGLRU")),
+    ("_preempt_rt", Chip::Flag("PREEMPT_RT")),
+    ("_acs_override", Chip::Flag("ACS override")),
+    ("_clear_patches", Chip::Flag("Clear Linux")),
+    ("_zenify", Chip::Flag("Zenify")),
+    ("_modprobeddb", Chip::Flag("modprobed-db")),
+];
+
+/// One-glance chips for the configured values, e.g. ["EEVDF", "1000 Hz",
+/// "Thin LTO", "fsync"]. Defaults and disabled options are left out.
+pub fn summary_chips(value: impl Fn(&str) -> Option<String>) -> Vec<String> {
+    let label = |key: &str, v: &str| {
+        all_options()
+            .find(|o| o.key == key)
+            .and_then(|o| match o.kind {
+                OptionKind::Combo(choices) => choices.iter().find(|(c, _)| *c == v),
+                _ => None,
+            })
+            .map(|(_, l)| *l)
+            .filter(|l| !matches!(*l, "Default" | "Disabled"))
+    };
+    SUMMARY
+        .iter()
+        .filter_map(|(key, chip)| {
+            let v = value(key).unwrap_or_default();
+            match chip {
+                Chip::Label => label(key, &v).map(str::to_string),
+                Chip::Value => Some(v).filter(|v| !v.is_empty()),
+                Chip::Named(name) => label(key, &v).map(|l| format!("{}: {}", name, l)),
+                Chip::Flag(text) => (v == "true" || v == "1").then(|| text.to_string()),
+            }
+        })
+        .collect()
+}
+
+/// Schedulers whose sched_yield and round-robin interval are tunable
+const YIELD_RR_SCHEDULERS: &[&str] = &["pds", "bmq", "upds", "muqss"];
+
+pub static SECTIONS: &[OptionSection] = &[
+    OptionSection {
+        title: "CPU Scheduling",
+        default_open: true,
+        advanced: false,
+        options: &[
+            combo("_cpusched", "CPU Scheduler", &[
+                ("", "Default"),
+                ("pds", "PDS"),
+                ("bmq", "BMQ"),
+                ("bore", "BORE"),
+                ("cfs", "CFS"),
+                ("eevdf", "EEVDF"),
+                ("upds", "UPDS"),
+                ("muqss", "MuQSS"),
+            ]),
+            combo("_sched_yield_type", "Sched Yield Type", &[
+                ("0", "No yield"),
+                ("1", "Yield to better priority (default)"),
+                ("2", "Expire timeslice"),
+            ]).for_schedulers(YIELD_RR_SCHEDULERS),
+            combo("_rr_interval", "Round Robin Interval", &[
+                ("default", "Default"),
+                ("2", "2ms"),
+                ("4", "4ms"),
+                ("6", "6ms"),
+                ("8", "8ms"),
+            ]).for_schedulers(YIELD_RR_SCHEDULERS),
+            combo("_rqshare", "RQ Share", &[
+                ("none", "None"),
+                ("smt", "SMT"),
+                ("mc", "MC"),
+                ("mc-llc", "MC-LLC"),
+                ("smp", "SMP"),
+                ("all", "All"),
+            ]).for_schedulers(&["pds", "bmq", "upds"]),
+            check("_smt_nice", "SMT Nice").for_schedulers(&["pds", "upds", "muqss"]),
+        ],
+    },
+    OptionSection {
+        title: "Compiler",
+        default_open: true,
+        advanced: false,
+        options: &[
+            combo("_compiler", "Compiler", &[
+                ("", "GCC"),
+                ("llvm", "LLVM/Clang"),
+            ]),
+            combo("_compileroptlevel", "Optimization Level", &[
+                ("1", "-O2"),
+                ("2", "-O3"),
+                ("3", "-Os"),
+            ]),
+            combo("_lto_mode", "LTO Mode", &[
+                ("", "Default"),
+                ("no", "Disabled"),
+                ("full", "Full LTO"),
+                ("thin", "Thin LTO"),
+            ]),
+            combo("_llvm_ias", "LLVM Integrated Assembler", &[
+                ("0", "Disabled"),
+                ("1", "Enabled"),
+            ]).advanced(),
+        ],
+    },
+    OptionSection {
+        title: "Kernel Version & Source",
+        default_open: true,
+        advanced: false,
+        options: &[
+            text("_version", "Kernel Version"),
+            combo("_git_mirror", "Git Mirror", &[
+                ("kernel.org", "kernel.org"),
+                ("googlesource.com", "googlesource.com"),
+                ("gregkh", "gregkh"),
+                ("torvalds", "torvalds"),
+            ]),
+            combo("_distro", "Distribution", &[
+                ("Arch", "Arch"),
+                ("Ubuntu", "Ubuntu"),
+                ("Debian", "Debian"),
+                ("Fedora", "Fedora"),
+                ("Suse", "Suse"),
+                ("Gentoo", "Gentoo"),
+                ("Generic", "Generic"),
+            ]),
+        ],
+    },
+    OptionSection {
+        title: "CPU & Performance",
+        default_open: false,
+        advanced: false,
+        options: &[
+            combo("_processor_opt", "Processor Optimization", &[
+                ("", "Default"),
+                ("x86-64", "x86-64 (baseline)"),
+                ("x86-64-v2", "x86-64-v2 (~2008+)"),
+                ("x86-64-v3", "x86-64-v3 (~2013+)"),
+                ("x86-64-v4", "x86-64-v4 (Skylake/Zen4+)"),
+                ("native", "Native (auto-detect)"),
+                ("znver5", "Zen 5 (Ryzen 9000)"),
+                ("znver4", "Zen 4 (Ryzen 7000/8000)"),
+                ("znver3", "Zen 3 (Ryzen 5000/6000)"),
+                ("znver2", "Zen 2 (Ryzen 3000/4000)"),
+                ("znver1", "Zen 1 (Ryzen 1000/2000)"),
+                ("arrowlake-s", "Arrow Lake-S (Core Ultra 200)"),
+                ("raptorlake", "Raptor Lake (13th/14th gen)"),
+                ("alderlake", "Alder Lake (12th gen)"),
+                ("skylake", "Skylake (6th-9th gen)"),
+            ]),
+            combo("_timer_freq", "Timer Frequency", &[
+                ("100", "100 Hz"),
+                ("250", "250 Hz"),
+                ("300", "300 Hz"),
+                ("500", "500 Hz"),
+                ("750", "750 Hz"),
+                ("1000", "1000 Hz"),
+            ]),
+            combo("_tickless", "Tickless Mode", &[
+                ("0", "Periodic"),
+                ("1", "Full"),
+                ("2", "Idle"),
+            ]).advanced(),
+            combo("_tcp_cong_alg", "TCP Congestion Algorithm", &[
+                ("", "Default"),
+                ("yeah", "YeAH"),
+                ("bbr", "BBR"),
+                ("cubic", "CUBIC"),
+                ("reno", "Reno"),
+                ("vegas", "Vegas"),
+                ("westwood", "Westwood"),
+            ]).advanced(),
+            combo("_default_cpu_gov", "Default CPU Governor", &[
+                ("", "Default"),
+                ("performance", "Performance"),
+                ("ondemand", "Ondemand"),
+                ("schedutil", "Schedutil"),
+            ]).advanced(),
+        ],
+    },
+    OptionSection {
+        title: "Patches & Features",
+        default_open: false,
+        advanced: false,
+        options: &[
+            check("_user_patches", "User Patches"),
+            check("_user_patches_no_confirm", "Skip User Patch Confirm"),
+            text("_community_patches", "Community Patches"),
+            check("_clear_patches", "Clear Linux Patches"),
+            check("_openrgb", "OpenRGB"),
+            check("_acs_override", "ACS Override"),
+            check("_preempt_rt", "PREEMPT_RT"),
+            check("_fsync_backport", "Fsync Backport"),
+            check("_fsync_legacy", "Fsync Legacy"),
+            check("_ntsync", "NTSync"),
+            check("_zenify", "Zenify"),
+            check("_glitched_base", "Glitched Base"),
+            check("_mglru", "MGLRU (Multi-Gen LRU)"),
+            check("_irq_threading", "Force IRQ Threading"),
+            check("_random_trust_cpu", "Trust CPU RNG"),
+        ],
+    },
+    OptionSection {
+        title: "Configuration Management",
+        default_open: false,
+        advanced: true,
+        options: &[
+            text("_configfile", "Config File Path"),
+            combo("_config_updating", "Config Updating", &[
+                ("olddefconfig", "olddefconfig"),
+                ("oldconfig", "oldconfig"),
+            ]),
+            text("_kernel_work_folder", "Kernel Work Folder"),
+            text("_kernel_source_folder", "Kernel Source Folder"),
+            check("_offline", "Offline Mode").help(
+                "Build without network access: linux-tkg skips fetching the kernel git tree \
+                 and uses the checkout and patches already on disk. Only enable it after a \
+                 successful online build, or the build fails while preparing sources.",
+            ),
+            check("_nofallback", "No Fallback (exit on error)").help(
+                "Stop the build when a step fails instead of falling back to a default \
+                 (e.g. a stock config or an unpatched tree). Safer for reproducible builds, \
+                 but less forgiving of missing files.",
+            ),
+        ],
+    },
+    OptionSection {
+        title: "Build & Debug",
+        default_open: false,
+        advanced: true,
+        options: &[
+            check("_debugdisable", "Disable Debug"),
+            check("_STRIP", "Strip Binaries"),
+            check("_ftracedisable", "Disable Ftrace"),
+            check("_numadisable", "Disable NUMA"),
+            check("_misc_adds", "Misc Additions"),
+            check("_kernel_on_diet", "Kernel on Diet"),
+            check("_modprobeddb", "modprobed-db"),
+            text("_modprobeddb_db_path", "modprobed-db Path"),
+            check("_config_fragments", "Config Fragments"),
+            check("_config_fragments_no_confirm", "Skip Config Fragments Confirm"),
+            check("_NUKR", "NUKR"),
+            check("_force_all_threads", "Force All Threads"),
+            combo("_menunconfig", "Menu Config", &[
+                ("0", "Disabled"),
+                ("1", "menuconfig"),
+                ("2", "nconfig"),
+                ("3", "xconfig"),
+            ]),
+            combo("_install_after_building", "Install After Building", &[
+                ("prompt", "Prompt"),
+                ("true", "Yes"),
+                ("false", "No"),
+            ]),
+            text("_NR_CPUS_value", "Max CPUs (NR_CPUS)"),
+        ],
+    },
+    OptionSection {
+        title: "Compression",
+        default_open: false,
+        advanced: false,
+        options: &[
+            combo("_kernel_compression", "Kernel Image", &[
+                ("gzip", "gzip"),
+                ("lz4", "LZ4"),
+                ("xz", "XZ"),
+                ("zstd", "Zstandard"),
+            ])
+            .if_present()
+            .help("LZ4 boots fastest, XZ gives the smallest image"),
+            combo("_kernel_compression_level", "Kernel zstd Level", ZSTD_LEVELS)
+                .if_present()
+                .shown_when("_kernel_compression", "zstd"),
+            combo("_module_compression", "Modules", &[
+                ("none", "None"),
+                ("gzip", "gzip"),
+                ("xz", "XZ"),
+                ("zstd", "Zstandard"),
+            ])
+            .if_present()
+            .help("Compressed modules take less space in /usr/lib/modules"),
+            combo("_module_compression_level", "Module zstd Level", ZSTD_LEVELS)
+                .if_present()
+                .shown_when("_module_compression", "zstd")
+                .help("Higher levels shrink modules further at the cost of build time"),
+        ],
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn option_keys_are_unique() {
+        let mut seen = HashSet::new();
+        for option in all_options() {
+            assert!(seen.insert(option.key), "duplicate option {}", option.key);
+        }
+    }
+
+    #[test]
+    fn scheduler_tunables_follow_cpusched() {
+        let rr = all_options().find(|o| o.key == "_rr_interval").unwrap();
+        assert!(rr.applies_to_scheduler("pds"));
+        assert!(!rr.applies_to_scheduler("bore"));
+        let compiler = all_options().find(|o| o.key == "_compiler").unwrap();
+        assert!(compiler.applies_to_scheduler("bore"));
+    }
+
+    #[test]
+    fn search_matches_key_or_label() {
+        let tcp = all_options().find(|o| o.key == "_tcp_cong_alg").unwrap();
+        assert!(tcp.matches(""));
+        assert!(tcp.matches("tcp_cong"));
+        assert!(tcp.matches(&tcp.label.to_lowercase()));
+        assert!(!tcp.matches("zzz"));
+    }
+
+    #[test]
+    fn compressor_choices_name_installable_programs() {
+        for key in COMPRESSOR_KEYS {
+            let option = all_options().find(|o| o.key == *key).unwrap();
+            let OptionKind::Combo(choices) = option.kind else {
+                panic!("{} is not a combo", key);
+            };
+            for (value, _) in choices.iter().filter(|(v, _)| *v != "none") {
+                assert!(compressor_program(value).is_some(), "{} = {}", key, value);
+            }
+        }
+    }
+
+    #[test]
+    fn summary_lists_impactful_non_default_values() {
+        let values: std::collections::HashMap<&str, &str> = [
+            ("_cpusched", "eevdf"),
+            ("_timer_freq", "1000"),
+            ("_processor_opt", "znver4"),
+            ("_lto_mode", "no"),
+            ("_fsync_backport", "true"),
+            ("_ntsync", "false"),
+            ("_tcp_cong_alg", "bbr"),
+        ]
+        .into_iter()
+        .collect();
+        let chips = summary_chips(|k| values.get(k).map(|v| v.to_string()));
+        assert_eq!(chips, vec!["EEVDF", "1000 Hz", "znver4", "GCC", "TCP: BBR", "fsync"]);
+    }
+}
@@ -0,0 +1,71 @@
+/// Recommended customization.cfg defaults for a kernel series
+#[derive(Clone, Debug)]
+pub struct ConfigTemplate {
+    pub series: &'static str,
+    pub description: &'static str,
+    /// `(key, value)` pairs to apply
+    pub values: &'static [(&'static str, &'static str)],
+}
+
+/// Look up the template for a kernel series (e.g., "6.13")
+pub fn template_for_series(series: &str) -> Option<&'static ConfigTemplate> {
+    TEMPLATES.iter().find(|t| t.series == series)
+}
+
+/// One-click "gaming kernel" preset: low-latency scheduler and timer,
+/// fsync/NTSync for Wine/Proton, MGLRU, performance governor and thin LTO.
+pub static GAMING_PRESET: &[(&str, &str)] = &[
+    ("_cpusched", "bore"),
+    ("_timer_freq", "1000"),
+    ("_tickless", "2"),
+    ("_fsync_backport", "true"),
+    ("_ntsync", "true"),
+    ("_mglru", "true"),
+    ("_default_cpu_gov", "performance"),
+    ("_compiler", "llvm"),
+    ("_lto_mode", "thin"),
+];
+
+static TEMPLATES: &[ConfigTemplate] = &[
+    ConfigTemplate {
+        series: "6.1",
+        description: "LTS: PDS scheduler, 500 Hz timer",
+        values: &[("_cpusched", "pds"), ("_timer_freq", "500"), ("_tickless", "2")],
+    },
+    ConfigTemplate {
+        series: "6.6",
+        description: "LTS: EEVDF (new upstream default), 500 Hz timer",
+        values: &[("_cpusched", "eevdf"), ("_timer_freq", "500"), ("_tickless", "2")],
+    },
+    ConfigTemplate {
+        series: "6.12",
+        description: "LTS: BORE scheduler, 1000 Hz timer, MGLRU",
+        values: &[
+            ("_cpusched", "bore"),
+            ("_timer_freq", "1000"),
+            ("_tickless", "2"),
+            ("_mglru", "true"),
+        ],
+    },
+    ConfigTemplate {
+        series: "6.13",
+        description: "BORE scheduler, 1000 Hz timer, MGLRU",
+        values: &[
+            ("_cpusched", "bore"),
+            ("_timer_freq", "1000"),
+            ("_tickless", "2"),
+            ("_mglru", "true"),
+        ],
+    },
+    ConfigTemplate {
+        series: "6.14",
+        description: "BORE scheduler, 1000 Hz timer, MGLRU, NTSync",
+        values: &[
+            ("_cpusched", "bore"),
+            ("_timer_freq", "1000"),
+            ("_tickless", "2"),
+            ("_mglru", "true"),
+            ("_ntsync", "true"),
+        ],
+    },
+];
@@ -1 +1,3 @@
 pub mod catalog;
+pub mod config_options;
+pub mod config_templates;
@@ -5,6 +5,12 @@ mod settings;
 mod tabs;
 
 fn main() -> eframe::Result<()> {
+    // Refuse to scatter config and data into the working directory
+    if let Err(e) = settings::check_dirs() {
+        eprintln!("tkg-gui: {}", e);
+        std::process::exit(1);
+    }
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_title("TKG Kernel Builder")
@@ -15,6 +21,6 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "TKG Kernel Builder",
         options,
-        Box::new(|_cc| Ok(Box::new(app::TkgApp::new()))),
+        Box::new(|cc| Ok(Box::new(app::TkgApp::new(&cc.egui_ctx)))),
     )
 }
@@ -1,46 +1,323 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-fn home_dir() -> PathBuf {
-    if let Ok(home) = std::env::var("HOME") {
-        PathBuf::from(home)
-    } else {
-        PathBuf::from(".")
+/// An absolute path from `var`, ignoring unset, empty and relative values
+fn absolute_env(var: &str) -> Option<PathBuf> {
+    absolute(std::env::var_os(var))
+}
+
+fn absolute(value: Option<std::ffi::OsString>) -> Option<PathBuf> {
+    value.map(PathBuf::from).filter(|p| p.is_absolute())
+}
+
+/// Home directory of the current user from the password database, for
+/// environments that don't set `$HOME`
+fn passwd_home() -> Option<PathBuf> {
+    use std::ffi::CStr;
+    use std::os::unix::ffi::OsStrExt;
+    let mut buf = vec![0 as libc::c_char; 4096];
+    // SAFETY: passwd and result are valid out-pointers and buf outlives the
+    // returned pw_dir, which points into it
+    unsafe {
+        let mut passwd: libc::passwd = std::mem::zeroed();
+        let mut result = std::ptr::null_mut();
+        let rc = libc::getpwuid_r(
+            libc::getuid(),
+            &mut passwd,
+            buf.as_mut_ptr(),
+            buf.len(),
+            &mut result,
+        );
+        if rc != 0 || result.is_null() || passwd.pw_dir.is_null() {
+            return None;
+        }
+        let dir = CStr::from_ptr(passwd.pw_dir).to_bytes();
+        Some(PathBuf::from(std::ffi::OsStr::from_bytes(dir))).filter(|p| p.is_absolute())
+    }
+}
+
+/// The user's home directory: `$HOME`, else the password database entry.
+/// Never falls back to the working directory.
+pub fn home_dir() -> Result<PathBuf, String> {
+    absolute_env("HOME")
+        .or_else(passwd_home)
+        .ok_or_else(|| "Cannot determine the home directory: $HOME is not set".to_string())
+}
+
+/// `$<var>` if it holds an absolute path, else `<home>/<fallback>`, as the
+/// XDG base directory spec describes
+fn xdg_dir(var: &str, fallback: &str) -> Result<PathBuf, String> {
+    resolve_xdg(var, std::env::var_os(var), home_dir, fallback)
+}
+
+fn resolve_xdg(
+    var: &str,
+    value: Option<std::ffi::OsString>,
+    home: impl FnOnce() -> Result<PathBuf, String>,
+    fallback: &str,
+) -> Result<PathBuf, String> {
+    match absolute(value) {
+        Some(dir) => Ok(dir),
+        None => home()
+            .map(|home| home.join(fallback))
+            .map_err(|e| format!("{} (or set ${})", e, var)),
+    }
+}
+
+/// Fail early, before anything is written, if the config, data or cache
+/// directory can't be resolved
+pub fn check_dirs() -> Result<(), String> {
+    xdg_dir("XDG_CONFIG_HOME", ".config")?;
+    xdg_dir("XDG_DATA_HOME", ".local/share")?;
+    xdg_dir("XDG_CACHE_HOME", ".cache")?;
+    Ok(())
+}
+
+/// `xdg_dir(..)/tkg-gui`; [`check_dirs`] runs at startup, so this resolves
+fn app_dir(var: &str, fallback: &str) -> PathBuf {
+    match xdg_dir(var, fallback) {
+        Ok(dir) => dir.join("tkg-gui"),
+        Err(e) => panic!("{}", e),
     }
 }
 
+/// `path` with `$VAR` and `${VAR}` replaced from the current environment,
+/// so a stored `$HOME/src` follows the machine it is used on. Unset
+/// variables are left as written.
+pub fn expand_path(path: &Path) -> PathBuf {
+    PathBuf::from(expand_vars(&path.to_string_lossy(), |var| std::env::var(var).ok()))
+}
+
+fn expand_vars(text: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, len) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            },
+            None => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+        match lookup(name).filter(|_| !name.is_empty()) {
+            Some(value) => out.push_str(&value),
+            None => out.push_str(&rest[start..start + 1 + len]),
+        }
+        rest = &after[len..];
+    }
+    out.push_str(rest);
+    out
+}
+
 fn default_linux_tkg_path() -> PathBuf {
-    home_dir()
-        .join(".local")
-        .join("share")
-        .join("tkg-gui")
-        .join("linux-tkg")
+    AppSettings::data_dir().join("linux-tkg")
+}
+
+fn default_kernel_sources_dir() -> PathBuf {
+    AppSettings::cache_dir().join("kernel-sources")
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_build_logs_keep() -> usize {
+    20
+}
+
+fn default_min_free_space_gb() -> u64 {
+    10
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct AppSettings {
     #[serde(default = "default_linux_tkg_path")]
     pub linux_tkg_path: PathBuf,
+    /// Kernel series to treat as supported by linux-tkg. Empty means detect
+    /// from the clone (falling back to a built-in list).
+    #[serde(default)]
+    pub supported_series_override: Vec<String>,
+    /// Show low-level config groups in the Config tab
+    #[serde(default)]
+    pub show_advanced_config: bool,
+    /// Keep kernel downloads outside the temporary work dir so they survive restarts
+    #[serde(default = "default_true")]
+    pub persist_kernel_sources: bool,
+    /// Persistent kernel download location, used when `persist_kernel_sources` is set
+    #[serde(default = "default_kernel_sources_dir")]
+    pub kernel_sources_dir: PathBuf,
+    /// Sanity-check extracted kernel trees (key files, Makefile version)
+    #[serde(default = "default_true")]
+    pub verify_kernel_tree: bool,
+    /// Keep the downloaded `.tar.xz` so the tree can be re-extracted
+    /// without downloading again
+    #[serde(default = "default_true")]
+    pub keep_kernel_tarball: bool,
+    /// Run builds under a pseudo-terminal instead of piped stdio
+    #[serde(default)]
+    pub build_use_pty: bool,
+    /// URL of a remote catalog index (JSON) merged into the patch catalog.
+    /// Empty disables remote catalog refresh.
+    #[serde(default)]
+    pub catalog_index_url: String,
+    /// Directory the Build tab builds in. `None` uses the linux-tkg work dir.
+    #[serde(default)]
+    pub build_dir: Option<PathBuf>,
+    /// Verbosity of the app's own log file (off/error/warn/info/debug)
+    #[serde(default = "default_log_level")]
+    pub log_level: String,
+    /// Build with `makepkg -s` and ask before installing the packages
+    #[serde(default = "default_true")]
+    pub confirm_install: bool,
+    /// Build the existing `src/` with `makepkg --noextract`, leaving local
+    /// source edits alone
+    #[serde(default)]
+    pub build_keep_sources: bool,
+    /// Show build warnings as errors in the log view (display only)
+    #[serde(default)]
+    pub warnings_as_errors: bool,
+    /// Delete makepkg's src/, pkg/ and old packages before building
+    #[serde(default)]
+    pub build_clean: bool,
+    /// `make -j` for builds; 0 leaves MAKEFLAGS as inherited
+    #[serde(default)]
+    pub build_jobs: usize,
+    /// `make -l`: start no new jobs while the load average is above this
+    #[serde(default)]
+    pub build_load_limit: Option<f64>,
+    /// Build output files kept in `data_dir()/build-logs`; 0 keeps all
+    #[serde(default = "default_build_logs_keep")]
+    pub build_logs_keep: usize,
+    /// Kernel downloads and builds refuse to start with less free space (GiB);
+    /// 0 disables the check
+    #[serde(default = "default_min_free_space_gb")]
+    pub min_free_space_gb: u64,
+    /// Keep only this many kernel downloads in the persistent cache; 0 keeps all
+    #[serde(default)]
+    pub kernel_keep_downloads: usize,
+    /// Ask before pruning old kernel downloads
+    #[serde(default = "default_true")]
+    pub confirm_kernel_prune: bool,
+    /// Command run before makepkg/install.sh; empty disables
+    #[serde(default)]
+    pub pre_build_command: String,
+    /// Skip the build when the pre-build command exits non-zero
+    #[serde(default = "default_true")]
+    pub pre_build_abort_on_failure: bool,
+    /// Command run after a successful build (and confirmed install); empty disables
+    #[serde(default)]
+    pub post_build_command: String,
+    /// Run the post-build command even when the build failed
+    #[serde(default)]
+    pub post_build_on_failure: bool,
+    /// Parent of the work directory instead of the system temp dir; takes
+    /// effect on the next start
+    #[serde(default)]
+    pub work_dir_override: Option<PathBuf>,
+    /// Userpatches directory to use instead of the detected one
+    #[serde(default)]
+    pub patch_dir_override: Option<PathBuf>,
+    /// Open the getting-started wizard on startup
+    #[serde(default = "default_true")]
+    pub show_wizard: bool,
+    /// FIFO or Unix socket that build output is also written to, so it can
+    /// be followed from a terminal or another tool
+    #[serde(default)]
+    pub build_output_mirror: Option<PathBuf>,
+    /// Show the download size and ask before cloning linux-tkg
+    #[serde(default = "default_true")]
+    pub confirm_clone: bool,
+    /// When linux-tkg was last cloned or fetched successfully
+    #[serde(default)]
+    pub linux_tkg_updated: Option<DateTime<Utc>>,
+    /// Kernel version selected when the app was last used
+    #[serde(default)]
+    pub last_selected_version: Option<String>,
+    /// Tab open when the app was last used; see `Tab::name`
+    #[serde(default)]
+    pub last_active_tab: String,
 }
 
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
             linux_tkg_path: default_linux_tkg_path(),
+            supported_series_override: Vec::new(),
+            show_advanced_config: false,
+            persist_kernel_sources: true,
+            kernel_sources_dir: default_kernel_sources_dir(),
+            verify_kernel_tree: true,
+            keep_kernel_tarball: true,
+            build_use_pty: false,
+            catalog_index_url: String::new(),
+            build_dir: None,
+            log_level: default_log_level(),
+            confirm_install: true,
+            build_keep_sources: false,
+            warnings_as_errors: false,
+            build_clean: false,
+            build_jobs: 0,
+            build_load_limit: None,
+            build_logs_keep: default_build_logs_keep(),
+            min_free_space_gb: default_min_free_space_gb(),
+            kernel_keep_downloads: 0,
+            confirm_kernel_prune: true,
+            pre_build_command: String::new(),
+            pre_build_abort_on_failure: true,
+            post_build_command: String::new(),
+            post_build_on_failure: false,
+            work_dir_override: None,
+            patch_dir_override: None,
+            show_wizard: true,
+            build_output_mirror: None,
+            confirm_clone: true,
+            linux_tkg_updated: None,
+            last_selected_version: None,
+            last_active_tab: String::new(),
         }
     }
 }
 
 impl AppSettings {
-    /// Directory for app configuration files: ~/.config/tkg-gui/
+    /// Where build output is saved, one file per build
+    pub fn build_logs_dir() -> PathBuf {
+        Self::data_dir().join("build-logs")
+    }
+
+    /// `min_free_space_gb` in bytes
+    pub fn min_free_space(&self) -> u64 {
+        self.min_free_space_gb.saturating_mul(1024 * 1024 * 1024)
+    }
+
+    /// Directory for app configuration files: $XDG_CONFIG_HOME/tkg-gui/
+    /// (~/.config/tkg-gui/)
     pub fn config_dir() -> PathBuf {
-        home_dir().join(".config").join("tkg-gui")
+        app_dir("XDG_CONFIG_HOME", ".config")
     }
 
-    /// Directory for app data files (patch registry, etc.): ~/.local/share/tkg-gui/
+    /// Directory for app data files (patch registry, etc.): $XDG_DATA_HOME/tkg-gui/
+    /// (~/.local/share/tkg-gui/)
     pub fn data_dir() -> PathBuf {
-        home_dir().join(".local").join("share").join("tkg-gui")
+        app_dir("XDG_DATA_HOME", ".local/share")
+    }
+
+    /// Directory for re-creatable downloads: $XDG_CACHE_HOME/tkg-gui/
+    /// (~/.cache/tkg-gui/)
+    pub fn cache_dir() -> PathBuf {
+        app_dir("XDG_CACHE_HOME", ".cache")
     }
 
     /// Path where linux-tkg is installed by the system package (PKGBUILD).
@@ -57,7 +334,6 @@ impl AppSettings {
         }
     }
 
-    #[allow(dead_code)]
     pub fn save(&self) -> Result<(), String> {
         let dir = Self::config_dir();
         fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
@@ -66,8 +342,60 @@ impl AppSettings {
         fs::write(&path, content).map_err(|e| e.to_string())
     }
 
+    /// `linux_tkg_path` with environment variables expanded
+    pub fn linux_tkg_dir(&self) -> PathBuf {
+        expand_path(&self.linux_tkg_path)
+    }
+
+    /// `kernel_sources_dir` with environment variables expanded
+    pub fn kernel_sources(&self) -> PathBuf {
+        expand_path(&self.kernel_sources_dir)
+    }
+
     /// Returns true if linux-tkg appears to be cloned at linux_tkg_path
     pub fn is_cloned(&self) -> bool {
-        self.linux_tkg_path.join("customization.cfg").exists()
+        self.linux_tkg_dir().join("customization.cfg").exists()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn home() -> Result<PathBuf, String> {
+        Ok(PathBuf::from("/home/user"))
+    }
+
+    #[test]
+    fn xdg_overrides_win_over_home_defaults() {
+        let data = |value: Option<&str>| {
+            resolve_xdg("XDG_DATA_HOME", value.map(Into::into), home, ".local/share")
+        };
+        assert_eq!(data(None), Ok(PathBuf::from("/home/user/.local/share")));
+        assert_eq!(data(Some("/tmp/xdg-data")), Ok(PathBuf::from("/tmp/xdg-data")));
+        // The spec says empty and relative values are ignored
+        assert_eq!(data(Some("")), Ok(PathBuf::from("/home/user/.local/share")));
+        assert_eq!(data(Some("rel/data")), Ok(PathBuf::from("/home/user/.local/share")));
+
+        let no_home = || Err("no home".to_string());
+        assert!(resolve_xdg("XDG_CONFIG_HOME", None, no_home, ".config").is_err());
+        assert_eq!(
+            resolve_xdg("XDG_CONFIG_HOME", Some("/cfg".into()), no_home, ".config"),
+            Ok(PathBuf::from("/cfg"))
+        );
+    }
+
+    #[test]
+    fn paths_expand_set_variables_only() {
+        let lookup = |var: &str| match var {
+            "HOME" => Some("/home/me".to_string()),
+            "XDG_DATA_HOME" => Some("/data".to_string()),
+            _ => None,
+        };
+        assert_eq!(expand_vars("$HOME/src", lookup), "/home/me/src");
+        assert_eq!(expand_vars("${XDG_DATA_HOME}/tkg", lookup), "/data/tkg");
+        assert_eq!(expand_vars("$HOME-${HOME}", lookup), "/home/me-/home/me");
+        assert_eq!(expand_vars("$NOPE/x/${NOPE}", lookup), "$NOPE/x/${NOPE}");
+        assert_eq!(expand_vars("/a$/b${HOME", lookup), "/a$/b${HOME");
     }
 }
@@ -1,8 +1,25 @@
-use crate::core::build_manager::{self, BuildHandle, BuildMsg};
+use crate::core::bug_report::{self, BugReport, LOG_TAIL_LINES};
+use crate::core::build_manager::{self, BuildHandle, BuildMsg, LineKind, MissingDeps};
 use crate::core::config_manager::ConfigManager;
+use crate::core::kernel_downloader::format_bytes;
+use crate::core::{patch_manager, repo_manager, system, version, work_dir};
+use crate::data::config_options::summary_chips;
+use crate::settings::{self, AppSettings};
+use crate::tabs::settings::expansion_hint;
+use egui::text::{LayoutJob, TextFormat};
 use egui::{Context, RichText, Ui};
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Receiver};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Warn (and offer to stop) when the build dir's filesystem drops below this
+const LOW_SPACE_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+/// How often free space is re-checked while a build runs
+const SPACE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Log lines longer than this (in bytes) are cut short in the view until
+/// clicked; laying out megabyte-long lines stalls the UI
+const LONG_LINE: usize = 4000;
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum BuildState {
@@ -21,6 +38,222 @@ pub enum LogLevel {
     Input,
 }
 
+/// What the process behind `rx` is
+#[derive(Clone, Copy, PartialEq)]
+enum Step {
+    PreBuild,
+    Build,
+    Install,
+    PostBuild,
+}
+
+/// Parameters of a build, kept for "Rebuild"
+#[derive(Clone)]
+struct BuildParams {
+    dir: PathBuf,
+    confirm_install: bool,
+    keep_sources: bool,
+    /// Delete the output of earlier makepkg runs first, after confirming
+    clean: bool,
+    use_pty: bool,
+    makeflags: Option<String>,
+    pre_build_command: String,
+    pre_build_abort: bool,
+    /// Refuse to start with less free space than this, in bytes
+    min_free: u64,
+    /// Build logs to keep once this build's is written
+    logs_keep: usize,
+}
+
+impl BuildParams {
+    fn new(dir: &Path, settings: &AppSettings) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            confirm_install: settings.confirm_install,
+            keep_sources: settings.build_keep_sources,
+            clean: settings.build_clean && !settings.build_keep_sources,
+            use_pty: settings.build_use_pty,
+            makeflags: build_manager::make_flags(settings.build_jobs, settings.build_load_limit),
+            pre_build_command: settings.pre_build_command.trim().to_string(),
+            pre_build_abort: settings.pre_build_abort_on_failure,
+            min_free: settings.min_free_space(),
+            logs_keep: settings.build_logs_keep,
+        }
+    }
+}
+
+/// PKGBUILD of the build directory opened for inspection and editing
+struct PkgbuildEditor {
+    path: PathBuf,
+    text: String,
+    /// Content as last read or written, to tell unsaved edits apart
+    saved: String,
+    mtime: Option<SystemTime>,
+    status: String,
+}
+
+impl PkgbuildEditor {
+    fn open(path: PathBuf) -> Result<Self, String> {
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        Ok(Self {
+            mtime: file_mtime(&path),
+            saved: text.clone(),
+            text,
+            path,
+            status: String::new(),
+        })
+    }
+
+    /// Pick up edits made in an external editor, unless there are unsaved
+    /// ones here
+    fn check_disk(&mut self) {
+        let mtime = file_mtime(&self.path);
+        if mtime == self.mtime {
+            return;
+        }
+        if self.text != self.saved {
+            self.status = "Changed on disk; Reload discards the edits here.".to_string();
+            return;
+        }
+        self.reload();
+    }
+
+    fn reload(&mut self) {
+        match Self::open(self.path.clone()) {
+            Ok(fresh) => {
+                *self = fresh;
+                self.status = "Reloaded from disk.".to_string();
+            }
+            Err(e) => self.status = e,
+        }
+    }
+
+    fn save(&mut self) {
+        match std::fs::write(&self.path, &self.text) {
+            Ok(()) => {
+                self.saved = self.text.clone();
+                self.mtime = file_mtime(&self.path);
+                self.status = "Saved.".to_string();
+            }
+            Err(e) => self.status = format!("Save failed: {}", e),
+        }
+    }
+}
+
+fn file_mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// What ▶ Build would run, gathered without spawning anything
+struct BuildPreview {
+    /// `(label, value)` rows: command, directory, distro, version, ...
+    facts: Vec<(&'static str, String)>,
+    chips: Vec<String>,
+    /// `(ok, description)` prerequisite checks
+    checks: Vec<(bool, String)>,
+}
+
+impl BuildPreview {
+    fn gather(dir: &Path, settings: &AppSettings) -> Self {
+        let mut facts = Vec::new();
+        let mut checks = Vec::new();
+
+        let work_dir = build_manager::resolve_work_dir(dir);
+        let dir = work_dir.as_deref().unwrap_or(dir);
+        let config = ConfigManager::load(dir.join("customization.cfg")).ok();
+        let option = |key: &str| config.as_ref().and_then(|c| c.get_option(key));
+
+        let use_makepkg = detect_use_makepkg(dir);
+        let install = !(use_makepkg && settings.confirm_install);
+        let command =
+            build_manager::build_command_line(use_makepkg, install, settings.build_keep_sources);
+        facts.push(("Command", command.into()));
+        facts.push(("Directory", dir.display().to_string()));
+        facts.push((
+            "Method",
+            format!(
+                "{} (_distro={})",
+                if use_makepkg { "makepkg (Arch)" } else { "install.sh" },
+                option("_distro").unwrap_or_else(|| "unset".to_string())
+            ),
+        ));
+        facts.push((
+            "Kernel",
+            option("_version").filter(|v| !v.is_empty()).unwrap_or_else(|| "not set".into()),
+        ));
+        let pkgrel = use_makepkg.then(|| build_manager::pkgbuild_field(dir, "pkgrel")).flatten();
+        facts.push((
+            "Installs",
+            match build_manager::expected_release(|key| option(key), pkgrel.as_deref()) {
+                Some(release) => format!("This will install kernel {}", release),
+                None => "unknown until _version is set".to_string(),
+            },
+        ));
+        facts.push(("Terminal", if settings.build_use_pty { "PTY" } else { "pipes" }.into()));
+        if !settings.pre_build_command.trim().is_empty() {
+            facts.push(("Pre-build", settings.pre_build_command.trim().to_string()));
+        }
+        if !settings.post_build_command.trim().is_empty() {
+            facts.push(("Post-build", settings.post_build_command.trim().to_string()));
+        }
+        let makeflags = build_manager::make_flags(settings.build_jobs, settings.build_load_limit);
+        let env: Vec<String> = build_manager::BUILD_ENV_VARS
+            .iter()
+            .filter_map(|var| {
+                let value = match (*var, &makeflags) {
+                    ("MAKEFLAGS", Some(flags)) => format!("\"{}\" (from settings)", flags),
+                    _ => std::env::var(var).ok()?,
+                };
+                Some(format!("{}={}", var, value))
+            })
+            .collect();
+        facts.push((
+            "Environment",
+            if env.is_empty() { "inherited, no build overrides".into() } else { env.join(" ") },
+        ));
+
+        match &work_dir {
+            Ok(_) => match build_manager::validate_build_dir(dir) {
+                Ok(entry) => checks.push((true, format!("{} found", entry))),
+                Err(e) => checks.push((false, e)),
+            },
+            Err(e) => checks.push((false, e.clone())),
+        }
+        checks.push(match config {
+            Some(_) => (true, "customization.cfg readable".to_string()),
+            None => (false, "customization.cfg missing or unreadable".to_string()),
+        });
+        let compiler = option("_compiler").unwrap_or_default();
+        let tools = build_manager::required_tools(use_makepkg, &compiler);
+        let missing: Vec<&str> = tools
+            .iter()
+            .copied()
+            .filter(|t| system::find_in_path(t).is_none())
+            .collect();
+        checks.push(if missing.is_empty() {
+            (true, format!("Tools on PATH: {}", tools.join(", ")))
+        } else {
+            (false, format!("Not on PATH: {}", missing.join(", ")))
+        });
+        if let Some(free) = system::free_space(dir) {
+            checks.push((
+                free >= LOW_SPACE_BYTES,
+                format!("{} free in the build directory", format_bytes(free)),
+            ));
+        }
+        for problem in offline_problems(dir) {
+            checks.push((false, format!("_offline: {}", problem)));
+        }
+        if let Some(tool) = menunconfig_tool(dir) {
+            checks.push((false, format!("_menunconfig opens {}; needs a terminal", tool)));
+        }
+
+        let chips = summary_chips(|key| option(key));
+        Self { facts, chips, checks }
+    }
+}
+
 pub struct LogLine {
     pub text: String,
     pub level: LogLevel,
@@ -33,6 +266,66 @@ pub struct BuildTab {
     build_handle: Option<BuildHandle>,
     auto_scroll: bool,
     input_text: String,
+    copy_with_levels: bool,
+    // Which levels the log view shows, indexed by `LogLevel as usize`
+    shown_levels: [bool; LogLevel::ALL.len()],
+    log_search: String,
+    // Which match ◀/▶ are on, and whether to scroll it into view this frame
+    search_current: usize,
+    scroll_to_match: bool,
+    search_only_matches: bool,
+    // Error line ◀/▶ Errors last jumped to, and whether to scroll to it
+    error_focus: Option<usize>,
+    scroll_to_error: bool,
+    // Indices of over-long log lines shown in full
+    expanded_lines: HashSet<usize>,
+    // Resolved directory of the last makepkg run, used to install the
+    // packages it produced
+    last_makepkg_run: Option<PathBuf>,
+    built_packages: Vec<PathBuf>,
+    // Kernel release derived from the config at build start, and the one
+    // read from the built package name
+    expected_release: Option<String>,
+    built_release: Option<String>,
+    // Set when the build reaches an interactive config menu that needs a TTY
+    needs_terminal: bool,
+    // makepkg ran without -i; ask before installing once it succeeds
+    install_pending: bool,
+    show_install_confirm: bool,
+    // Missing makedepends reported by the current makepkg run
+    missing_deps: MissingDeps,
+    // "Build in:" edit buffer, seeded from settings; empty means the work dir
+    build_dir_input: Option<String>,
+    // Load-limit edit buffer, seeded from settings
+    load_limit_input: Option<String>,
+    last_build: Option<BuildParams>,
+    step: Step,
+    // Exit code of the build/install, kept while the post-build hook runs
+    build_exit: i32,
+    // ▶ Build was pressed with _menunconfig set; offer the terminal instead
+    menu_prompt: Option<&'static str>,
+    // ▶ Build was pressed with _offline set but local sources are missing
+    offline_problems: Vec<String>,
+    // _version is outside what the linux-tkg clone supports; ask before building
+    version_warning: Option<String>,
+    // What a clean build would delete, and the build to start once confirmed
+    clean_confirm: Option<(Vec<PathBuf>, BuildParams)>,
+    // Confirmed clean-build deletions, done right before makepkg starts
+    to_clean: Vec<PathBuf>,
+    preview: Option<BuildPreview>,
+    // Markdown bug report of the failed build, shown until closed
+    report: Option<String>,
+    report_status: String,
+    pkgbuild: Option<PkgbuildEditor>,
+    // Copy of settings.build_output_mirror for the spawn helpers
+    mirror: Option<PathBuf>,
+    // Resolved directory of the current build; hooks run here and its free
+    // space is watched while running
+    run_dir: Option<PathBuf>,
+    space_checked: Option<Instant>,
+    free_space: Option<u64>,
+    // The build printed "No space left on device"
+    out_of_space: bool,
 }
 
 impl Default for BuildTab {
@@ -44,34 +337,145 @@ impl Default for BuildTab {
             build_handle: None,
             auto_scroll: true,
             input_text: String::new(),
+            copy_with_levels: false,
+            shown_levels: [true; LogLevel::ALL.len()],
+            log_search: String::new(),
+            search_current: 0,
+            scroll_to_match: false,
+            search_only_matches: false,
+            error_focus: None,
+            scroll_to_error: false,
+            expanded_lines: HashSet::new(),
+            last_makepkg_run: None,
+            built_packages: Vec::new(),
+            expected_release: None,
+            built_release: None,
+            needs_terminal: false,
+            build_dir_input: None,
+            load_limit_input: None,
+            missing_deps: MissingDeps::default(),
+            install_pending: false,
+            show_install_confirm: false,
+            last_build: None,
+            step: Step::Build,
+            build_exit: 0,
+            menu_prompt: None,
+            offline_problems: Vec::new(),
+            version_warning: None,
+            clean_confirm: None,
+            to_clean: Vec::new(),
+            preview: None,
+            report: None,
+            report_status: String::new(),
+            pkgbuild: None,
+            mirror: None,
+            run_dir: None,
+            space_checked: None,
+            free_space: None,
+            out_of_space: false,
         }
     }
 }
 
 impl BuildTab {
-    pub fn ui(&mut self, ui: &mut Ui, ctx: &Context, linux_tkg_path: &Path) {
+    pub fn ui(
+        &mut self,
+        ui: &mut Ui,
+        ctx: &Context,
+        linux_tkg_path: &Path,
+        settings: &mut AppSettings,
+    ) {
+        self.mirror = settings.build_output_mirror.as_deref().map(settings::expand_path);
+
         // Drain messages from build process
         let mut should_clear_rx = false;
         let mut got_messages = false;
+        let mut finished_step: Option<i32> = None;
+        let mut pre_build_exit: Option<i32> = None;
         
         if let Some(rx) = &self.rx {
             while let Ok(msg) = rx.try_recv() {
                 got_messages = true;
                 match msg {
                     BuildMsg::Line(text) => {
+                        if needs_terminal(&text) {
+                            self.needs_terminal = true;
+                        }
+                        if self.last_makepkg_run.is_some() {
+                            self.missing_deps.feed(&text);
+                        }
+                        if build_manager::is_out_of_space(&text) {
+                            self.out_of_space = true;
+                        }
                         let level = classify_line(&text);
                         self.log.push(LogLine { text, level });
                     }
-                    BuildMsg::Exit(code) => {
+                    BuildMsg::Exit(result) if self.step == Step::PreBuild => {
+                        let code = result.exit_code;
+                        self.log.push(LogLine {
+                            text: format!(
+                                "==> Pre-build command finished with exit code {}",
+                                code
+                            ),
+                            level: if code == 0 {
+                                LogLevel::Stage
+                            } else {
+                                LogLevel::Error
+                            },
+                        });
+                        pre_build_exit = Some(code);
+                        should_clear_rx = true;
+                    }
+                    BuildMsg::Exit(result) if self.step == Step::PostBuild => {
+                        let code = result.exit_code;
+                        self.state = BuildState::Done(self.build_exit);
+                        self.log.push(LogLine {
+                            text: format!(
+                                "==> Post-build command finished with exit code {}",
+                                code
+                            ),
+                            level: if code == 0 {
+                                LogLevel::Stage
+                            } else {
+                                LogLevel::Error
+                            },
+                        });
+                        should_clear_rx = true;
+                    }
+                    BuildMsg::Exit(result) => {
+                        let code = result.exit_code;
                         self.state = BuildState::Done(code);
+                        self.build_exit = code;
+                        if !result.artifacts.is_empty() {
+                            self.built_packages = result.artifacts.clone();
+                            self.built_release = self
+                                .built_packages
+                                .iter()
+                                .find_map(|p| build_manager::package_release(p));
+                            if let Some(line) = built_release_line(
+                                self.built_release.as_deref(),
+                                self.expected_release.as_deref(),
+                            ) {
+                                self.log.push(line);
+                            }
+                        }
+                        if self.install_pending {
+                            self.install_pending = false;
+                            self.show_install_confirm =
+                                code == 0 && !self.built_packages.is_empty();
+                        }
                         self.log.push(LogLine {
-                            text: format!("==> Build finished with exit code {}", code),
+                            text: format!("==> Build finished: {}", result.summary()),
                             level: if code == 0 {
                                 LogLevel::Stage
                             } else {
                                 LogLevel::Error
                             },
                         });
+                        // The hook follows the install when one is still to be confirmed
+                        if !self.show_install_confirm {
+                            finished_step = Some(code);
+                        }
                         should_clear_rx = true;
                     }
                     BuildMsg::SpawnError(e) => {
@@ -80,6 +484,9 @@ impl BuildTab {
                             text: format!("Error: {}", e),
                             level: LogLevel::Error,
                         });
+                        if self.step == Step::PreBuild {
+                            pre_build_exit = Some(-1);
+                        }
                         should_clear_rx = true;
                     }
                 }
@@ -90,55 +497,477 @@ impl BuildTab {
             self.rx = None;
             self.build_handle = None;
         }
+        if let Some(code) = pre_build_exit {
+            let abort = self.last_build.as_ref().is_none_or(|p| p.pre_build_abort);
+            if code != 0 && abort {
+                self.state = BuildState::Done(code);
+                self.log.push(LogLine {
+                    text: "==> Build aborted: the pre-build command failed".to_string(),
+                    level: LogLevel::Error,
+                });
+            } else {
+                self.spawn_build(ctx.clone());
+            }
+        }
+        if let Some(code) = finished_step {
+            let command = settings.post_build_command.trim();
+            if !command.is_empty() && (code == 0 || settings.post_build_on_failure) {
+                self.start_hook(Step::PostBuild, command, ctx.clone(), settings.build_use_pty);
+            }
+        }
         if got_messages {
             ctx.request_repaint();
         }
 
-        let work_dir = linux_tkg_path.to_path_buf();
-
         ui.heading("🔨 Build");
         ui.add_space(4.0);
 
+        self.check_free_space();
+        self.disk_space_banner(ui);
+
+        let build_dir_input = self.build_dir_input.get_or_insert_with(|| {
+            settings
+                .build_dir
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default()
+        });
+        let custom_dir = Some(build_dir_input.trim())
+            .filter(|s| !s.is_empty())
+            .map(PathBuf::from);
+        let work_dir = custom_dir
+            .as_deref()
+            .map(settings::expand_path)
+            .unwrap_or_else(|| linux_tkg_path.to_path_buf());
+        let validation = build_manager::validate_build_dir(&work_dir);
+        let keep_sources_problem = settings
+            .build_keep_sources
+            .then(|| build_manager::keep_sources_problem(&work_dir, detect_use_makepkg(&work_dir)))
+            .flatten();
+
+        ui.horizontal(|ui| {
+            ui.label("Build in:");
+            ui.add(
+                egui::TextEdit::singleline(build_dir_input)
+                    .hint_text(linux_tkg_path.display().to_string())
+                    .desired_width(400.0),
+            )
+            .on_hover_text(
+                "Directory containing PKGBUILD or install.sh. \
+                 Leave empty to use the linux-tkg work dir.",
+            );
+            if custom_dir.is_some() && ui.small_button("↺ Work Dir").clicked() {
+                build_dir_input.clear();
+            }
+            match &validation {
+                Ok(entry) => {
+                    ui.label(RichText::new(format!("✓ {}", entry)).color(egui::Color32::GREEN));
+                }
+                Err(e) => {
+                    ui.label(RichText::new(format!("✗ {}", e)).color(egui::Color32::RED));
+                }
+            }
+        });
+        expansion_hint(ui, build_dir_input);
+        if validation.is_ok() {
+            if let Some(warning) = build_manager::tkg_tree_warning(&work_dir) {
+                ui.label(RichText::new(format!("⚠ {}", warning)).color(egui::Color32::YELLOW));
+            }
+        }
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut settings.build_keep_sources, "Don't touch sources")
+                .on_hover_text(
+                    "Compile and package the existing src/ with makepkg --noextract: no \
+                     extraction, no prepare(), no patching. For trees you have edited by hand.",
+                )
+                .changed()
+            {
+                let _ = settings.save();
+            }
+            if let Some(problem) = &keep_sources_problem {
+                ui.label(RichText::new(format!("✗ {}", problem)).color(egui::Color32::RED));
+            }
+        });
+        ui.add_enabled_ui(!settings.build_keep_sources, |ui| {
+            if ui
+                .checkbox(&mut settings.build_clean, "Clean build")
+                .on_hover_text(
+                    "Before ▶ Build, delete src/, pkg/ and old *.pkg.tar.* files from the \
+                     makepkg directory so nothing is reused from earlier runs. You are asked \
+                     first. install.sh builds and the terminal build are not affected.",
+                )
+                .on_disabled_hover_text("Can't clean while sources are left untouched")
+                .changed()
+            {
+                let _ = settings.save();
+            }
+        });
+        let load_limit = self.make_limits_ui(ui, settings);
+        let can_build =
+            validation.is_ok() && keep_sources_problem.is_none() && load_limit.is_ok();
+
         ui.horizontal(|ui| {
             let is_running = self.state == BuildState::Running;
 
             if ui
                 .add_enabled(
-                    !is_running,
+                    !is_running && can_build,
                     egui::Button::new(RichText::new("▶ Build").color(egui::Color32::GREEN)),
                 )
                 .clicked()
             {
-                self.start_build(&work_dir, ctx.clone());
+                if settings.build_dir != custom_dir {
+                    settings.build_dir = custom_dir.clone();
+                    let _ = settings.save();
+                }
+                self.offline_problems = offline_problems(&work_dir);
+                if self.offline_problems.is_empty() {
+                    self.check_version_then_build(&work_dir, settings, ctx.clone());
+                }
+            }
+
+            if let Some(last) = self.last_build.clone() {
+                let finished = matches!(self.state, BuildState::Done(_) | BuildState::Failed);
+                if finished
+                    && ui
+                        .button("🔁 Rebuild")
+                        .on_hover_text(format!(
+                            "Run the last build again in {} ({}{}{}{})",
+                            last.dir.display(),
+                            if last.confirm_install { "ask before install" } else { "install" },
+                            if last.keep_sources { ", sources untouched" } else { "" },
+                            if last.clean { ", clean" } else { "" },
+                            if last.use_pty { ", PTY" } else { "" }
+                        ))
+                        .clicked()
+                {
+                    self.confirm_clean_then_start(last, ctx.clone());
+                }
             }
 
-            // Stop button - note: we can't easily kill the process, just stop listening
             if ui
                 .add_enabled(
                     is_running,
                     egui::Button::new(RichText::new("■ Stop").color(egui::Color32::RED)),
                 )
-                .on_hover_text("Stop monitoring (process continues in background)")
+                .on_hover_text(
+                    "Terminate the build and every process it started (SIGTERM, then \
+                     SIGKILL after a few seconds)",
+                )
                 .clicked()
             {
-                self.rx = None;
-                self.build_handle = None;
-                self.state = BuildState::Idle;
-                self.log.push(LogLine {
-                    text: "==> Stopped monitoring".to_string(),
-                    level: LogLevel::Warning,
-                });
+                self.kill_build();
+            }
+
+            // Offer to redo only the install step once packages exist
+            let can_retry_install = !is_running
+                && self.last_makepkg_run.is_some()
+                && !self.built_packages.is_empty();
+            if can_retry_install
+                && ui
+                    .button("📦 Retry Install")
+                    .on_hover_text(format!(
+                        "Install the already built packages without recompiling:\n{}",
+                        self.built_packages
+                            .iter()
+                            .filter_map(|p| p.file_name())
+                            .map(|n| n.to_string_lossy())
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    ))
+                    .clicked()
+            {
+                self.start_install(ctx.clone(), settings.build_use_pty);
+            }
+
+            if !is_running && !self.built_packages.is_empty() {
+                let install = build_manager::package_install_args(&self.built_packages);
+                let hover = match &install {
+                    Ok(args) => format!(
+                        "Install with the system package manager:\n{}{}",
+                        args.join(" "),
+                        self.release_hint().map(|h| format!("\n{}", h)).unwrap_or_default()
+                    ),
+                    Err(e) => e.clone(),
+                };
+                if ui
+                    .add_enabled(install.is_ok(), egui::Button::new("📥 Install Package"))
+                    .on_hover_text(hover)
+                    .clicked()
+                {
+                    if let Ok(args) = install {
+                        self.start_package_install(args, ctx.clone(), settings.build_use_pty);
+                    }
+                }
+            }
+
+            if ui
+                .button("🔍 Preview")
+                .on_hover_text("Show what ▶ Build would run and check prerequisites")
+                .clicked()
+            {
+                self.preview = Some(BuildPreview::gather(&work_dir, settings));
+            }
+
+            let pkgbuild = work_dir.join("PKGBUILD");
+            let has_pkgbuild = pkgbuild.is_file();
+            if ui
+                .add_enabled(has_pkgbuild, egui::Button::new("📄 PKGBUILD"))
+                .on_hover_text("View or edit the PKGBUILD makepkg builds from")
+                .on_disabled_hover_text(
+                    "No PKGBUILD in the build directory. install.sh builds don't use one; \
+                     makepkg builds need the one shipped with linux-tkg.",
+                )
+                .clicked()
+            {
+                match PkgbuildEditor::open(pkgbuild) {
+                    Ok(editor) => self.pkgbuild = Some(editor),
+                    Err(e) => self.log.push(LogLine {
+                        text: format!("Error: {}", e),
+                        level: LogLevel::Error,
+                    }),
+                }
             }
 
-            ui.label(format!("Working dir: {}", work_dir.display()));
+            if ui
+                .add_enabled(!is_running && can_build, egui::Button::new("🖥 Build in Terminal"))
+                .on_hover_text("Run the build in an external terminal emulator")
+                .clicked()
+            {
+                if settings.build_dir != custom_dir {
+                    settings.build_dir = custom_dir.clone();
+                    let _ = settings.save();
+                }
+                self.launch_in_terminal(&work_dir, settings);
+            }
         });
 
+        self.preview_panel(ui, &work_dir, settings);
+        self.report_window(ctx);
+        self.pkgbuild_window(ctx);
+
+        if !self.offline_problems.is_empty() {
+            ui.add_space(4.0);
+            ui.group(|ui| {
+                ui.label(
+                    RichText::new(
+                        "⚠ _offline is set, so linux-tkg won't download anything, but local \
+                         sources look incomplete:",
+                    )
+                    .color(egui::Color32::YELLOW),
+                );
+                for problem in &self.offline_problems {
+                    ui.label(format!("• {}", problem));
+                }
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Build anyway")
+                        .on_hover_text("The build will likely fail early while fetching sources")
+                        .clicked()
+                    {
+                        self.offline_problems.clear();
+                        self.check_version_then_build(&work_dir, settings, ctx.clone());
+                    }
+                    if ui
+                        .button("Cancel")
+                        .on_hover_text("Turn off Offline Mode on the Config tab to fetch sources")
+                        .clicked()
+                    {
+                        self.offline_problems.clear();
+                    }
+                });
+            });
+        }
+
+        if let Some(warning) = self.version_warning.clone() {
+            ui.add_space(4.0);
+            ui.group(|ui| {
+                ui.label(RichText::new(format!("⚠ {}", warning)).color(egui::Color32::YELLOW));
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Build anyway")
+                        .on_hover_text("linux-tkg's scripts may fail in odd places")
+                        .clicked()
+                    {
+                        self.version_warning = None;
+                        self.request_build(&work_dir, settings, ctx.clone());
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.version_warning = None;
+                    }
+                });
+            });
+        }
+
+        if let Some((targets, params)) = self.clean_confirm.clone() {
+            ui.add_space(4.0);
+            ui.group(|ui| {
+                ui.label(
+                    RichText::new("⚠ Clean build will permanently delete:")
+                        .color(egui::Color32::YELLOW),
+                );
+                for path in &targets {
+                    let what = match path.file_name().and_then(|n| n.to_str()) {
+                        Some("src") => " (extracted sources, including edits made there)",
+                        Some("pkg") => " (packaging staging tree)",
+                        _ => "",
+                    };
+                    ui.monospace(format!("• {}{}", path.display(), what));
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Delete and Build").clicked() {
+                        self.clean_confirm = None;
+                        self.to_clean = targets.clone();
+                        self.start_build(params, ctx.clone());
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.clean_confirm = None;
+                    }
+                });
+            });
+        }
+
+        if let Some(tool) = self.menu_prompt {
+            ui.add_space(4.0);
+            ui.group(|ui| {
+                ui.label(
+                    RichText::new(format!(
+                        "⚠ _menunconfig will open {} before compiling. It needs a real \
+                         terminal{}, which the embedded log can't provide.",
+                        tool,
+                        if tool == "xconfig" { " and a graphical session" } else { "" }
+                    ))
+                    .color(egui::Color32::YELLOW),
+                );
+                ui.horizontal(|ui| {
+                    if ui.button("🖥 Run in Terminal").clicked() {
+                        self.menu_prompt = None;
+                        self.launch_in_terminal(&work_dir, settings);
+                    }
+                    if ui
+                        .button("Build here anyway")
+                        .on_hover_text("The config step may hang or fail without a terminal")
+                        .clicked()
+                    {
+                        self.menu_prompt = None;
+                        self.confirm_clean_then_start(
+                            BuildParams::new(&work_dir, settings),
+                            ctx.clone(),
+                        );
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.menu_prompt = None;
+                    }
+                });
+            });
+        }
+
+        if self.needs_terminal {
+            ui.add_space(4.0);
+            ui.horizontal_wrapped(|ui| {
+                ui.label(
+                    RichText::new(
+                        "⚠ The build is trying to open an interactive kernel config menu \
+                         (menuconfig/nconfig), which needs a real terminal.",
+                    )
+                    .color(egui::Color32::YELLOW),
+                );
+                if ui.button("🖥 Launch in Terminal").clicked() {
+                    self.launch_in_terminal(&work_dir, settings);
+                }
+                if ui.small_button("Dismiss").clicked() {
+                    self.needs_terminal = false;
+                }
+            });
+        }
+
+        if self.show_install_confirm {
+            ui.add_space(4.0);
+            self.install_confirm_panel(ui, ctx, settings.build_use_pty);
+        }
+
+        if self.missing_deps.failed || !self.missing_deps.packages.is_empty() {
+            ui.add_space(4.0);
+            self.missing_deps_panel(ui, ctx);
+        }
+
         ui.add_space(4.0);
 
         ui.horizontal(|ui| {
             ui.checkbox(&mut self.auto_scroll, "Auto-scroll");
             if ui.button("Clear").clicked() {
                 self.log.clear();
+                self.expanded_lines.clear();
+                self.error_focus = None;
+            }
+            if ui
+                .add_enabled(!self.log.is_empty(), egui::Button::new("📋 Copy Log"))
+                .on_hover_text("Copy the log lines shown below to the clipboard")
+                .clicked()
+            {
+                let (_, rows) = self.visible_lines(settings.warnings_as_errors);
+                let lines = rows.iter().map(|&i| &self.log[i]);
+                ctx.copy_text(log_as_text(lines, self.copy_with_levels));
+            }
+            ui.checkbox(&mut self.copy_with_levels, "Level prefixes");
+            if ui
+                .button("📂 Open Log Folder")
+                .on_hover_text(format!(
+                    "Every build's output is saved in {}",
+                    AppSettings::build_logs_dir().display()
+                ))
+                .clicked()
+            {
+                if let Err(e) = system::open_folder(&AppSettings::build_logs_dir()) {
+                    self.log.push(LogLine {
+                        text: format!("Error: {}", e),
+                        level: LogLevel::Error,
+                    });
+                }
+            }
+            ui.label("Keep");
+            if ui
+                .add(egui::DragValue::new(&mut settings.build_logs_keep).range(0..=1000))
+                .on_hover_text("Older build logs are deleted when a build starts; 0 keeps all")
+                .changed()
+            {
+                let _ = settings.save();
+            }
+            ui.label("logs");
+            let failed = matches!(self.state, BuildState::Failed)
+                || matches!(self.state, BuildState::Done(code) if code != 0);
+            if failed
+                && ui
+                    .button("📝 Report")
+                    .on_hover_text(
+                        "Put together a Markdown bug report: versions, command and \
+                         environment, config changes, patches and the end of the log",
+                    )
+                    .clicked()
+            {
+                self.report = Some(self.bug_report(&work_dir, settings));
+                self.report_status.clear();
+            }
+            if ui
+                .checkbox(&mut settings.confirm_install, "Confirm install")
+                .on_hover_text(
+                    "makepkg only: build without installing and show what will be \
+                     installed before doing so. Turn off for unattended builds.",
+                )
+                .changed()
+            {
+                let _ = settings.save();
+            }
+            if ui
+                .checkbox(&mut settings.build_use_pty, "PTY mode")
+                .on_hover_text(
+                    "Run the build under a pseudo-terminal so prompts (sudo, pacman) \
+                     behave as in a real terminal. Applies to the next build.",
+                )
+                .changed()
+            {
+                let _ = settings.save();
             }
 
             // State indicator
@@ -164,30 +993,68 @@ impl BuildTab {
             ui.label(RichText::new(state_text).color(color));
         });
 
+        let as_errors = settings.warnings_as_errors;
+        self.level_filter_ui(ui, settings);
+
+        let query = self.log_search.clone();
+        let (matches, rows) = self.visible_lines(as_errors);
+        self.search_ui(ui, matches.len());
+        self.search_current = self.search_current.min(matches.len().saturating_sub(1));
+        let current = matches.get(self.search_current).copied();
+
         ui.add_space(8.0);
 
-        // Log output
-        egui::ScrollArea::vertical()
+        // Log output. Rows are one line each (no wrapping), so only the
+        // visible ones are laid out.
+        let font = egui::TextStyle::Monospace.resolve(ui.style());
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        let mut area = egui::ScrollArea::both()
             .stick_to_bottom(self.auto_scroll)
-            .max_height(ui.available_height() - 40.0)
-            .show(ui, |ui| {
-                ui.set_min_width(ui.available_width());
-                for line in &self.log {
-                    let color = match line.level {
-                        LogLevel::Normal => egui::Color32::LIGHT_GRAY,
-                        LogLevel::Stage => egui::Color32::GREEN,
-                        LogLevel::Warning => egui::Color32::YELLOW,
-                        LogLevel::Error => egui::Color32::RED,
-                        LogLevel::Input => egui::Color32::LIGHT_BLUE,
-                    };
-                    let text = RichText::new(&line.text).color(color).monospace();
-                    if line.level == LogLevel::Stage {
-                        ui.label(text.strong());
+            .max_height(ui.available_height() - 40.0);
+        let scroll_target = if std::mem::take(&mut self.scroll_to_error) {
+            self.error_focus
+        } else if std::mem::take(&mut self.scroll_to_match) {
+            current
+        } else {
+            None
+        };
+        if let Some(row) = scroll_target.and_then(|c| rows.binary_search(&c).ok()) {
+            // Leave a few lines of context above the target
+            let spacing = ui.spacing().item_spacing.y;
+            area = area
+                .vertical_scroll_offset(row.saturating_sub(5) as f32 * (row_height + spacing));
+        }
+        area.show_rows(ui, row_height, rows.len(), |ui, range| {
+            ui.set_min_width(ui.available_width());
+            for &i in &rows[range] {
+                let line = &self.log[i];
+                let long = line.text.len() > LONG_LINE;
+                let expanded = self.expanded_lines.contains(&i);
+                let job = log_line_job(
+                    &line.text,
+                    line.level.shown_as(as_errors),
+                    &query,
+                    Some(i) == current,
+                    Some(i) == self.error_focus,
+                    expanded,
+                    &font,
+                );
+                if !long {
+                    ui.add(egui::Label::new(job).extend());
+                    continue;
+                }
+                let response = ui
+                    .add(egui::Label::new(job).extend().sense(egui::Sense::click()))
+                    .on_hover_text(if expanded {
+                        "Click to shorten this line"
                     } else {
-                        ui.label(text);
-                    }
+                        "Click to show the whole line"
+                    });
+                if response.clicked() && !self.expanded_lines.remove(&i) {
+                    self.expanded_lines.insert(i);
                 }
-            });
+            }
+        });
 
         // Input field for interactive builds
         ui.add_space(4.0);
@@ -227,36 +1094,688 @@ impl BuildTab {
             }
         });
 
-        // Keep repainting while building
+        // Poll for new output while building; frames with output already
+        // requested an immediate repaint above
         if self.state == BuildState::Running {
-            ctx.request_repaint();
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+    }
+
+    /// Re-read free space on the build dir every few seconds while running.
+    fn check_free_space(&mut self) {
+        if self.state != BuildState::Running {
+            return;
+        }
+        let Some(dir) = &self.run_dir else {
+            return;
+        };
+        if self
+            .space_checked
+            .is_some_and(|t| t.elapsed() < SPACE_CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.space_checked = Some(Instant::now());
+        self.free_space = system::free_space(dir);
+    }
+
+    fn disk_space_banner(&mut self, ui: &mut Ui) {
+        let low = self.state == BuildState::Running
+            && self.free_space.is_some_and(|free| free < LOW_SPACE_BYTES);
+        if !low && !self.out_of_space {
+            return;
+        }
+        let dir = self
+            .run_dir
+            .as_ref()
+            .map(|d| d.display().to_string())
+            .unwrap_or_default();
+        ui.group(|ui| {
+            if self.out_of_space {
+                ui.label(
+                    RichText::new("✗ The build ran out of disk space (No space left on device)")
+                        .color(egui::Color32::RED)
+                        .strong(),
+                );
+                ui.label(format!("Free up space on the filesystem holding {} and rebuild.", dir));
+            } else if let Some(free) = self.free_space {
+                ui.label(
+                    RichText::new(format!(
+                        "⚠ Low disk space: {} free in {}",
+                        format_bytes(free),
+                        dir
+                    ))
+                    .color(egui::Color32::RED)
+                    .strong(),
+                );
+            }
+            if self.state == BuildState::Running
+                && self.build_handle.is_some()
+                && ui
+                    .button("⏹ Stop Build")
+                    .on_hover_text("Terminate the build before the disk fills up")
+                    .clicked()
+            {
+                self.kill_build();
+            }
+        });
+        ui.add_space(4.0);
+    }
+
+    /// "This will install kernel X", from the packages or the config
+    fn release_hint(&self) -> Option<String> {
+        self.built_release
+            .as_ref()
+            .or(self.expected_release.as_ref())
+            .map(|r| format!("This will install kernel {}", r))
+    }
+
+    /// One toggle per log level with its line count; hidden lines stay in
+    /// the log and are still copied
+    fn level_filter_ui(&mut self, ui: &mut Ui, settings: &mut AppSettings) {
+        let as_errors = settings.warnings_as_errors;
+        let mut counts = [0usize; LogLevel::ALL.len()];
+        for line in &self.log {
+            counts[line.level.shown_as(as_errors) as usize] += 1;
         }
+        ui.horizontal(|ui| {
+            ui.label("Show:");
+            for level in LogLevel::ALL {
+                if as_errors && level == LogLevel::Warning {
+                    continue;
+                }
+                let shown = &mut self.shown_levels[level as usize];
+                let text = RichText::new(format!("{} {}", level.label(), counts[level as usize]))
+                    .color(if *shown { level.color() } else { egui::Color32::GRAY });
+                ui.toggle_value(shown, text);
+            }
+            if self.shown_levels.contains(&false) && ui.small_button("All").clicked() {
+                self.shown_levels = [true; LogLevel::ALL.len()];
+            }
+
+            ui.separator();
+            let errors: Vec<usize> = (0..self.log.len())
+                .filter(|&i| self.log[i].level.shown_as(as_errors) == LogLevel::Error)
+                .collect();
+            let can_jump = !errors.is_empty() && self.shown_levels[LogLevel::Error as usize];
+            let prev = ui
+                .add_enabled(can_jump, egui::Button::new("◀"))
+                .on_hover_text("Previous error")
+                .clicked();
+            ui.label("Errors");
+            let next = ui
+                .add_enabled(can_jump, egui::Button::new("▶"))
+                .on_hover_text("Next error")
+                .clicked();
+            if prev || next {
+                let focus = self.error_focus;
+                self.error_focus = if prev {
+                    let before = focus.map_or(errors.len(), |f| errors.partition_point(|&i| i < f));
+                    errors.get(before.checked_sub(1).unwrap_or(errors.len() - 1)).copied()
+                } else {
+                    let after = focus.map_or(0, |f| errors.partition_point(|&i| i <= f));
+                    errors.get(after).or(errors.first()).copied()
+                };
+                self.scroll_to_error = true;
+            }
+            if ui
+                .checkbox(&mut settings.warnings_as_errors, "Treat warnings as errors")
+                .on_hover_text(
+                    "Highlighting only: warnings are shown, counted and navigated as errors. \
+                     The build itself is unaffected.",
+                )
+                .changed()
+            {
+                let _ = settings.save();
+            }
+        });
     }
 
-    fn start_build(&mut self, work_dir: &Path, ctx: Context) {
+    /// Indices of the log lines matching the search among those the level
+    /// toggles show, and of the rows the log view displays
+    fn visible_lines(&self, as_errors: bool) -> (Vec<usize>, Vec<usize>) {
+        let query = &self.log_search;
+        let level_shown =
+            |line: &LogLine| self.shown_levels[line.level.shown_as(as_errors) as usize];
+        let matches: Vec<usize> = if query.is_empty() {
+            Vec::new()
+        } else {
+            (0..self.log.len())
+                .filter(|&i| {
+                    let line = &self.log[i];
+                    level_shown(line) && find_ignore_case(&line.text, query).is_some()
+                })
+                .collect()
+        };
+        let rows = if self.search_only_matches && !query.is_empty() {
+            matches.clone()
+        } else {
+            (0..self.log.len()).filter(|&i| level_shown(&self.log[i])).collect()
+        };
+        (matches, rows)
+    }
+
+    /// Search box with the match count and ◀/▶ (or Enter) to step through
+    /// the matches
+    fn search_ui(&mut self, ui: &mut Ui, match_count: usize) {
+        ui.horizontal(|ui| {
+            ui.label("🔍");
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.log_search)
+                    .hint_text("Search log")
+                    .desired_width(200.0),
+            );
+            if response.changed() {
+                self.search_current = 0;
+                self.scroll_to_match = true;
+            }
+            if self.log_search.is_empty() {
+                return;
+            }
+            let enter = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+            if match_count == 0 {
+                ui.label(RichText::new("No matches").color(egui::Color32::GRAY));
+            } else {
+                ui.label(format!("{}/{}", self.search_current + 1, match_count));
+            }
+            let prev = ui
+                .add_enabled(match_count > 0, egui::Button::new("◀"))
+                .on_hover_text("Previous match")
+                .clicked();
+            let next = ui
+                .add_enabled(match_count > 0, egui::Button::new("▶"))
+                .on_hover_text("Next match (Enter)")
+                .clicked();
+            if match_count > 0 && (prev || next || enter) {
+                self.search_current = if prev {
+                    (self.search_current + match_count - 1) % match_count
+                } else {
+                    (self.search_current + 1) % match_count
+                };
+                self.scroll_to_match = true;
+            }
+            if enter {
+                response.request_focus();
+            }
+            ui.checkbox(&mut self.search_only_matches, "Only matching lines");
+        });
+    }
+
+    fn pkgbuild_window(&mut self, ctx: &Context) {
+        let Some(editor) = &mut self.pkgbuild else {
+            return;
+        };
+        editor.check_disk();
+        let mut open = true;
+        egui::Window::new("PKGBUILD")
+            .open(&mut open)
+            .default_size([700.0, 500.0])
+            .show(ctx, |ui| {
+                ui.label(
+                    RichText::new(
+                        "⚠ Edits are at your own risk. linux-tkg's prepare step may \
+                         regenerate parts of the PKGBUILD, and updating the clone \
+                         overwrites it.",
+                    )
+                    .color(egui::Color32::YELLOW),
+                );
+                ui.label(RichText::new(editor.path.display().to_string()).small());
+                ui.horizontal(|ui| {
+                    let dirty = editor.text != editor.saved;
+                    if ui.add_enabled(dirty, egui::Button::new("💾 Save")).clicked() {
+                        editor.save();
+                    }
+                    if ui.button("🔄 Reload").clicked() {
+                        editor.reload();
+                    }
+                    if ui
+                        .button("📝 Edit in Editor")
+                        .on_hover_text(
+                            "Open in $VISUAL, $EDITOR (in a terminal) or xdg-open. \
+                             This view reloads when the file changes.",
+                        )
+                        .clicked()
+                    {
+                        editor.status = match system::open_in_editor(&editor.path) {
+                            Ok(name) => format!("Opened in {}", name),
+                            Err(e) => format!("Error: {}", e),
+                        };
+                    }
+                    if dirty {
+                        ui.label(RichText::new("● Modified").color(egui::Color32::YELLOW));
+                    }
+                    ui.label(&editor.status);
+                });
+                ui.separator();
+                egui::ScrollArea::both().auto_shrink([false, false]).show(ui, |ui| {
+                    ui.add(
+                        egui::TextEdit::multiline(&mut editor.text)
+                            .code_editor()
+                            .desired_width(f32::INFINITY),
+                    );
+                });
+            });
+        if !open {
+            self.pkgbuild = None;
+        }
+    }
+
+    fn install_confirm_panel(&mut self, ui: &mut Ui, ctx: &Context, use_pty: bool) {
+        let Some(work_dir) = &self.last_makepkg_run else {
+            return;
+        };
+        let pkgname = build_manager::pkgbuild_field(work_dir, "pkgname")
+            .or_else(|| build_manager::pkgbuild_field(work_dir, "pkgbase"));
+        let pkgver = build_manager::pkgbuild_field(work_dir, "pkgver");
+
+        let mut install = false;
+        ui.group(|ui| {
+            ui.label(
+                RichText::new("📦 Build finished. Install these packages?")
+                    .strong()
+                    .color(egui::Color32::LIGHT_BLUE),
+            );
+            if let Some(name) = pkgname {
+                ui.label(format!(
+                    "PKGBUILD: {} {}",
+                    name,
+                    pkgver.unwrap_or_default()
+                ));
+            }
+            for package in &self.built_packages {
+                if let Some(name) = package.file_name() {
+                    ui.monospace(format!("  {}", name.to_string_lossy()));
+                }
+            }
+            if let Some(hint) = self.release_hint() {
+                ui.label(format!("{}; its boot entry will use this name.", hint));
+            }
+            ui.label(
+                RichText::new("Installing a kernel package changes the running system.")
+                    .small()
+                    .color(egui::Color32::GRAY),
+            );
+            ui.horizontal(|ui| {
+                if ui.button("Install").clicked() {
+                    install = true;
+                }
+                if ui.button("Not Now").clicked() {
+                    self.show_install_confirm = false;
+                }
+            });
+        });
+        if install {
+            self.show_install_confirm = false;
+            self.start_install(ctx.clone(), use_pty);
+        }
+    }
+
+    fn missing_deps_panel(&mut self, ui: &mut Ui, ctx: &Context) {
+        ui.group(|ui| {
+            ui.label(
+                RichText::new("✗ makepkg could not resolve all build dependencies")
+                    .strong()
+                    .color(egui::Color32::RED),
+            );
+            if self.missing_deps.packages.is_empty() {
+                ui.label("Check the log for the packages pacman could not find.");
+            } else {
+                ui.label(format!(
+                    "Missing packages: {}",
+                    self.missing_deps.packages.join(", ")
+                ));
+                let command = self.missing_deps.install_command();
+                ui.horizontal(|ui| {
+                    ui.monospace(&command);
+                    if ui.small_button("📋 Copy").clicked() {
+                        ctx.copy_text(command.clone());
+                    }
+                });
+                ui.label(
+                    RichText::new("Install them in a terminal, then build again.")
+                        .small()
+                        .color(egui::Color32::GRAY),
+                );
+            }
+            if ui.small_button("Dismiss").clicked() {
+                self.missing_deps = MissingDeps::default();
+            }
+        });
+    }
+
+    fn preview_panel(&mut self, ui: &mut Ui, work_dir: &Path, settings: &AppSettings) {
+        let Some(preview) = &self.preview else {
+            return;
+        };
+        let mut refresh = false;
+        let mut close = false;
+        ui.add_space(4.0);
+        ui.group(|ui| {
+            ui.label(RichText::new("🔍 Build Preview").strong());
+            egui::Grid::new("build_preview").num_columns(2).show(ui, |ui| {
+                for (label, value) in &preview.facts {
+                    ui.label(RichText::new(*label).color(egui::Color32::GRAY));
+                    ui.monospace(value);
+                    ui.end_row();
+                }
+            });
+            if !preview.chips.is_empty() {
+                ui.label(format!("Config: {}", preview.chips.join(" · ")));
+            }
+            for (ok, check) in &preview.checks {
+                let (mark, color) = if *ok {
+                    ("✓", egui::Color32::GREEN)
+                } else {
+                    ("✗", egui::Color32::YELLOW)
+                };
+                ui.label(RichText::new(format!("{} {}", mark, check)).color(color));
+            }
+            ui.horizontal(|ui| {
+                refresh = ui.button("🔄 Refresh").clicked();
+                close = ui.button("Close").clicked();
+            });
+        });
+        if refresh {
+            self.preview = Some(BuildPreview::gather(work_dir, settings));
+        } else if close {
+            self.preview = None;
+        }
+    }
+
+    /// Markdown report of the last build for an issue. Lines typed into the
+    /// input field are left out, as they may be a sudo password.
+    fn bug_report(&self, work_dir: &Path, settings: &AppSettings) -> String {
+        let dir = self
+            .run_dir
+            .clone()
+            .or_else(|| self.last_build.as_ref().map(|b| b.dir.clone()))
+            .unwrap_or_else(|| work_dir.to_path_buf());
+        let preview = BuildPreview::gather(&dir, settings);
+        let mut facts = bug_report::system_facts();
+        facts.extend(preview.facts.into_iter().map(|(label, value)| (label.to_string(), value)));
+
+        let series = ConfigManager::load(dir.join("customization.cfg"))
+            .ok()
+            .and_then(|config| config.get_option("_version"))
+            .map(|v| version::series(&v))
+            .unwrap_or_default();
+        let patch_dir = settings
+            .patch_dir_override
+            .as_deref()
+            .map(settings::expand_path)
+            .unwrap_or_else(|| patch_manager::get_patch_dir(&dir, &series));
+
+        let tail = &self.log[self.log.len().saturating_sub(LOG_TAIL_LINES)..];
+        BugReport {
+            facts,
+            checks: preview.checks,
+            config: bug_report::config_changes(&dir),
+            patches: patch_manager::list_patches(&patch_dir)
+                .into_iter()
+                .map(|p| p.name)
+                .collect(),
+            log_tail: tail
+                .iter()
+                .map(|line| match line.level {
+                    LogLevel::Input => (false, ">>> (input redacted)".to_string()),
+                    level => (level == LogLevel::Error, line.text.clone()),
+                })
+                .collect(),
+        }
+        .to_markdown()
+    }
+
+    fn report_window(&mut self, ctx: &Context) {
+        let Some(report) = &self.report else {
+            return;
+        };
+        let mut open = true;
+        let mut save = false;
+        egui::Window::new("Build Report")
+            .open(&mut open)
+            .default_width(640.0)
+            .show(ctx, |ui| {
+                ui.label("Review the report before sharing it; it includes local paths.");
+                egui::ScrollArea::vertical()
+                    .id_salt("bug_report")
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::TextEdit::multiline(&mut report.as_str())
+                                .font(egui::TextStyle::Monospace)
+                                .desired_width(f32::INFINITY),
+                        );
+                    });
+                ui.horizontal(|ui| {
+                    if ui.button("📋 Copy").clicked() {
+                        ctx.copy_text(report.clone());
+                        self.report_status = "Copied to the clipboard".to_string();
+                    }
+                    save = ui.button("💾 Save").clicked();
+                    ui.label(&self.report_status);
+                });
+            });
+        if save {
+            let name = format!("build-report-{}.md", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+            let dir = AppSettings::data_dir().join("reports");
+            let path = dir.join(name);
+            let written = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, report));
+            self.report_status = match written {
+                Ok(()) => format!("Saved to {}", path.display()),
+                Err(e) => format!("Error saving {}: {}", path.display(), e),
+            };
+        }
+        if !open {
+            self.report = None;
+        }
+    }
+
+    pub fn state(&self) -> BuildState {
+        self.state
+    }
+
+    /// Background jobs still running, for "Stop All"
+    pub fn background_tasks(&self) -> Vec<&'static str> {
+        if self.state == BuildState::Running && self.build_handle.is_some() {
+            vec!["Build"]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Terminate the running build, as ■ Stop does
+    pub fn stop_background(&mut self) -> Vec<&'static str> {
+        let stopped = self.background_tasks();
+        if !stopped.is_empty() {
+            self.kill_build();
+        }
+        stopped
+    }
+
+    /// Kill the running build (or hook) with its whole process group and
+    /// stop following it. If it already exited, its exit is still reported.
+    fn kill_build(&mut self) {
+        let Some(handle) = &self.build_handle else {
+            return;
+        };
+        let (text, level) = match handle.kill() {
+            Ok(()) => {
+                self.rx = None;
+                self.build_handle = None;
+                self.state = BuildState::Idle;
+                self.install_pending = false;
+                ("==> Build terminated".to_string(), LogLevel::Warning)
+            }
+            Err(e) => (format!("Error: could not stop the build: {}", e), LogLevel::Error),
+        };
+        self.log.push(LogLine { text, level });
+    }
+
+    /// Ask first when customization.cfg's _version is outside the series
+    /// the linux-tkg clone in `work_dir` supports
+    fn check_version_then_build(&mut self, work_dir: &Path, settings: &AppSettings, ctx: Context) {
+        let version = ConfigManager::load(work_dir.join("customization.cfg"))
+            .ok()
+            .and_then(|config| config.get_option("_version"))
+            .filter(|v| !v.is_empty());
+        let supported =
+            repo_manager::supported_series(work_dir, &settings.supported_series_override);
+        self.version_warning = version
+            .and_then(|v| repo_manager::unsupported_version_warning(&v, &supported));
+        if self.version_warning.is_none() {
+            self.request_build(work_dir, settings, ctx);
+        }
+    }
+
+    /// Start the build, after asking if it is a clean build that would
+    /// delete anything
+    fn confirm_clean_then_start(&mut self, params: BuildParams, ctx: Context) {
+        let targets = match build_manager::resolve_work_dir(&params.dir) {
+            Ok(dir) if params.clean && detect_use_makepkg(&dir) => {
+                build_manager::clean_targets(&dir)
+            }
+            _ => Vec::new(),
+        };
+        if targets.is_empty() {
+            self.to_clean.clear();
+            self.start_build(params, ctx);
+        } else {
+            self.clean_confirm = Some((targets, params));
+        }
+    }
+
+    /// Start a build from ▶ Build, unless _menunconfig needs a terminal first
+    fn request_build(&mut self, work_dir: &Path, settings: &AppSettings, ctx: Context) {
+        // The embedded log can't host a curses/X config tool
+        if let Some(tool) = menunconfig_tool(work_dir) {
+            self.menu_prompt = Some(tool);
+        } else {
+            self.confirm_clean_then_start(BuildParams::new(work_dir, settings), ctx);
+        }
+    }
+
+    fn start_build(&mut self, params: BuildParams, ctx: Context) {
         self.log.clear();
+        self.expanded_lines.clear();
+        self.error_focus = None;
+        let requested_dir = params.dir.clone();
+        let pre_build_command = params.pre_build_command.clone();
+        let pre_build_abort = params.pre_build_abort;
+        let use_pty = params.use_pty;
+        let min_free = params.min_free;
+        self.last_build = Some(params);
+
+        // Follow symlinks once up front so every later path is the real tree
+        let resolved = build_manager::resolve_work_dir(&requested_dir).and_then(|path| {
+            work_dir::ensure_free_space(&path, min_free)?;
+            Ok(path)
+        });
+        let work_dir = match resolved {
+            Ok(path) => path,
+            Err(e) => {
+                self.state = BuildState::Failed;
+                self.log.push(LogLine {
+                    text: format!("Error: {}", e),
+                    level: LogLevel::Error,
+                });
+                return;
+            }
+        };
+
         self.state = BuildState::Running;
+        self.run_dir = Some(work_dir.clone());
+        self.space_checked = None;
+        self.out_of_space = false;
+        self.built_packages.clear();
+        self.built_release = None;
+        self.needs_terminal = false;
+        self.missing_deps = MissingDeps::default();
+        self.show_install_confirm = false;
         self.log.push(LogLine {
             text: format!("==> Starting build in {}", work_dir.display()),
             level: LogLevel::Stage,
         });
+        if work_dir != requested_dir {
+            self.log.push(LogLine {
+                text: format!("    (resolved from {})", requested_dir.display()),
+                level: LogLevel::Normal,
+            });
+        }
 
-        // Detect distro from config to determine build command
-        let config_path = work_dir.join("customization.cfg");
-        let use_makepkg = if let Ok(config) = ConfigManager::load(&config_path) {
-            config.get_option("_distro").unwrap_or_default() == "Arch"
-        } else {
-            false
-        };
+        if pre_build_command.is_empty() {
+            self.spawn_build(ctx);
+        } else if !self.start_hook(Step::PreBuild, &pre_build_command, ctx.clone(), use_pty) {
+            // Only an unparsable command fails to start; a started one
+            // reports its exit through `rx`
+            if pre_build_abort {
+                self.state = BuildState::Failed;
+                self.log.push(LogLine {
+                    text: "==> Build aborted: the pre-build command failed".to_string(),
+                    level: LogLevel::Error,
+                });
+            } else {
+                self.spawn_build(ctx);
+            }
+        }
+    }
 
-        let cmd_name = if use_makepkg {
-            "makepkg -si"
-        } else {
-            "./install.sh install"
+    /// Start makepkg/install.sh for the build prepared by `start_build`.
+    fn spawn_build(&mut self, ctx: Context) {
+        let (Some(work_dir), Some(params)) = (self.run_dir.clone(), self.last_build.clone())
+        else {
+            return;
         };
+        self.state = BuildState::Running;
+        self.step = Step::Build;
+
+        let use_makepkg = detect_use_makepkg(&work_dir);
+        let install = !(use_makepkg && params.confirm_install);
+        let targets = std::mem::take(&mut self.to_clean);
+        if params.clean && use_makepkg && !targets.is_empty() {
+            if let Err(e) = build_manager::clean_build_dir(&targets) {
+                self.state = BuildState::Failed;
+                self.log.push(LogLine {
+                    text: format!("Error: clean build failed: {}", e),
+                    level: LogLevel::Error,
+                });
+                return;
+            }
+            for path in &targets {
+                self.log.push(LogLine {
+                    text: format!("==> Clean build: deleted {}", path.display()),
+                    level: LogLevel::Stage,
+                });
+            }
+        }
+        if params.keep_sources {
+            if let Some(problem) = build_manager::keep_sources_problem(&work_dir, use_makepkg) {
+                self.state = BuildState::Failed;
+                self.log.push(LogLine {
+                    text: format!("Error: cannot build without touching sources: {}", problem),
+                    level: LogLevel::Error,
+                });
+                return;
+            }
+        }
+        let cmd_name = build_manager::build_command_line(use_makepkg, install, params.keep_sources);
+
+        if let Some(tool) = menunconfig_tool(&work_dir) {
+            self.log.push(LogLine {
+                text: format!(
+                    "==> _menunconfig opens {}, which needs a real terminal. \
+                     Use \"Build in Terminal\" if it doesn't appear.",
+                    tool
+                ),
+                level: LogLevel::Warning,
+            });
+        }
 
         self.log.push(LogLine {
-            text: format!("==> Running {}", cmd_name),
+            text: match &params.makeflags {
+                Some(flags) => format!("==> Running {} (MAKEFLAGS=\"{}\")", cmd_name, flags),
+                None => format!("==> Running {}", cmd_name),
+            },
             level: LogLevel::Stage,
         });
         self.log.push(LogLine {
@@ -264,23 +1783,443 @@ impl BuildTab {
             level: LogLevel::Normal,
         });
 
+        self.expected_release = expected_release(&work_dir, use_makepkg);
+        if let Some(release) = &self.expected_release {
+            self.log.push(LogLine {
+                text: format!("==> This will install kernel {}", release),
+                level: LogLevel::Normal,
+            });
+        }
+
+        self.install_pending = !install;
+        self.last_makepkg_run = use_makepkg.then(|| work_dir.clone());
+
+        let version = ConfigManager::load(work_dir.join("customization.cfg"))
+            .ok()
+            .and_then(|c| c.get_option("_version"));
+        let logs_dir = AppSettings::build_logs_dir();
+        let log_file = build_manager::build_log_path(&logs_dir, version.as_deref());
+        self.log.push(LogLine {
+            text: format!("    (Output is also saved to {})", log_file.display()),
+            level: LogLevel::Normal,
+        });
+
         let (tx, rx) = channel();
         self.rx = Some(rx);
 
-        let handle = build_manager::start_build(work_dir.to_path_buf(), tx, use_makepkg);
+        let handle = build_manager::start_build(
+            work_dir,
+            tx,
+            use_makepkg,
+            install,
+            params.keep_sources,
+            params.use_pty,
+            params.makeflags.as_deref(),
+            self.mirror.as_deref(),
+            Some(&log_file),
+        );
         self.build_handle = Some(handle);
+        build_manager::prune_build_logs(&logs_dir, params.logs_keep);
+        ctx.request_repaint();
+    }
+
+    /// Job count and load-limit fields. Errs while the typed load limit is
+    /// invalid, which blocks building.
+    fn make_limits_ui(&mut self, ui: &mut Ui, settings: &mut AppSettings) -> Result<(), String> {
+        let input = self.load_limit_input.get_or_insert_with(|| {
+            settings.build_load_limit.map(|l| l.to_string()).unwrap_or_default()
+        });
+        let parsed = build_manager::parse_load_limit(input);
+        ui.horizontal(|ui| {
+            ui.label("Jobs:");
+            if ui
+                .add(egui::DragValue::new(&mut settings.build_jobs).range(0..=1024))
+                .on_hover_text(
+                    "make -j. 0 keeps MAKEFLAGS from the environment. linux-tkg's \
+                     _force_all_threads passes its own -j and wins over this.",
+                )
+                .changed()
+            {
+                let _ = settings.save();
+            }
+            ui.label("Load limit:");
+            let response = ui
+                .add(
+                    egui::TextEdit::singleline(input)
+                        .hint_text("none")
+                        .desired_width(60.0),
+                )
+                .on_hover_text(
+                    "make -l: start no new jobs while the load average is above this. \
+                     Keeps a shared machine responsive during a build.",
+                );
+            match &parsed {
+                Ok(limit) => {
+                    if response.changed() && *limit != settings.build_load_limit {
+                        settings.build_load_limit = *limit;
+                        let _ = settings.save();
+                    }
+                }
+                Err(e) => {
+                    ui.label(RichText::new(format!("✗ {}", e)).color(egui::Color32::RED));
+                }
+            }
+            if let Some(flags) =
+                build_manager::make_flags(settings.build_jobs, settings.build_load_limit)
+            {
+                ui.label(
+                    RichText::new(format!("MAKEFLAGS=\"{}\"", flags))
+                        .small()
+                        .color(egui::Color32::GRAY),
+                );
+            }
+        });
+        parsed.map(|_| ())
+    }
+
+    fn launch_in_terminal(&mut self, requested_dir: &Path, settings: &AppSettings) {
+        let keep_sources = settings.build_keep_sources;
+        let work_dir = match build_manager::resolve_work_dir(requested_dir) {
+            Ok(path) => path,
+            Err(e) => {
+                self.log.push(LogLine {
+                    text: format!("Error: {}", e),
+                    level: LogLevel::Error,
+                });
+                return;
+            }
+        };
+        let use_makepkg = detect_use_makepkg(&work_dir);
+        let problem = keep_sources
+            .then(|| build_manager::keep_sources_problem(&work_dir, use_makepkg))
+            .flatten();
+        if let Some(problem) = problem {
+            self.log.push(LogLine {
+                text: format!("Error: cannot build without touching sources: {}", problem),
+                level: LogLevel::Error,
+            });
+            return;
+        }
+        let mut cmd = build_manager::build_command_line(use_makepkg, true, keep_sources);
+        let with_flags;
+        if let Some(flags) =
+            build_manager::make_flags(settings.build_jobs, settings.build_load_limit)
+        {
+            with_flags = format!("MAKEFLAGS='{}' {}", flags, cmd);
+            cmd = &with_flags;
+        }
+        let (text, level) = match build_manager::launch_in_terminal(&work_dir, cmd) {
+            Ok(terminal) => (
+                format!("==> Launched `{}` in {} ({})", cmd, terminal, work_dir.display()),
+                LogLevel::Stage,
+            ),
+            Err(e) => (format!("Error: {}", e), LogLevel::Error),
+        };
+        self.log.push(LogLine { text, level });
+        self.needs_terminal = false;
+    }
+
+    /// Run a pre- or post-build command in the build dir. Returns false if
+    /// it could not be started.
+    fn start_hook(&mut self, step: Step, command: &str, ctx: Context, use_pty: bool) -> bool {
+        let Some(work_dir) = self.run_dir.clone() else {
+            return false;
+        };
+        let name = if step == Step::PreBuild { "Pre-build" } else { "Post-build" };
+        self.log.push(LogLine {
+            text: format!("==> {}: {}", name, command),
+            level: LogLevel::Stage,
+        });
+        let args = match build_manager::parse_command(command) {
+            Ok(args) => args,
+            Err(e) => {
+                self.log.push(LogLine {
+                    text: format!("Error: invalid {} command: {}", name.to_lowercase(), e),
+                    level: LogLevel::Error,
+                });
+                return false;
+            }
+        };
+        self.step = step;
+        self.state = BuildState::Running;
+        let (tx, rx) = channel();
+        self.rx = Some(rx);
+        self.build_handle = Some(build_manager::start_command(
+            &args,
+            work_dir,
+            tx,
+            use_pty,
+            self.mirror.as_deref(),
+        ));
+        ctx.request_repaint();
+        true
+    }
+
+    /// Install the built packages with pacman/dpkg/rpm rather than makepkg
+    fn start_package_install(&mut self, args: Vec<String>, ctx: Context, use_pty: bool) {
+        let Some(dir) = self.built_packages.first().and_then(|p| p.parent()) else {
+            return;
+        };
+        let dir = dir.to_path_buf();
+        self.state = BuildState::Running;
+        self.step = Step::Install;
+        self.log.push(LogLine {
+            text: format!("==> Installing built packages: {}", args.join(" ")),
+            level: LogLevel::Stage,
+        });
+
+        let (tx, rx) = channel();
+        self.rx = Some(rx);
+        self.build_handle = Some(build_manager::start_command(
+            &args,
+            dir,
+            tx,
+            use_pty,
+            self.mirror.as_deref(),
+        ));
+        ctx.request_repaint();
+    }
+
+    fn start_install(&mut self, ctx: Context, use_pty: bool) {
+        let Some(work_dir) = self.last_makepkg_run.clone() else {
+            return;
+        };
+        self.state = BuildState::Running;
+        self.step = Step::Install;
+        self.log.push(LogLine {
+            text: "==> Installing built packages: makepkg --install --needed".to_string(),
+            level: LogLevel::Stage,
+        });
+
+        let (tx, rx) = channel();
+        self.rx = Some(rx);
+        self.build_handle = Some(build_manager::start_install(
+            work_dir,
+            tx,
+            use_pty,
+            self.mirror.as_deref(),
+        ));
         ctx.request_repaint();
     }
 }
 
-fn classify_line(text: &str) -> LogLevel {
-    if text.starts_with("==>") {
-        LogLevel::Stage
-    } else if text.contains("warning:") || text.contains("WARNING") {
-        LogLevel::Warning
-    } else if text.contains("error:") || text.contains("ERROR") || text.contains("FAILED") {
-        LogLevel::Error
+impl LogLevel {
+    /// Level a line is displayed, counted and navigated as
+    fn shown_as(self, warnings_as_errors: bool) -> LogLevel {
+        if warnings_as_errors && self == LogLevel::Warning {
+            LogLevel::Error
+        } else {
+            self
+        }
+    }
+
+    pub const ALL: [LogLevel; 5] = [
+        LogLevel::Normal,
+        LogLevel::Stage,
+        LogLevel::Warning,
+        LogLevel::Error,
+        LogLevel::Input,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            LogLevel::Normal => "Normal",
+            LogLevel::Stage => "Stage",
+            LogLevel::Warning => "Warning",
+            LogLevel::Error => "Error",
+            LogLevel::Input => "Input",
+        }
+    }
+
+    fn color(self) -> egui::Color32 {
+        match self {
+            LogLevel::Normal => egui::Color32::LIGHT_GRAY,
+            LogLevel::Stage => egui::Color32::GREEN,
+            LogLevel::Warning => egui::Color32::YELLOW,
+            LogLevel::Error => egui::Color32::RED,
+            LogLevel::Input => egui::Color32::LIGHT_BLUE,
+        }
+    }
+
+    fn prefix(self) -> &'static str {
+        match self {
+            LogLevel::Normal => "",
+            LogLevel::Stage => "[STAGE] ",
+            LogLevel::Warning => "[WARN] ",
+            LogLevel::Error => "[ERROR] ",
+            LogLevel::Input => "[INPUT] ",
+        }
+    }
+}
+
+/// Join log lines into plain text, optionally tagging each with its level.
+fn log_as_text<'a>(lines: impl IntoIterator<Item = &'a LogLine>, with_levels: bool) -> String {
+    let mut out = String::new();
+    for line in lines {
+        if with_levels {
+            out.push_str(line.level.prefix());
+        }
+        out.push_str(&line.text);
+        out.push('\n');
+    }
+    out
+}
+
+/// Detect distro from config to determine build command
+fn detect_use_makepkg(work_dir: &Path) -> bool {
+    let config_path = work_dir.join("customization.cfg");
+    if let Ok(config) = ConfigManager::load(&config_path) {
+        config.get_option("_distro").unwrap_or_default() == "Arch"
     } else {
-        LogLevel::Normal
+        false
+    }
+}
+
+/// Kernel release the build in `work_dir` should produce, from its
+/// customization.cfg (and PKGBUILD `pkgrel` for makepkg builds)
+fn expected_release(work_dir: &Path, use_makepkg: bool) -> Option<String> {
+    let config = ConfigManager::load(work_dir.join("customization.cfg")).ok()?;
+    let pkgrel = use_makepkg
+        .then(|| build_manager::pkgbuild_field(work_dir, "pkgrel"))
+        .flatten();
+    build_manager::expected_release(|key| config.get_option(key), pkgrel.as_deref())
+}
+
+/// Log line naming the kernel read from the built packages, warning when it
+/// differs from the one expected at build start
+fn built_release_line(built: Option<&str>, expected: Option<&str>) -> Option<LogLine> {
+    let built = built?;
+    let mismatch = expected.filter(|e| *e != built);
+    Some(LogLine {
+        text: match mismatch {
+            Some(expected) => format!("==> Built kernel {} (expected {})", built, expected),
+            None => format!("==> Built kernel {}", built),
+        },
+        level: if mismatch.is_some() { LogLevel::Warning } else { LogLevel::Stage },
+    })
+}
+
+/// The config tool customization.cfg asks linux-tkg to open before
+/// compiling, if any
+fn menunconfig_tool(work_dir: &Path) -> Option<&'static str> {
+    let value = ConfigManager::load(work_dir.join("customization.cfg"))
+        .ok()
+        .and_then(|c| c.get_option("_menunconfig"))?;
+    match value.as_str() {
+        "" | "0" | "false" => None,
+        "1" => Some("menuconfig"),
+        "2" => Some("nconfig"),
+        "3" => Some("xconfig"),
+        _ => Some("a config menu"),
+    }
+}
+
+/// Byte offset of the first ASCII case-insensitive occurrence of `needle`
+fn find_ignore_case(text: &str, needle: &str) -> Option<usize> {
+    if needle.is_empty() {
+        return None;
+    }
+    text.as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+/// One log row in its level's colour. Matches of `query` get a yellow
+/// background (orange on the `current` match line) and a `focused` error is
+/// shaded. Lines over [`LONG_LINE`] are cut there unless `expanded`; the full
+/// text stays in the log.
+fn log_line_job(
+    text: &str,
+    level: LogLevel,
+    query: &str,
+    current: bool,
+    focused: bool,
+    expanded: bool,
+    font: &egui::FontId,
+) -> LayoutJob {
+    let mut normal = TextFormat::simple(font.clone(), level.color());
+    if focused {
+        normal.background = egui::Color32::from_rgb(80, 20, 20);
+    }
+    let hit = TextFormat {
+        color: egui::Color32::BLACK,
+        background: if current {
+            egui::Color32::from_rgb(255, 150, 0)
+        } else {
+            egui::Color32::YELLOW
+        },
+        ..normal.clone()
+    };
+    let mut job = LayoutJob::default();
+    let mut rest = text;
+    let mut hidden = 0;
+    if !expanded && rest.len() > LONG_LINE {
+        let mut cut = LONG_LINE;
+        while !rest.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        hidden = rest.len() - cut;
+        rest = &rest[..cut];
+    }
+    while let Some(start) = find_ignore_case(rest, query) {
+        let end = start + query.len();
+        job.append(&rest[..start], 0.0, normal.clone());
+        job.append(&rest[start..end], 0.0, hit.clone());
+        rest = &rest[end..];
+    }
+    job.append(rest, 0.0, normal.clone());
+    if hidden > 0 {
+        job.append(
+            &format!(" …({} truncated, click to expand)…", format_bytes(hidden as u64)),
+            0.0,
+            TextFormat {
+                color: egui::Color32::GRAY,
+                italics: true,
+                ..normal
+            },
+        );
+    }
+    job
+}
+
+/// What an `_offline` build would miss locally; empty when `_offline` is
+/// off or everything is in place
+fn offline_problems(work_dir: &Path) -> Vec<String> {
+    let Ok(config) = ConfigManager::load(work_dir.join("customization.cfg")) else {
+        return Vec::new();
+    };
+    let enabled = |key: &str| matches!(config.get_option(key).as_deref(), Some("true" | "1"));
+    if !enabled("_offline") {
+        return Vec::new();
+    }
+
+    let mut problems = Vec::new();
+    let source_dir = match config.get_option("_kernel_source_folder").as_deref() {
+        None | Some("") | Some("default") => work_dir.join("linux-src-git"),
+        Some(dir) => work_dir.join(dir),
+    };
+    if !source_dir.join(".git").exists() {
+        problems.push(format!("No kernel git checkout in {}", source_dir.display()));
+    }
+    if !work_dir.join("linux-tkg-patches").is_dir() {
+        problems.push(format!("No linux-tkg-patches folder in {}", work_dir.display()));
+    }
+    problems
+}
+
+/// Output that means a curses config menu was started without a usable TTY
+fn needs_terminal(text: &str) -> bool {
+    text.contains("Error opening terminal")
+        || text.contains("Your display is too small to run Menuconfig")
+        || text.contains("Unable to determine the terminal")
+        || (text.contains("make") && (text.contains("menuconfig") || text.contains("nconfig")))
+}
+
+fn classify_line(text: &str) -> LogLevel {
+    match build_manager::classify_line(text) {
+        LineKind::Normal => LogLevel::Normal,
+        LineKind::Stage => LogLevel::Stage,
+        LineKind::Warning => LogLevel::Warning,
+        LineKind::Error => LogLevel::Error,
     }
 }
@@ -1,19 +1,66 @@
+use crate::core::config_export::{export_config, import_config};
 use crate::core::config_manager::ConfigManager;
+use crate::core::presets::Presets;
+use crate::core::{repo_manager, system, version};
+use crate::data::config_options::{
+    all_options, compressor_program, summary_chips, ConfigOption, OptionKind, COMPRESSOR_KEYS,
+    SECTIONS,
+};
+use crate::data::config_templates::{template_for_series, ConfigTemplate, GAMING_PRESET};
+use crate::settings::{self, AppSettings};
 use egui::Ui;
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::SystemTime;
+
+/// A profile's differences from the current values, shown before applying it
+struct ProfileCompare {
+    name: String,
+    /// `(key, current, profile)` for every key the profile would set;
+    /// `current` is None for keys the loaded file doesn't have
+    changes: Vec<(String, Option<String>, String)>,
+    /// Which changes to apply, parallel to `changes`
+    picked: Vec<bool>,
+}
 
 #[derive(Default)]
 pub struct ConfigTab {
     values: HashMap<String, String>,
+    /// Values as last loaded from or written to disk, used to list pending changes
+    saved_values: HashMap<String, String>,
+    show_review: bool,
+    /// customization.cfg as on disk when the review window was opened
+    review_disk: HashMap<String, String>,
+    profile_compare: Option<ProfileCompare>,
+    // Saved presets, loaded on first use
+    presets: Option<Presets>,
+    selected_preset: String,
+    preset_name: String,
+    // Folder of the last exported/imported file, where the dialogs open
+    share_dir: Option<std::path::PathBuf>,
+    // Open export/import file dialog; sends (export, picked file) when closed
+    share_rx: Option<Receiver<(bool, Option<PathBuf>)>>,
+    // Option filter; non-empty expands matching sections and hides the rest
+    search: String,
+    // Per-series template offer, shown once per series per session
+    template_offer: Option<&'static ConfigTemplate>,
+    templates_offered: HashSet<String>,
+    template_overwrite_edits: bool,
     loaded: bool,
     dirty: bool,
     status: String,
     config_path: Option<std::path::PathBuf>,
+    // Modification time of the file as last loaded/saved, to notice outside edits
+    loaded_mtime: Option<SystemTime>,
+    // Save was refused because the file changed on disk since it was loaded
+    save_conflict: bool,
+    window_focused: bool,
+    last_frame: u64,
 }
 
 impl ConfigTab {
-    pub fn ui(&mut self, ui: &mut Ui, linux_tkg_path: &Path) {
+    pub fn ui(&mut self, ui: &mut Ui, linux_tkg_path: &Path, settings: &mut AppSettings) {
         let config_path = linux_tkg_path.join("customization.cfg");
 
         // Reload if the path changed (e.g. user updated settings)
@@ -26,6 +73,16 @@ impl ConfigTab {
             self.load_config(&config_path);
         }
 
+        // Pick up edits made in an external editor when the window regains
+        // focus or this tab is shown again
+        let focused = ui.ctx().input(|i| i.viewport().focused.unwrap_or(true));
+        let frame = ui.ctx().cumulative_pass_nr();
+        if focused && (!self.window_focused || frame != self.last_frame + 1) {
+            self.check_external_changes(&config_path);
+        }
+        self.window_focused = focused;
+        self.last_frame = frame;
+
         ui.heading("⚙ Configuration Options");
         ui.add_space(4.0);
 
@@ -36,222 +93,782 @@ impl ConfigTab {
             if ui.button("🔄 Reload").clicked() {
                 self.load_config(&config_path);
             }
+            if ui
+                .button("🔍 Check for Changes")
+                .on_hover_text("Look for edits made to customization.cfg outside tkg-gui")
+                .clicked()
+            {
+                if self.changed_on_disk(&config_path) {
+                    self.check_external_changes(&config_path);
+                } else {
+                    self.status = "No external changes".to_string();
+                }
+            }
+            if ui
+                .button("📝 Edit in Editor")
+                .on_hover_text(
+                    "Open customization.cfg in $VISUAL, $EDITOR (in a terminal) or xdg-open. \
+                     The view reloads when you come back to this window.",
+                )
+                .clicked()
+            {
+                self.status = match system::open_in_editor(&config_path) {
+                    Ok(editor) => format!("Opened in {}", editor),
+                    Err(e) => format!("Error: {}", e),
+                };
+            }
+            if ui
+                .add_enabled(self.dirty, egui::Button::new("📝 Preview Changes…"))
+                .on_hover_text(
+                    "List every option you changed with its value on disk and the new one, \
+                     then apply or discard",
+                )
+                .clicked()
+            {
+                self.review_disk = ConfigManager::load(&config_path)
+                    .map(|c| c.get_all_options())
+                    .unwrap_or_else(|_| self.saved_values.clone());
+                self.show_review = true;
+            }
+            if ui
+                .button("🎮 Gaming Preset")
+                .on_hover_text(
+                    "BORE scheduler, 1000 Hz, fsync/NTSync, MGLRU, performance governor \
+                     and Clang thin LTO. Compare it with the current config and pick what \
+                     to apply; nothing is written until you save.",
+                )
+                .clicked()
+            {
+                let preset = GAMING_PRESET.iter().map(|(k, v)| (k.to_string(), v.to_string()));
+                self.compare_profile("Gaming Preset".to_string(), preset);
+            }
             if self.dirty {
                 ui.label(egui::RichText::new("● Modified").color(egui::Color32::YELLOW));
             }
             ui.label(&self.status);
         });
+        self.presets_ui(ui);
+        self.share_ui(ui);
+
+        if ui
+            .checkbox(&mut settings.show_advanced_config, "Show advanced options")
+            .on_hover_text("Show low-level scheduler, compiler and build internals")
+            .changed()
+        {
+            if let Err(e) = settings.save() {
+                self.status = format!("Error saving settings: {}", e);
+            }
+        }
+        let advanced = settings.show_advanced_config;
+
+        self.support_line(ui, linux_tkg_path, settings);
+
+        if self.show_review {
+            self.review_window(ui.ctx(), &config_path);
+        }
+
+        if self.profile_compare.is_some() {
+            self.compare_window(ui.ctx());
+        }
+
+        if self.save_conflict {
+            self.conflict_banner(ui, &config_path);
+        }
+
+        if let Some(template) = self.template_offer {
+            self.template_banner(ui, template);
+        }
+
+        if self.loaded {
+            self.missing_options_banner(ui);
+            self.summary_ui(ui);
+        }
 
         ui.add_space(8.0);
 
+        ui.horizontal(|ui| {
+            ui.label("🔍");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.search)
+                    .hint_text("Search options by name or key")
+                    .desired_width(250.0),
+            );
+            if !self.search.is_empty() && ui.small_button("✖").clicked() {
+                self.search.clear();
+            }
+        });
+        let query = self.search.trim().to_lowercase();
+        let cpusched = self.values.get("_cpusched").cloned().unwrap_or_default();
+        // Matches the search would list with advanced options shown
+        let hidden_matches = if query.is_empty() || advanced {
+            0
+        } else {
+            SECTIONS
+                .iter()
+                .flat_map(|s| s.options.iter().map(move |o| (s.advanced || o.advanced, o)))
+                .filter(|&(is_advanced, o)| {
+                    is_advanced && self.option_shown(o, &cpusched) && o.matches(&query)
+                })
+                .count()
+        };
+
         egui::ScrollArea::vertical().show(ui, |ui| {
-            // CPU Scheduling
-            egui::CollapsingHeader::new("CPU Scheduling")
-                .default_open(true)
-                .show(ui, |ui| {
-                    self.combo_option(ui, "_cpusched", "CPU Scheduler", &[
-                        ("", "Default"),
-                        ("pds", "PDS"),
-                        ("bmq", "BMQ"),
-                        ("bore", "BORE"),
-                        ("cfs", "CFS"),
-                        ("eevdf", "EEVDF"),
-                        ("upds", "UPDS"),
-                        ("muqss", "MuQSS"),
-                    ]);
-                    self.combo_option(ui, "_sched_yield_type", "Sched Yield Type", &[
-                        ("0", "No yield"),
-                        ("1", "Yield to better priority (default)"),
-                        ("2", "Expire timeslice"),
-                    ]);
-                    self.combo_option(ui, "_rr_interval", "Round Robin Interval", &[
-                        ("default", "Default"),
-                        ("2", "2ms"),
-                        ("4", "4ms"),
-                        ("6", "6ms"),
-                        ("8", "8ms"),
-                    ]);
+            let mut any_match = false;
+            for section in SECTIONS {
+                if section.advanced && !advanced {
+                    continue;
+                }
+                let header = egui::CollapsingHeader::new(section.title);
+                let header = if query.is_empty() {
+                    header.default_open(section.default_open)
+                } else {
+                    let matched = section.options.iter().any(|o| {
+                        (advanced || !o.advanced)
+                            && self.option_shown(o, &cpusched)
+                            && o.matches(&query)
+                    });
+                    if !matched {
+                        continue;
+                    }
+                    any_match = true;
+                    // Own id, so clearing the search brings back the normal open state
+                    header.id_salt(("search", section.title)).open(Some(true))
+                };
+                header.show(ui, |ui| {
+                    let mut shown = 0;
+                    for option in section.options {
+                        if option.advanced && !advanced {
+                            continue;
+                        }
+                        if !option.matches(&query) {
+                            continue;
+                        }
+                        if option.if_present && !self.values.contains_key(option.key) {
+                            continue;
+                        }
+                        if let Some((key, value)) = option.shown_when {
+                            shown += 1;
+                            if self.values.get(key).map(String::as_str) == Some(value) {
+                                self.option_ui(ui, option);
+                            }
+                            continue;
+                        }
+                        shown += 1;
+                        if !option.schedulers.is_empty() {
+                            // Scheduler tunables sit indented under the scheduler combo
+                            if option.applies_to_scheduler(&cpusched) {
+                                ui.indent(option.key, |ui| self.option_ui(ui, option));
+                            }
+                            continue;
+                        }
+                        self.option_ui(ui, option);
+                        if option.key == "_menunconfig" {
+                            self.menunconfig_warning(ui);
+                        }
+                        if COMPRESSOR_KEYS.contains(&option.key) {
+                            self.compressor_warning(ui, option.key);
+                        }
+                    }
+                    if shown == 0 && query.is_empty() {
+                        ui.label(
+                            egui::RichText::new(
+                                "This linux-tkg's customization.cfg has none of these \
+                                 options.",
+                            )
+                            .small()
+                            .color(egui::Color32::GRAY),
+                        );
+                    }
                 });
+            }
+            if !query.is_empty() && !any_match {
+                ui.label(
+                    egui::RichText::new(format!("No options match \"{}\"", self.search.trim()))
+                        .color(egui::Color32::GRAY),
+                );
+            }
+            if hidden_matches > 0 {
+                ui.label(
+                    egui::RichText::new(format!(
+                        "{} match{} hidden (advanced)",
+                        hidden_matches,
+                        if hidden_matches == 1 { "" } else { "es" }
+                    ))
+                    .small()
+                    .color(egui::Color32::GRAY),
+                )
+                .on_hover_text("Tick \"Show advanced options\" to list them");
+            }
+        });
+    }
 
-            // Compiler
-            egui::CollapsingHeader::new("Compiler")
-                .default_open(true)
-                .show(ui, |ui| {
-                    self.combo_option(ui, "_compiler", "Compiler", &[
-                        ("", "GCC"),
-                        ("llvm", "LLVM/Clang"),
-                    ]);
-                    self.combo_option(ui, "_compileroptlevel", "Optimization Level", &[
-                        ("1", "-O2"),
-                        ("2", "-O3"),
-                        ("3", "-Os"),
-                    ]);
-                    self.combo_option(ui, "_lto_mode", "LTO Mode", &[
-                        ("", "Default"),
-                        ("no", "Disabled"),
-                        ("full", "Full LTO"),
-                        ("thin", "Thin LTO"),
-                    ]);
-                    self.combo_option(ui, "_llvm_ias", "LLVM Integrated Assembler", &[
-                        ("0", "Disabled"),
-                        ("1", "Enabled"),
-                    ]);
-                });
+    /// Whether `option` is drawn with the current values: the file has it
+    /// when it must, and its `shown_when` or scheduler condition holds
+    fn option_shown(&self, option: &ConfigOption, cpusched: &str) -> bool {
+        if option.if_present && !self.values.contains_key(option.key) {
+            return false;
+        }
+        match option.shown_when {
+            Some((key, value)) => self.values.get(key).map(String::as_str) == Some(value),
+            None => option.applies_to_scheduler(cpusched),
+        }
+    }
 
-            // Kernel Version & Source
-            egui::CollapsingHeader::new("Kernel Version & Source")
-                .default_open(true)
-                .show(ui, |ui| {
-                    self.text_option(ui, "_version", "Kernel Version");
-                    self.combo_option(ui, "_git_mirror", "Git Mirror", &[
-                        ("kernel.org", "kernel.org"),
-                        ("googlesource.com", "googlesource.com"),
-                        ("gregkh", "gregkh"),
-                        ("torvalds", "torvalds"),
-                    ]);
-                    self.combo_option(ui, "_distro", "Distribution", &[
-                        ("Arch", "Arch"),
-                        ("Ubuntu", "Ubuntu"),
-                        ("Debian", "Debian"),
-                        ("Fedora", "Fedora"),
-                        ("Suse", "Suse"),
-                        ("Gentoo", "Gentoo"),
-                        ("Generic", "Generic"),
-                    ]);
-                });
+    /// Options the tab defines that the loaded file doesn't set, e.g. after
+    /// linux-tkg added options to a config copied from an older release.
+    fn missing_options(&self) -> Vec<&'static ConfigOption> {
+        all_options()
+            .filter(|o| !o.if_present && !self.values.contains_key(o.key))
+            .collect()
+    }
 
-            // CPU & Performance
-            egui::CollapsingHeader::new("CPU & Performance")
-                .default_open(false)
-                .show(ui, |ui| {
-                    self.combo_option(ui, "_processor_opt", "Processor Optimization", &[
-                        ("", "Default"),
-                        ("x86-64", "x86-64 (baseline)"),
-                        ("x86-64-v2", "x86-64-v2 (~2008+)"),
-                        ("x86-64-v3", "x86-64-v3 (~2013+)"),
-                        ("x86-64-v4", "x86-64-v4 (Skylake/Zen4+)"),
-                        ("native", "Native (auto-detect)"),
-                        ("znver5", "Zen 5 (Ryzen 9000)"),
-                        ("znver4", "Zen 4 (Ryzen 7000/8000)"),
-                        ("znver3", "Zen 3 (Ryzen 5000/6000)"),
-                        ("znver2", "Zen 2 (Ryzen 3000/4000)"),
-                        ("znver1", "Zen 1 (Ryzen 1000/2000)"),
-                        ("arrowlake-s", "Arrow Lake-S (Core Ultra 200)"),
-                        ("raptorlake", "Raptor Lake (13th/14th gen)"),
-                        ("alderlake", "Alder Lake (12th gen)"),
-                        ("skylake", "Skylake (6th-9th gen)"),
-                    ]);
-                    self.combo_option(ui, "_timer_freq", "Timer Frequency", &[
-                        ("100", "100 Hz"),
-                        ("250", "250 Hz"),
-                        ("300", "300 Hz"),
-                        ("500", "500 Hz"),
-                        ("750", "750 Hz"),
-                        ("1000", "1000 Hz"),
-                    ]);
-                    self.combo_option(ui, "_tickless", "Tickless Mode", &[
-                        ("0", "Periodic"),
-                        ("1", "Full"),
-                        ("2", "Idle"),
-                    ]);
-                    self.combo_option(ui, "_tcp_cong_alg", "TCP Congestion Algorithm", &[
-                        ("", "Default"),
-                        ("yeah", "YeAH"),
-                        ("bbr", "BBR"),
-                        ("cubic", "CUBIC"),
-                        ("reno", "Reno"),
-                        ("vegas", "Vegas"),
-                        ("westwood", "Westwood"),
-                    ]);
-                    self.combo_option(ui, "_default_cpu_gov", "Default CPU Governor", &[
-                        ("", "Default"),
-                        ("performance", "Performance"),
-                        ("ondemand", "Ondemand"),
-                        ("schedutil", "Schedutil"),
-                    ]);
-                    self.combo_option(ui, "_rqshare", "RQ Share", &[
-                        ("none", "None"),
-                        ("smt", "SMT"),
-                        ("mc", "MC"),
-                        ("mc-llc", "MC-LLC"),
-                        ("smp", "SMP"),
-                        ("all", "All"),
-                    ]);
-                });
+    fn missing_options_banner(&mut self, ui: &mut Ui) {
+        let missing = self.missing_options();
+        if missing.is_empty() {
+            return;
+        }
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            ui.label(
+                egui::RichText::new(format!(
+                    "⚠ {} option(s) present in the current schema are missing from your config",
+                    missing.len()
+                ))
+                .color(egui::Color32::YELLOW),
+            )
+            .on_hover_text(
+                missing
+                    .iter()
+                    .map(|o| format!("{} ({})", o.key, o.label))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            );
+            if ui
+                .button("Add with Defaults")
+                .on_hover_text(
+                    "Add the missing keys with the values shown below; save to write them",
+                )
+                .clicked()
+            {
+                for option in missing {
+                    self.values
+                        .insert(option.key.to_string(), option.default_value().to_string());
+                }
+                self.dirty = true;
+            }
+        });
+    }
 
-            // Configuration Management
-            egui::CollapsingHeader::new("Configuration Management")
-                .default_open(false)
-                .show(ui, |ui| {
-                    self.text_option(ui, "_configfile", "Config File Path");
-                    self.combo_option(ui, "_config_updating", "Config Updating", &[
-                        ("olddefconfig", "olddefconfig"),
-                        ("oldconfig", "oldconfig"),
-                    ]);
-                    self.text_option(ui, "_kernel_work_folder", "Kernel Work Folder");
-                    self.text_option(ui, "_kernel_source_folder", "Kernel Source Folder");
-                    self.checkbox_option(ui, "_offline", "Offline Mode");
-                    self.checkbox_option(ui, "_nofallback", "No Fallback (exit on error)");
-                });
+    fn option_ui(&mut self, ui: &mut Ui, option: &ConfigOption) {
+        let response = ui
+            .scope(|ui| match option.kind {
+                OptionKind::Combo(choices) => {
+                    self.combo_option(ui, option.key, option.label, choices)
+                }
+                OptionKind::Check => self.checkbox_option(ui, option.key, option.label),
+                OptionKind::Text => self.text_option(ui, option.key, option.label),
+            })
+            .response;
+        if let Some(help) = option.help {
+            response.on_hover_text(help);
+        }
+    }
 
-            // Patches & Features
-            egui::CollapsingHeader::new("Patches & Features")
-                .default_open(false)
-                .show(ui, |ui| {
-                    self.checkbox_option(ui, "_user_patches", "User Patches");
-                    self.checkbox_option(ui, "_user_patches_no_confirm", "Skip User Patch Confirm");
-                    self.text_option(ui, "_community_patches", "Community Patches");
-                    self.checkbox_option(ui, "_clear_patches", "Clear Linux Patches");
-                    self.checkbox_option(ui, "_openrgb", "OpenRGB");
-                    self.checkbox_option(ui, "_acs_override", "ACS Override");
-                    self.checkbox_option(ui, "_preempt_rt", "PREEMPT_RT");
-                    self.checkbox_option(ui, "_fsync_backport", "Fsync Backport");
-                    self.checkbox_option(ui, "_fsync_legacy", "Fsync Legacy");
-                    self.checkbox_option(ui, "_ntsync", "NTSync");
-                    self.checkbox_option(ui, "_zenify", "Zenify");
-                    self.checkbox_option(ui, "_glitched_base", "Glitched Base");
-                    self.checkbox_option(ui, "_mglru", "MGLRU (Multi-Gen LRU)");
-                    self.checkbox_option(ui, "_irq_threading", "Force IRQ Threading");
-                    self.checkbox_option(ui, "_smt_nice", "SMT Nice");
-                    self.checkbox_option(ui, "_random_trust_cpu", "Trust CPU RNG");
-                });
+    fn menunconfig_warning(&self, ui: &mut Ui) {
+        if matches!(
+            self.values.get("_menunconfig").map(String::as_str),
+            Some("1") | Some("2")
+        ) {
+            ui.label(
+                egui::RichText::new(
+                    "⚠ menuconfig/nconfig need a real terminal: \
+                     use \"Build in Terminal\" on the Build tab.",
+                )
+                .small()
+                .color(egui::Color32::YELLOW),
+            );
+        }
+    }
+
+    /// Warn when the compressor chosen for `key` isn't installed
+    fn compressor_warning(&self, ui: &mut Ui, key: &str) {
+        let Some(program) = self.values.get(key).and_then(|v| compressor_program(v)) else {
+            return;
+        };
+        if system::find_in_path(program).is_none() {
+            ui.label(
+                egui::RichText::new(format!(
+                    "⚠ {} is not installed; the build will fail when it compresses.",
+                    program
+                ))
+                .small()
+                .color(egui::Color32::YELLOW),
+            );
+        }
+    }
+
+    /// Open the comparison of `profile` against the current values
+    fn compare_profile(
+        &mut self,
+        name: String,
+        profile: impl IntoIterator<Item = (String, String)>,
+    ) {
+        let changes: Vec<_> = profile
+            .into_iter()
+            .filter(|(k, v)| self.values.get(k) != Some(v))
+            .map(|(k, v)| {
+                let current = self.values.get(&k).cloned();
+                (k, current, v)
+            })
+            .collect();
+        if changes.is_empty() {
+            self.status = format!("Config already matches the {}", name);
+            return;
+        }
+        // Keys this customization.cfg lacks may belong to another linux-tkg
+        // release; they are only added when ticked
+        self.profile_compare = Some(ProfileCompare {
+            name,
+            picked: changes.iter().map(|(_, current, _)| current.is_some()).collect(),
+            changes,
+        });
+    }
+
+    /// Write the current values to a file to share, or merge one back in
+    fn share_ui(&mut self, ui: &mut Ui) {
+        if let Some((export, picked)) = self.share_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.share_rx = None;
+            // None when the dialog was cancelled
+            if let Some(file) = picked {
+                self.share_file(export, &file);
+            }
+        }
+
+        let idle = self.loaded && self.share_rx.is_none();
+        let mut export = false;
+        let mut import = false;
+        ui.horizontal(|ui| {
+            ui.label("Share:");
+            export = ui
+                .add_enabled(idle, egui::Button::new("📤 Export Config…"))
+                .on_hover_text(
+                    "Write every current value, including unsaved edits. A .cfg name \
+                     writes customization.cfg lines, anything else JSON.",
+                )
+                .clicked();
+            import = ui
+                .add_enabled(idle, egui::Button::new("📥 Import Config…"))
+                .on_hover_text(
+                    "Merge an export or a customization.cfg into the current values; \
+                     nothing is written until you save. Options this linux-tkg doesn't \
+                     know are skipped.",
+                )
+                .clicked();
+        });
+        if !export && !import {
+            return;
+        }
+
+        // The dialog blocks until closed; keep it off the UI thread
+        let (tx, rx) = channel();
+        self.share_rx = Some(rx);
+        let start_dir = self.share_dir.clone().or_else(|| settings::home_dir().ok());
+        let ctx = ui.ctx().clone();
+        std::thread::spawn(move || {
+            let mut dialog = rfd::FileDialog::new()
+                .add_filter("tkg-gui export", &["json"])
+                .add_filter("customization.cfg", &["cfg"]);
+            if let Some(dir) = start_dir {
+                dialog = dialog.set_directory(dir);
+            }
+            let picked = if export {
+                dialog.set_file_name("tkg-config.json").save_file()
+            } else {
+                dialog.pick_file()
+            };
+            let _ = tx.send((export, picked));
+            ctx.request_repaint();
+        });
+    }
+
+    /// Export to or import from the file picked in [`Self::share_ui`]
+    fn share_file(&mut self, export: bool, file: &Path) {
+        self.share_dir = file.parent().map(Path::to_path_buf);
+
+        if export {
+            self.status = match export_config(&self.values, file) {
+                Ok(n) => format!("Exported {} options to {}", n, file.display()),
+                Err(e) => format!("Export failed: {}", e),
+            };
+        } else {
+            // Keys of the loaded file or the tab's schema; anything else is
+            // probably from another linux-tkg release
+            let known = |key: &str| {
+                self.values.contains_key(key) || all_options().any(|o| o.key == key)
+            };
+            match import_config(file, known) {
+                Ok(imported) => {
+                    let changed = imported
+                        .values
+                        .iter()
+                        .filter(|(k, v)| self.values.get(*k) != Some(*v))
+                        .count();
+                    self.values.extend(imported.values);
+                    self.dirty |= changed > 0;
+                    self.status = format!("Imported {} changed option(s)", changed);
+                    if !imported.unknown.is_empty() {
+                        self.status += &format!(
+                            "; ⚠ skipped {} unknown: {}",
+                            imported.unknown.len(),
+                            imported.unknown.join(", ")
+                        );
+                    }
+                }
+                Err(e) => self.status = format!("Import failed: {}", e),
+            }
+        }
+    }
+
+    /// Save the current values as a named preset, or compare one with the
+    /// current values to apply it
+    fn presets_ui(&mut self, ui: &mut Ui) {
+        let config_dir = AppSettings::config_dir();
+        if self.presets.is_none() {
+            match Presets::load(&config_dir) {
+                Ok(loaded) => self.presets = Some(loaded),
+                Err(e) => self.status = e,
+            }
+        }
+        let presets = self.presets.get_or_insert_with(Presets::default);
+        if !presets.presets.contains_key(&self.selected_preset) {
+            self.selected_preset = presets.presets.keys().next().cloned().unwrap_or_default();
+        }
+        let mut apply = false;
+        let mut delete = false;
+        let mut save = false;
+        ui.horizontal(|ui| {
+            ui.label("Presets:");
+            ui.add_enabled_ui(!presets.presets.is_empty(), |ui| {
+                egui::ComboBox::from_id_salt("config_preset")
+                    .selected_text(if presets.presets.is_empty() {
+                        "none saved"
+                    } else {
+                        self.selected_preset.as_str()
+                    })
+                    .show_ui(ui, |ui| {
+                        for name in presets.presets.keys() {
+                            ui.selectable_value(&mut self.selected_preset, name.clone(), name);
+                        }
+                    });
+                apply = ui
+                    .button("Apply…")
+                    .on_hover_text(
+                        "Compare the preset with the current config and pick what to apply; \
+                         options the preset doesn't set are left alone",
+                    )
+                    .clicked();
+                delete = ui.button("🗑").on_hover_text("Delete this preset").clicked();
+            });
+            ui.separator();
+            ui.add(
+                egui::TextEdit::singleline(&mut self.preset_name)
+                    .hint_text("preset name")
+                    .desired_width(120.0),
+            );
+            save = ui
+                .add_enabled(
+                    !self.preset_name.trim().is_empty(),
+                    egui::Button::new("💾 Save as Preset"),
+                )
+                .on_hover_text(
+                    "Save every current value except _version, including unsaved edits. \
+                     A preset with the same name is replaced.",
+                )
+                .clicked();
+        });
+
+        let name = self.selected_preset.clone();
+        if apply {
+            if let Some(values) = presets.get(&name).cloned() {
+                self.compare_profile(format!("preset \"{}\"", name), values);
+            }
+        } else if delete {
+            presets.remove(&name);
+            self.status = match presets.save(&config_dir) {
+                Ok(()) => format!("Deleted preset \"{}\"", name),
+                Err(e) => format!("Error saving presets: {}", e),
+            };
+        } else if save {
+            let name = self.preset_name.trim().to_string();
+            presets.insert(&name, &self.values);
+            self.status = match presets.save(&config_dir) {
+                Ok(()) => format!("Saved preset \"{}\"", name),
+                Err(e) => format!("Error saving presets: {}", e),
+            };
+            self.selected_preset = name;
+            self.preset_name.clear();
+        }
+    }
+
+    fn compare_window(&mut self, ctx: &egui::Context) {
+        let Some(compare) = self.profile_compare.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        let mut apply: Option<bool> = None;
 
-            // Build & Debug
-            egui::CollapsingHeader::new("Build & Debug")
-                .default_open(false)
-                .show(ui, |ui| {
-                    self.checkbox_option(ui, "_debugdisable", "Disable Debug");
-                    self.checkbox_option(ui, "_STRIP", "Strip Binaries");
-                    self.checkbox_option(ui, "_ftracedisable", "Disable Ftrace");
-                    self.checkbox_option(ui, "_numadisable", "Disable NUMA");
-                    self.checkbox_option(ui, "_misc_adds", "Misc Additions");
-                    self.checkbox_option(ui, "_kernel_on_diet", "Kernel on Diet");
-                    self.checkbox_option(ui, "_modprobeddb", "modprobed-db");
-                    self.text_option(ui, "_modprobeddb_db_path", "modprobed-db Path");
-                    self.checkbox_option(ui, "_config_fragments", "Config Fragments");
-                    self.checkbox_option(ui, "_config_fragments_no_confirm", "Skip Config Fragments Confirm");
-                    self.checkbox_option(ui, "_NUKR", "NUKR");
-                    self.checkbox_option(ui, "_force_all_threads", "Force All Threads");
-                    self.combo_option(ui, "_menunconfig", "Menu Config", &[
-                        ("0", "Disabled"),
-                        ("1", "menuconfig"),
-                        ("2", "nconfig"),
-                        ("3", "xconfig"),
-                    ]);
-                    self.combo_option(ui, "_install_after_building", "Install After Building", &[
-                        ("prompt", "Prompt"),
-                        ("true", "Yes"),
-                        ("false", "No"),
-                    ]);
-                    self.text_option(ui, "_NR_CPUS_value", "Max CPUs (NR_CPUS)");
+        egui::Window::new(format!("Compare with {}", compare.name))
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                let added = compare.changes.iter().filter(|c| c.1.is_none()).count();
+                ui.label(format!(
+                    "{} option(s) differ: {} changed, {} not in this file. Untick any you \
+                     want to keep.",
+                    compare.changes.len(),
+                    compare.changes.len() - added,
+                    added
+                ));
+                ui.add_space(4.0);
+                egui::ScrollArea::vertical()
+                    .id_salt("profile_compare")
+                    .max_height(320.0)
+                    .show(ui, |ui| {
+                        for ((key, current, value), picked) in
+                            compare.changes.iter().zip(compare.picked.iter_mut())
+                        {
+                            let Some(current) = current else {
+                                ui.checkbox(
+                                    picked,
+                                    egui::RichText::new(format!("{}: (new) \"{}\"", key, value))
+                                        .monospace(),
+                                )
+                                .on_hover_text(
+                                    "Not in this customization.cfg, so this linux-tkg may \
+                                     not know it. Tick to add it anyway.",
+                                );
+                                continue;
+                            };
+                            ui.checkbox(
+                                picked,
+                                egui::RichText::new(format!(
+                                    "{}: \"{}\" → \"{}\"",
+                                    key, current, value
+                                ))
+                                .monospace(),
+                            );
+                        }
+                    });
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    let any = compare.picked.iter().any(|p| *p);
+                    if ui.add_enabled(any, egui::Button::new("Apply Selected")).clicked() {
+                        apply = Some(false);
+                    }
+                    if ui.button("Apply All").clicked() {
+                        apply = Some(true);
+                    }
                 });
+            });
+
+        if let Some(all) = apply {
+            let Some(compare) = self.profile_compare.take() else {
+                return;
+            };
+            let mut applied = 0;
+            for ((key, _, value), picked) in compare.changes.into_iter().zip(compare.picked) {
+                if all || picked {
+                    self.values.insert(key, value);
+                    applied += 1;
+                }
+            }
+            self.dirty = !self.pending_changes().is_empty();
+            self.status = format!(
+                "Applied {} option(s) from the {}; review the changes and save",
+                applied, compare.name
+            );
+            self.show_review = self.dirty;
+        } else if !open {
+            self.profile_compare = None;
+        }
+    }
+
+    /// Offer the recommended defaults for `series` the first time it is selected.
+    pub fn offer_template(&mut self, series: &str) {
+        if self.templates_offered.insert(series.to_string()) {
+            self.template_offer = template_for_series(series);
+        }
+    }
+
+    fn template_banner(&mut self, ui: &mut Ui, template: &'static ConfigTemplate) {
+        // Keys the user has edited but not saved; kept unless they opt in
+        let edited: Vec<&str> = template
+            .values
+            .iter()
+            .map(|(k, _)| *k)
+            .filter(|k| self.values.get(*k) != self.saved_values.get(*k))
+            .collect();
+
+        ui.add_space(4.0);
+        ui.group(|ui| {
+            ui.label(
+                egui::RichText::new(format!(
+                    "💡 Recommended defaults for {}: {}",
+                    template.series, template.description
+                ))
+                .strong(),
+            );
+            for (key, value) in template.values {
+                let current = self.values.get(*key).map(String::as_str).unwrap_or("");
+                if current != *value {
+                    ui.monospace(format!("  {}: \"{}\" → \"{}\"", key, current, value));
+                }
+            }
+            if !edited.is_empty() {
+                ui.checkbox(
+                    &mut self.template_overwrite_edits,
+                    format!("Also overwrite my unsaved edits ({})", edited.join(", ")),
+                );
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Apply Defaults").clicked() {
+                    for (key, value) in template.values {
+                        if self.template_overwrite_edits || !edited.contains(key) {
+                            self.values.insert(key.to_string(), value.to_string());
+                        }
+                    }
+                    self.dirty = !self.pending_changes().is_empty();
+                    self.status = "Template applied; review and save to write it".to_string();
+                    self.template_offer = None;
+                }
+                if ui.button("Dismiss").clicked() {
+                    self.template_offer = None;
+                }
+            });
         });
     }
 
+    /// Pending edits as `(key, old, new)`, sorted by key. `old` is `None`
+    /// for keys not present in the file on disk.
+    fn pending_changes(&self) -> Vec<(String, Option<String>, String)> {
+        let mut changes: Vec<_> = self
+            .values
+            .iter()
+            .filter(|(k, v)| self.saved_values.get(*k) != Some(*v))
+            .map(|(k, v)| (k.clone(), self.saved_values.get(k).cloned(), v.clone()))
+            .collect();
+        changes.sort_by(|a, b| a.0.cmp(&b.0));
+        changes
+    }
+
+    fn review_window(&mut self, ctx: &egui::Context, config_path: &Path) {
+        // Only touched options, but compared with the file: that's what Apply overwrites
+        let changes: Vec<_> = self
+            .pending_changes()
+            .into_iter()
+            .map(|(key, _, new)| {
+                let old = self.review_disk.get(&key).cloned();
+                (key, old, new)
+            })
+            .filter(|(_, old, new)| old.as_ref() != Some(new))
+            .collect();
+        let mut open = true;
+        let mut to_discard: Option<String> = None;
+        let mut apply = false;
+
+        egui::Window::new("Review Pending Changes")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                if changes.is_empty() {
+                    ui.label("No pending changes.");
+                    return;
+                }
+                ui.label(format!("{} change(s) will be written to disk:", changes.len()));
+                ui.add_space(4.0);
+                egui::ScrollArea::vertical()
+                    .id_salt("config_review")
+                    .max_height(320.0)
+                    .show(ui, |ui| {
+                        for (key, old, new) in &changes {
+                            ui.horizontal(|ui| {
+                                if ui
+                                    .small_button("↩")
+                                    .on_hover_text("Discard this change")
+                                    .clicked()
+                                {
+                                    to_discard = Some(key.clone());
+                                }
+                                let old = old
+                                    .as_deref()
+                                    .map_or("(unset)".to_string(), |o| format!("\"{}\"", o));
+                                ui.monospace(format!("{}: {} → \"{}\"", key, old, new));
+                            });
+                        }
+                    });
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("💾 Apply").clicked() {
+                        apply = true;
+                    }
+                    if ui.button("Discard All").clicked() {
+                        self.values = self.saved_values.clone();
+                        self.dirty = false;
+                        self.show_review = false;
+                    }
+                });
+            });
+
+        if let Some(key) = to_discard {
+            match self.saved_values.get(&key) {
+                Some(old) => {
+                    self.values.insert(key, old.clone());
+                }
+                None => {
+                    self.values.remove(&key);
+                }
+            }
+            self.dirty = !self.pending_changes().is_empty();
+        }
+        if apply {
+            self.save_config(config_path);
+            self.show_review = false;
+        }
+        if !open {
+            self.show_review = false;
+        }
+    }
+
+    fn changed_on_disk(&self, path: &Path) -> bool {
+        let mtime = file_mtime(path);
+        self.loaded && mtime.is_some() && mtime != self.loaded_mtime
+    }
+
+    /// Reload if the file changed on disk since it was loaded, unless that
+    /// would discard unsaved edits.
+    fn check_external_changes(&mut self, path: &Path) {
+        if !self.changed_on_disk(path) {
+            return;
+        }
+        if self.dirty {
+            // loaded_mtime stays put so saving asks how to combine the edits
+            self.status = "customization.cfg changed on disk; saving will ask how to combine \
+                           it with your edits"
+                .to_string();
+        } else {
+            self.load_config(path);
+            self.status = "Reloaded external changes".to_string();
+        }
+    }
+
     fn load_config(&mut self, path: &Path) {
         match ConfigManager::load(path) {
             Ok(manager) => {
+                self.loaded_mtime = file_mtime(path);
                 self.values = manager.get_all_options();
+                self.saved_values = self.values.clone();
                 self.loaded = true;
                 self.dirty = false;
                 self.config_path = Some(path.to_path_buf());
@@ -263,7 +880,19 @@ impl ConfigTab {
         }
     }
 
+    /// Save, unless the file changed underneath us; then ask first
     fn save_config(&mut self, path: &Path) {
+        if self.changed_on_disk(path) {
+            self.save_conflict = true;
+            self.status = "Not saved: customization.cfg changed on disk".to_string();
+            return;
+        }
+        self.write_config(path);
+    }
+
+    /// Write every value over the file on disk
+    fn write_config(&mut self, path: &Path) {
+        self.save_conflict = false;
         match ConfigManager::load(path) {
             Ok(mut manager) => {
                 for (key, value) in &self.values {
@@ -272,6 +901,8 @@ impl ConfigTab {
                 match manager.save() {
                     Ok(()) => {
                         self.dirty = false;
+                        self.saved_values = self.values.clone();
+                        self.loaded_mtime = file_mtime(path);
                         self.status = "Config saved".to_string();
                     }
                     Err(e) => {
@@ -328,18 +959,156 @@ impl ConfigTab {
         }
     }
 
-    pub fn set_version(&mut self, version: &str) {
-        // Ensure version has 'v' prefix as required by linux-tkg
-        let version = if version.starts_with('v') {
-            version.to_string()
+    /// Chips for the most impactful settings, copyable for bug reports
+    fn summary_ui(&mut self, ui: &mut Ui) {
+        let chips = summary_chips(|key| self.values.get(key).cloned());
+        egui::CollapsingHeader::new("📋 Build Summary")
+            .default_open(true)
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    if chips.is_empty() {
+                        ui.label(egui::RichText::new("All defaults").color(egui::Color32::GRAY));
+                    }
+                    for chip in &chips {
+                        egui::Frame::none()
+                            .fill(ui.visuals().faint_bg_color)
+                            .rounding(4.0)
+                            .inner_margin(egui::Margin::symmetric(6.0, 2.0))
+                            .show(ui, |ui| {
+                                ui.label(egui::RichText::new(chip).small());
+                            });
+                    }
+                    if !chips.is_empty()
+                        && ui
+                            .small_button("📋 Copy")
+                            .on_hover_text("Copy as one line, e.g. for a bug report")
+                            .clicked()
+                    {
+                        ui.ctx().copy_text(chips.join(" · "));
+                        self.status = "Summary copied".to_string();
+                    }
+                });
+            });
+    }
+
+    /// Offer reload, merge or overwrite after a save hit external changes
+    fn conflict_banner(&mut self, ui: &mut Ui, path: &Path) {
+        let disk = ConfigManager::load(path)
+            .map(|m| m.get_all_options())
+            .unwrap_or_default();
+        let pending = self.pending_changes();
+        // Keys both we and the external edit changed; ours win on merge
+        let both: Vec<&str> = pending
+            .iter()
+            .filter(|(key, old, _)| disk.get(key) != old.as_ref())
+            .map(|(key, _, _)| key.as_str())
+            .collect();
+
+        ui.group(|ui| {
+            ui.label(
+                egui::RichText::new(
+                    "⚠ customization.cfg was changed outside tkg-gui since it was loaded.",
+                )
+                .color(egui::Color32::YELLOW),
+            );
+            if !both.is_empty() {
+                ui.label(format!("Changed on both sides: {}", both.join(", ")));
+            }
+            ui.horizontal(|ui| {
+                if ui
+                    .button("🔄 Reload")
+                    .on_hover_text("Use the file on disk and drop your edits")
+                    .clicked()
+                {
+                    self.save_conflict = false;
+                    self.load_config(path);
+                }
+                if ui
+                    .button("🔀 Merge")
+                    .on_hover_text(format!(
+                        "Apply your {} change(s) on top of the file on disk",
+                        pending.len()
+                    ))
+                    .clicked()
+                {
+                    self.merge_config(path);
+                }
+                if ui
+                    .button("Overwrite")
+                    .on_hover_text("Write all values shown here, replacing the external edits")
+                    .clicked()
+                {
+                    self.write_config(path);
+                }
+                if ui.button("Cancel").clicked() {
+                    self.save_conflict = false;
+                }
+            });
+        });
+    }
+
+    /// Reload the file from disk and re-apply only the pending edits
+    fn merge_config(&mut self, path: &Path) {
+        let pending = self.pending_changes();
+        let manager = match ConfigManager::load(path) {
+            Ok(manager) => manager,
+            Err(e) => {
+                self.status = format!("Error loading config: {}", e);
+                return;
+            }
+        };
+        self.loaded_mtime = file_mtime(path);
+        self.values = manager.get_all_options();
+        self.saved_values = self.values.clone();
+        for (key, _, value) in &pending {
+            self.values.insert(key.clone(), value.clone());
+        }
+        self.write_config(path);
+        if !self.dirty {
+            self.status = format!("Merged {} change(s) with the file on disk", pending.len());
+        }
+    }
+
+    /// The kernel series the linux-tkg clone supports, and a warning when
+    /// `_version` falls outside them
+    fn support_line(&self, ui: &mut Ui, linux_tkg_path: &Path, settings: &AppSettings) {
+        let detected = repo_manager::detect_supported_series(linux_tkg_path);
+        let source = if !settings.supported_series_override.is_empty() {
+            "override"
+        } else if !detected.is_empty() {
+            "detected"
         } else {
-            format!("v{}", version)
+            "built-in list"
         };
-        self.values.insert("_version".to_string(), version);
+        let supported =
+            repo_manager::supported_series(linux_tkg_path, &settings.supported_series_override);
+        if let Some((oldest, newest)) = repo_manager::supported_range(&supported) {
+            ui.label(
+                egui::RichText::new(format!(
+                    "linux-tkg supports kernels {}–{} ({})",
+                    oldest, newest, source
+                ))
+                .small()
+                .color(egui::Color32::GRAY),
+            )
+            .on_hover_text(supported.join(", "));
+        }
+        let warning = self
+            .values
+            .get("_version")
+            .filter(|v| !v.is_empty())
+            .and_then(|v| repo_manager::unsupported_version_warning(v, &supported));
+        if let Some(warning) = warning {
+            ui.label(egui::RichText::new(format!("⚠ {}", warning)).color(egui::Color32::YELLOW));
+        }
+    }
+
+    pub fn set_version(&mut self, version: &str) {
+        // linux-tkg requires the 'v' prefix
+        self.values.insert("_version".to_string(), version::with_v(version));
         self.dirty = true;
     }
 
-    #[allow(dead_code)]
     pub fn get_version(&self) -> Option<String> {
         self.values.get("_version").cloned()
     }
@@ -349,5 +1118,8 @@ impl ConfigTab {
         let config_path = linux_tkg_path.join("customization.cfg");
         self.save_config(&config_path);
     }
+}
 
+fn file_mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
 }
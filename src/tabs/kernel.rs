@@ -1,16 +1,32 @@
-use crate::core::kernel_downloader::{self, DownloadProgress};
+use crate::core::http_client::{download_command, Downloader};
+use crate::core::kernel_downloader::{self, CachedKernel, DownloadProgress};
+use crate::core::repo_manager;
+use crate::core::task::CancelToken;
+use crate::core::version;
+use crate::core::work_dir;
+use crate::settings::AppSettings;
 use crate::core::kernel_fetcher::{
-    self, get_previous_version, CommitInfo, FetchResult, ShortlogResult, VersionInfo,
+    self, get_previous_version, latest_per_series, AnnotationResult, CommitInfo, FetchResult, ShortlogResult,
+    VersionInfo,
 };
 use egui::{Context, RichText, Ui};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
 use std::thread;
 
 pub struct KernelTab {
     versions: Vec<VersionInfo>,
+    // Newest point release of each series in `versions`
+    latest_in_series: HashSet<String>,
     filter: String,
+    // List mainline -rcN tags alongside releases
+    show_rc: bool,
     pub selected: Option<String>,
+    // Version selected last session, selected once the fetched list has it
+    restore: Option<String>,
     fetch_rx: Option<Receiver<FetchResult>>,
     shortlog_rx: Option<Receiver<ShortlogResult>>,
     status: String,
@@ -18,42 +34,84 @@ pub struct KernelTab {
     shortlog: Vec<CommitInfo>,
     shortlog_status: String,
     comparing_versions: Option<(String, String)>,
+    // Tag annotations, cached per version so refreshes don't refetch them
+    annotations: HashMap<String, Option<String>>,
+    annotation_rx: Option<Receiver<AnnotationResult>>,
+    annotation_status: String,
     // Download state
     download_rx: Option<Receiver<DownloadProgress>>,
     download_status: String,
     download_progress: Option<(u64, Option<u64>)>, // (downloaded, total)
     downloaded_path: Option<PathBuf>,
+    download_cancel: Option<CancelToken>,
+    // Auto-prune of old downloads once a new one completes
+    prune_after_download: bool,
+    prune_pending: Vec<CachedKernel>,
+    prune_rx: Option<Receiver<Result<Vec<String>, String>>>,
+    prune_status: String,
 }
 
 impl Default for KernelTab {
     fn default() -> Self {
         Self {
             versions: Vec::new(),
+            latest_in_series: HashSet::new(),
             filter: String::new(),
+            show_rc: false,
             selected: None,
+            restore: None,
             fetch_rx: None,
             shortlog_rx: None,
             status: "Click 'Refresh' to fetch kernel versions".to_string(),
             shortlog: Vec::new(),
             shortlog_status: String::new(),
             comparing_versions: None,
+            annotations: HashMap::new(),
+            annotation_rx: None,
+            annotation_status: String::new(),
             download_rx: None,
             download_status: String::new(),
             download_progress: None,
             downloaded_path: None,
+            download_cancel: None,
+            prune_after_download: false,
+            prune_pending: Vec::new(),
+            prune_rx: None,
+            prune_status: String::new(),
         }
     }
 }
 
 impl KernelTab {
-    pub fn ui(&mut self, ui: &mut Ui, ctx: &Context, kernel_sources_dir: &Path) {
+    pub fn ui(
+        &mut self,
+        ui: &mut Ui,
+        ctx: &Context,
+        kernel_sources_dir: &Path,
+        supported_series: &[String],
+        settings: &AppSettings,
+    ) {
         // Drain any pending fetch results
         let mut should_clear_fetch_rx = false;
         if let Some(rx) = &self.fetch_rx {
             if let Ok(result) = rx.try_recv() {
                 match result {
-                    FetchResult::Done(versions) => {
+                    FetchResult::Done(mut versions) => {
                         self.status = format!("{} versions loaded", versions.len());
+                        for info in &mut versions {
+                            if let Some(annotation) = self.annotations.get(&info.version) {
+                                info.annotation = annotation.clone();
+                            }
+                        }
+                        self.latest_in_series = latest_per_series(&versions, self.show_rc);
+                        if let Some(version) = self.restore.take() {
+                            if self.selected.is_none()
+                                && (self.show_rc || !version::is_rc(&version))
+                                && versions.iter().any(|v| v.version == version)
+                            {
+                                self.selected = Some(version);
+                            }
+                        }
                         self.versions = versions;
                     }
                     FetchResult::Error(e) => {
@@ -88,8 +146,35 @@ impl KernelTab {
             self.shortlog_rx = None;
         }
 
+        // Drain annotation results
+        let mut should_clear_annotation_rx = false;
+        if let Some(rx) = &self.annotation_rx {
+            if let Ok(result) = rx.try_recv() {
+                match result {
+                    AnnotationResult::Done(version, annotation) => {
+                        self.annotation_status.clear();
+                        if let Some(info) = self.versions.iter_mut().find(|v| v.version == version) {
+                            info.annotation = annotation.clone();
+                        }
+                        self.annotations.insert(version, annotation);
+                    }
+                    AnnotationResult::Error(version, e) => {
+                        // Ignore late failures for a version that is no longer selected
+                        if self.selected.as_ref() == Some(&version) {
+                            self.annotation_status = format!("Could not load tag message: {}", e);
+                        }
+                    }
+                }
+                should_clear_annotation_rx = true;
+            }
+        }
+        if should_clear_annotation_rx {
+            self.annotation_rx = None;
+        }
+
         // Drain download progress updates
         let mut should_clear_download_rx = false;
+        let mut completed_version: Option<String> = None;
         if let Some(rx) = &self.download_rx {
             while let Ok(progress) = rx.try_recv() {
                 match progress {
@@ -106,8 +191,16 @@ impl KernelTab {
                         self.download_status = "Extracting...".to_string();
                         self.download_progress = None;
                     }
+                    DownloadProgress::Verifying => {
+                        self.download_status = "Verifying checksum...".to_string();
+                    }
                     DownloadProgress::Complete(path) => {
                         self.download_status = format!("✓ Downloaded to: {}", path.display());
+                        completed_version = path
+                            .file_name()
+                            .and_then(|n| n.to_str())
+                            .and_then(|n| n.strip_prefix("linux-"))
+                            .map(str::to_string);
                         self.downloaded_path = Some(path);
                         self.download_progress = None;
                         should_clear_download_rx = true;
@@ -117,11 +210,42 @@ impl KernelTab {
                         self.download_progress = None;
                         should_clear_download_rx = true;
                     }
+                    DownloadProgress::Cancelled => {
+                        self.download_status = "Download cancelled".to_string();
+                        self.download_progress = None;
+                        should_clear_download_rx = true;
+                    }
                 }
             }
         }
         if should_clear_download_rx {
             self.download_rx = None;
+            self.download_cancel = None;
+        }
+        if let Some(version) = completed_version {
+            if std::mem::take(&mut self.prune_after_download) && settings.persist_kernel_sources {
+                let candidates = kernel_downloader::prune_candidates(
+                    kernel_sources_dir,
+                    settings.kernel_keep_downloads,
+                    &version,
+                );
+                if settings.confirm_kernel_prune {
+                    self.prune_pending = candidates;
+                } else if !candidates.is_empty() {
+                    self.start_prune(candidates, ctx.clone(), kernel_sources_dir.to_path_buf());
+                }
+            }
+        }
+
+        // Drain prune results
+        if let Some(rx) = &self.prune_rx {
+            if let Ok(result) = rx.try_recv() {
+                self.prune_status = match result {
+                    Ok(removed) => format!("Removed old downloads: {}", removed.join(", ")),
+                    Err(e) => format!("✗ Could not remove old download: {}", e),
+                };
+                self.prune_rx = None;
+            }
         }
 
         ui.heading("🐧 Kernel Version Browser");
@@ -145,6 +269,15 @@ impl KernelTab {
             cols[0].horizontal(|ui| {
                 ui.label("Filter:");
                 ui.text_edit_singleline(&mut self.filter);
+                if ui
+                    .checkbox(&mut self.show_rc, "Show RC versions")
+                    .on_hover_text(
+                        "Include mainline release candidates, downloaded from git.kernel.org",
+                    )
+                    .changed()
+                {
+                    self.latest_in_series = latest_per_series(&self.versions, self.show_rc);
+                }
             });
 
             cols[0].add_space(4.0);
@@ -153,6 +286,7 @@ impl KernelTab {
             let filtered: Vec<_> = self
                 .versions
                 .iter()
+                .filter(|v| self.show_rc || !version::is_rc(&v.version))
                 .filter(|v| v.version.to_lowercase().contains(&filter_lower))
                 .collect();
 
@@ -180,6 +314,7 @@ impl KernelTab {
                                     self.shortlog.clear();
                                     self.shortlog_status.clear();
                                     self.comparing_versions = None;
+                                    self.annotation_status.clear();
                                 }
                             }
                             if let Some(date) = &info.date {
@@ -187,16 +322,40 @@ impl KernelTab {
                                     RichText::new(date).small().color(egui::Color32::GRAY),
                                 );
                             }
+                            if self.latest_in_series.contains(&info.version) {
+                                ui.label(
+                                    RichText::new("latest in series")
+                                        .small()
+                                        .color(egui::Color32::LIGHT_GREEN),
+                                )
+                                .on_hover_text(format!(
+                                    "Newest {} release",
+                                    version::series(&info.version)
+                                ));
+                            }
+                            if !repo_manager::is_version_supported(&info.version, supported_series) {
+                                ui.label(
+                                    RichText::new("⚠").small().color(egui::Color32::YELLOW),
+                                )
+                                .on_hover_text("Not in linux-tkg's supported kernel series");
+                            }
                         });
                     }
                 });
 
             // Right column: detail panel
-            self.detail_panel(&mut cols[1], ctx, kernel_sources_dir);
+            self.detail_panel(&mut cols[1], ctx, kernel_sources_dir, supported_series, settings);
         });
     }
 
-    fn detail_panel(&mut self, ui: &mut Ui, ctx: &Context, kernel_sources_dir: &Path) {
+    fn detail_panel(
+        &mut self,
+        ui: &mut Ui,
+        ctx: &Context,
+        kernel_sources_dir: &Path,
+        supported_series: &[String],
+        settings: &AppSettings,
+    ) {
         ui.group(|ui| {
             if let Some(selected) = &self.selected.clone() {
                 ui.heading(format!("📋 {}", selected));
@@ -206,12 +365,29 @@ impl KernelTab {
                     if let Some(date) = &info.date {
                         ui.label(format!("Released: {}", date));
                     }
+                    if let Some(annotation) = &info.annotation {
+                        ui.add_space(4.0);
+                        ui.label(RichText::new(annotation).italics());
+                    }
+                }
+
+                // Lazily fetch the tag message the first time a version is shown
+                if !self.annotations.contains_key(selected) && self.annotation_rx.is_none() {
+                    if self.annotation_status.is_empty() {
+                        self.start_annotation_fetch(selected.clone(), ctx.clone());
+                    } else {
+                        ui.label(
+                            RichText::new(&self.annotation_status)
+                                .small()
+                                .color(egui::Color32::GRAY),
+                        );
+                    }
                 }
 
                 ui.add_space(8.0);
 
                 // Find previous version to compare against
-                let prev_version = get_previous_version(selected, &self.versions);
+                let prev_version = get_previous_version(selected, &self.versions, self.show_rc);
 
                 if let Some(prev) = &prev_version {
                     ui.label(format!("Changes since {}", prev));
@@ -296,21 +472,88 @@ impl KernelTab {
                 ui.add_space(4.0);
 
                 let download_url = kernel_downloader::get_download_url(selected);
-                ui.label(
-                    RichText::new(format!("From: {}", download_url))
+                ui.horizontal(|ui| {
+                    ui.label(
+                        RichText::new(format!("From: {}", download_url))
+                            .small()
+                            .color(egui::Color32::GRAY),
+                    );
+                    let tarball = kernel_downloader::tarball_path(
+                        kernel_sources_dir,
+                        version::strip_v(selected),
+                    );
+                    copy_command_menu(ui, &[(download_url.clone(), tarball)], false);
+                });
+
+                if !repo_manager::is_version_supported(selected, supported_series) {
+                    ui.add_space(4.0);
+                    ui.label(
+                        RichText::new(format!(
+                            "⚠ linux-tkg does not list support for the {} series; \
+                             the build scripts may not handle this kernel.",
+                            version::series(selected)
+                        ))
                         .small()
-                        .color(egui::Color32::GRAY),
-                );
+                        .color(egui::Color32::YELLOW),
+                    );
+                }
 
                 ui.add_space(4.0);
 
                 let is_downloading = self.download_rx.is_some();
+                let previously_downloaded =
+                    kernel_downloader::has_download_record(kernel_sources_dir, selected);
                 ui.horizontal(|ui| {
+                    if previously_downloaded
+                        && ui
+                            .add_enabled(!is_downloading, egui::Button::new("♻ Verify & Reuse"))
+                            .on_hover_text(
+                                "Check the kept tarball's size and SHA-256 and the extracted \
+                                 tree before reusing this earlier download",
+                            )
+                            .clicked()
+                    {
+                        self.start_reuse(
+                            selected.clone(),
+                            ctx.clone(),
+                            kernel_sources_dir.to_path_buf(),
+                        );
+                    }
+
+                    let download_label = if previously_downloaded {
+                        "⬇ Re-download"
+                    } else {
+                        "⬇ Download Kernel Sources"
+                    };
                     if ui
-                        .add_enabled(!is_downloading, egui::Button::new("⬇ Download Kernel Sources"))
+                        .add_enabled(!is_downloading, egui::Button::new(download_label))
                         .clicked()
                     {
-                        self.start_download(selected.clone(), ctx.clone(), kernel_sources_dir.to_path_buf());
+                        self.start_download(
+                            selected.clone(),
+                            ctx.clone(),
+                            kernel_sources_dir.to_path_buf(),
+                            settings.verify_kernel_tree,
+                            settings.keep_kernel_tarball,
+                            settings.min_free_space(),
+                        );
+                    }
+
+                    // Only the download itself can be aborted, not extraction
+                    let can_cancel = self.download_progress.is_some()
+                        && self
+                            .download_cancel
+                            .as_ref()
+                            .is_some_and(|c| !c.is_cancelled());
+                    if is_downloading
+                        && ui
+                            .add_enabled(can_cancel, egui::Button::new("✖ Cancel"))
+                            .clicked()
+                    {
+                        if let Some(cancel) = &self.download_cancel {
+                            cancel.cancel();
+                        }
+                        self.download_status = "Cancelling...".to_string();
                     }
                 });
 
@@ -353,6 +596,8 @@ impl KernelTab {
                             .color(egui::Color32::LIGHT_GREEN),
                     );
                 }
+
+                self.prune_ui(ui, ctx, kernel_sources_dir, settings);
             } else {
                 ui.label("Select a version to see details");
             }
@@ -386,21 +631,115 @@ impl KernelTab {
         });
     }
 
-    fn start_download(&mut self, version: String, ctx: Context, kernel_sources_dir: PathBuf) {
+    fn start_annotation_fetch(&mut self, version: String, ctx: Context) {
+        let (tx, rx) = channel();
+        self.annotation_rx = Some(rx);
+
+        thread::spawn(move || {
+            let result = kernel_fetcher::fetch_annotation(&version);
+            let _ = tx.send(result);
+            ctx.request_repaint();
+        });
+    }
+
+    fn prune_ui(
+        &mut self,
+        ui: &mut Ui,
+        ctx: &Context,
+        kernel_sources_dir: &Path,
+        settings: &AppSettings,
+    ) {
+        if !self.prune_pending.is_empty() {
+            ui.add_space(4.0);
+            ui.group(|ui| {
+                ui.label(format!(
+                    "Keeping the last {} downloads. Delete these older ones?",
+                    settings.kernel_keep_downloads
+                ));
+                for kernel in &self.prune_pending {
+                    ui.label(RichText::new(format!("• linux-{}", kernel.version)).small());
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("🗑 Delete").clicked() {
+                        let candidates = std::mem::take(&mut self.prune_pending);
+                        self.start_prune(candidates, ctx.clone(), kernel_sources_dir.to_path_buf());
+                    }
+                    if ui.button("Keep").clicked() {
+                        self.prune_pending.clear();
+                    }
+                });
+            });
+        }
+        if self.prune_rx.is_some() {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Removing old downloads...");
+            });
+        } else if !self.prune_status.is_empty() {
+            ui.label(RichText::new(&self.prune_status).small().color(egui::Color32::GRAY));
+        }
+    }
+
+    fn start_prune(&mut self, candidates: Vec<CachedKernel>, ctx: Context, dest_dir: PathBuf) {
+        self.prune_status.clear();
+        let (tx, rx) = channel();
+        self.prune_rx = Some(rx);
+        thread::spawn(move || {
+            let result = candidates
+                .iter()
+                .map(|k| {
+                    kernel_downloader::remove_cached_kernel(&dest_dir, &k.version)
+                        .map(|_| k.version.clone())
+                })
+                .collect::<Result<Vec<_>, _>>();
+            let _ = tx.send(result);
+            ctx.request_repaint();
+        });
+    }
+
+    fn start_reuse(&mut self, version: String, ctx: Context, kernel_sources_dir: PathBuf) {
+        self.download_status = "Verifying previous download...".to_string();
+        self.download_progress = None;
+        self.downloaded_path = None;
+        self.prune_after_download = false;
+
+        let (tx, rx) = channel();
+        self.download_rx = Some(rx);
+
+        thread::spawn(move || {
+            let _ = kernel_downloader::reuse_kernel(&version, &kernel_sources_dir, tx);
+            ctx.request_repaint();
+        });
+    }
+
+    fn start_download(
+        &mut self,
+        version: String,
+        ctx: Context,
+        kernel_sources_dir: PathBuf,
+        verify: bool,
+        keep_tarball: bool,
+        min_free: u64,
+    ) {
+        if let Err(e) = work_dir::ensure_free_space(&kernel_sources_dir, min_free) {
+            self.download_status = format!("Error: {}", e);
+            return;
+        }
         self.download_status = "Starting download...".to_string();
         self.download_progress = None;
         self.downloaded_path = None;
+        self.prune_after_download = true;
+        self.prune_pending.clear();
 
         let (tx, rx) = channel();
         self.download_rx = Some(rx);
+        let cancel = CancelToken::default();
+        self.download_cancel = Some(cancel.clone());
 
         thread::spawn(move || {
             let dest_dir = kernel_sources_dir;
 
             // Spawn a repaint thread to keep UI updated during download
-            use std::sync::atomic::{AtomicBool, Ordering};
-            use std::sync::Arc;
-
             let running = Arc::new(AtomicBool::new(true));
             let running_clone = running.clone();
             let ctx_clone = ctx.clone();
@@ -412,7 +751,14 @@ impl KernelTab {
                 }
             });
 
-            let _ = kernel_downloader::download_kernel(&version, &dest_dir, tx);
+            let _ = kernel_downloader::download_kernel(
+                &version,
+                &dest_dir,
+                verify,
+                keep_tarball,
+                cancel.flag(),
+                tx,
+            );
 
             // Stop the repaint thread
             running.store(false, Ordering::Relaxed);
@@ -421,20 +767,81 @@ impl KernelTab {
         });
     }
 
+    /// Background jobs still running, for "Stop All". Reusing a previous
+    /// download and pruning old ones are short local jobs and aren't listed.
+    pub fn background_tasks(&self) -> Vec<&'static str> {
+        let mut tasks = Vec::new();
+        if self.download_cancel.as_ref().is_some_and(|c| !c.is_cancelled()) {
+            tasks.push("Kernel download");
+        }
+        if self.fetch_rx.is_some() {
+            tasks.push("Version list refresh");
+        }
+        if self.shortlog_rx.is_some() {
+            tasks.push("Changelog fetch");
+        }
+        if self.annotation_rx.is_some() {
+            tasks.push("Kernel tag message fetch");
+        }
+        tasks
+    }
+
+    /// Cancel the download and stop waiting for the fetches; their threads
+    /// finish on their own and the results are dropped.
+    pub fn stop_background(&mut self) -> Vec<&'static str> {
+        let stopped = self.background_tasks();
+        if let Some(cancel) = &self.download_cancel {
+            cancel.cancel();
+            self.download_status = "Cancelling...".to_string();
+        }
+        if self.fetch_rx.take().is_some() {
+            self.status = "Refresh stopped".to_string();
+        }
+        if self.shortlog_rx.take().is_some() {
+            self.shortlog_status = "Stopped".to_string();
+        }
+        if self.annotation_rx.take().is_some() {
+            // Non-empty status keeps the detail panel from refetching
+            self.annotation_status = "Tag message not loaded".to_string();
+        }
+        stopped
+    }
+
+    /// Select `version` once a fetched list confirms it still exists
+    pub fn restore_selection(&mut self, version: Option<String>) {
+        self.restore = version;
+    }
+
     pub fn get_selected_version(&self) -> Option<String> {
         self.selected.clone()
     }
 
     /// Extract major.minor from version string (e.g., "v6.13.1" -> "6.13")
     pub fn get_kernel_series(&self) -> Option<String> {
-        self.selected.as_ref().map(|v| {
-            let stripped = v.trim_start_matches('v');
-            let parts: Vec<&str> = stripped.split('.').collect();
-            if parts.len() >= 2 {
-                format!("{}.{}", parts[0], parts[1])
-            } else {
-                stripped.to_string()
-            }
-        })
+        self.selected.as_deref().map(version::series)
     }
 }
+
+/// Menu that copies a curl or wget command downloading each `(url, dest)`
+/// outside the app; `decompress` as in [`download_command`]
+pub fn copy_command_menu(ui: &mut Ui, files: &[(String, PathBuf)], decompress: bool) {
+    ui.menu_button("📋 Command", |ui| {
+        for tool in Downloader::ALL {
+            let command = files
+                .iter()
+                .map(|(url, dest)| download_command(tool, url, dest, decompress))
+                .collect::<Vec<_>>()
+                .join("\n");
+            if ui
+                .button(format!("Copy {} command", tool.name()))
+                .on_hover_text(&command)
+                .clicked()
+            {
+                ui.ctx().copy_text(command);
+                ui.close_menu();
+            }
+        }
+    })
+    .response
+    .on_hover_text("Copy a command to download this yourself");
+}
@@ -3,3 +3,4 @@ pub mod config;
 pub mod kernel;
 pub mod patches;
 pub mod settings;
+pub mod wizard;
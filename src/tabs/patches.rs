@@ -1,13 +1,22 @@
+use crate::core::patch_bundle::{export_bundle, import_bundle};
 use crate::core::patch_manager::{
-    delete_patch, download_patch, extract_filename_from_url, get_patch_dir, list_patches,
-    toggle_patch, DownloadInfo, DownloadResult, PatchEntry,
+    check_file_name, delete_patch, diff_lines, download_patch, extract_filename_from_url,
+    fetch_patch_text, download_batch, get_patch_dir, is_patch_url, list_patches, toggle_patch,
+    BatchItem, BatchProgress, DiffLine, DownloadInfo, DownloadResult, PatchEntry,
 };
 use crate::core::patch_registry::{
     check_update, PatchMeta, PatchRegistry, UpdateCheckResult, UpdateStatus,
 };
-use crate::data::catalog::{catalog_for_series, CatalogEntry};
+use crate::core::system;
+use crate::core::task::CancelToken;
+use crate::core::version;
+use crate::data::catalog::{catalog_for_series, group_by_category, CatalogEntryOwned};
+use crate::settings::{self, AppSettings};
+use crate::tabs::kernel::copy_command_menu;
+use crate::tabs::settings::expansion_hint;
 use chrono::Utc;
 use egui::{Color32, Context, RichText, Ui};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Receiver};
 use std::thread;
@@ -21,23 +30,78 @@ pub struct PatchesTab {
     download_rx: Option<Receiver<DownloadResult>>,
     status: String,
     last_url: String,
+    // Clipboard is checked for a URL once each time the URL section opens
+    clipboard_checked: bool,
 
     // Registry and catalog
     registry: PatchRegistry,
     catalog_filter: String,
+    catalog_tag: Option<String>,
     update_rx: Option<Receiver<UpdateCheckResult>>,
     update_status: String,
 
     // Track pending download metadata
     pending_download: Option<PendingDownload>,
+    /// Error of the last single download; its context stays in
+    /// `pending_download` until retried successfully or dismissed
+    download_error: Option<String>,
+    // Patch set archive path for export/import (None until seeded)
+    bundle_path: Option<String>,
+
+    // Resolved userpatches dir per (clone, series); the override wins
+    patch_dirs: HashMap<(PathBuf, String), PathBuf>,
+    dir_override: Option<PathBuf>,
+    dir_override_input: Option<String>,
+
+    // "Download All" over the visible catalog entries
+    batch_stop_on_failure: bool,
+    batch: Option<CatalogBatch>,
+
+    // Registry entries whose file was removed outside the app
+    missing_patches: Vec<PatchMeta>,
+
+    // On-disk vs upstream diff for a stale patch
+    diff_rx: Option<Receiver<Result<Vec<DiffLine>, String>>>,
+    diff_view: Option<PatchDiff>,
 
     // Track last data_dir to detect changes and reload registry
     last_data_dir: Option<PathBuf>,
+
+    // List scroll offsets, restored when the tab is shown again
+    catalog_scroll: f32,
+    installed_scroll: f32,
+    // Frame the tab was last drawn in; a gap means another tab was open
+    last_frame: u64,
+    restore_scroll: bool,
+}
+
+struct PatchDiff {
+    filename: String,
+    /// `None` while the upstream copy is being fetched
+    result: Option<Result<Vec<DiffLine>, String>>,
+}
+
+struct CatalogBatch {
+    items: Vec<BatchItem>,
+    catalog_ids: Vec<String>,
+    rx: Option<Receiver<BatchProgress>>,
+    cancel: CancelToken,
+    succeeded: Vec<String>,
+    failed: Vec<(String, String)>,
+    /// Not attempted because the batch stopped at a failure
+    skipped: Vec<String>,
 }
 
+/// Everything needed to run a single download again
+#[derive(Clone)]
 struct PendingDownload {
     url: String,
     catalog_id: Option<String>,
+    dest_path: PathBuf,
+    /// Pinned SHA-256 from the catalog entry
+    sha256: Option<String>,
+    /// Shown in status messages, e.g. the catalog entry name
+    label: String,
 }
 
 impl Default for PatchesTab {
@@ -50,18 +114,51 @@ impl Default for PatchesTab {
             download_rx: None,
             status: String::new(),
             last_url: String::new(),
+            clipboard_checked: false,
             registry: PatchRegistry::default(),
             catalog_filter: String::new(),
+            catalog_tag: None,
             update_rx: None,
             update_status: String::new(),
             pending_download: None,
+            download_error: None,
+            bundle_path: None,
+            patch_dirs: HashMap::new(),
+            dir_override: None,
+            dir_override_input: None,
+            batch_stop_on_failure: true,
+            batch: None,
+            missing_patches: Vec::new(),
+            diff_rx: None,
+            diff_view: None,
             last_data_dir: None,
+            catalog_scroll: 0.0,
+            installed_scroll: 0.0,
+            last_frame: 0,
+            restore_scroll: false,
         }
     }
 }
 
 impl PatchesTab {
-    pub fn ui(&mut self, ui: &mut Ui, ctx: &Context, linux_tkg_path: &Path, data_dir: &Path) {
+    pub fn ui(
+        &mut self,
+        ui: &mut Ui,
+        ctx: &Context,
+        linux_tkg_path: &Path,
+        data_dir: &Path,
+        settings: &mut AppSettings,
+    ) {
+        let frame = ctx.cumulative_pass_nr();
+        self.restore_scroll = frame > self.last_frame + 1;
+        self.last_frame = frame;
+
+        let dir_override = settings.patch_dir_override.as_deref().map(settings::expand_path);
+        if self.dir_override != dir_override {
+            self.dir_override = dir_override;
+            self.refresh_patches(linux_tkg_path);
+        }
+
         // Reload registry if data_dir changed
         if self.last_data_dir.as_deref() != Some(data_dir) {
             self.registry = PatchRegistry::load(data_dir);
@@ -69,26 +166,24 @@ impl PatchesTab {
         }
 
         // Drain download results
-        let mut download_complete = false;
         if let Some(rx) = &self.download_rx {
             if let Ok(result) = rx.try_recv() {
+                self.download_rx = None;
                 match result {
                     DownloadResult::Done(info) => {
                         self.handle_download_complete(info, data_dir);
                         self.refresh_patches(linux_tkg_path);
-                        download_complete = true;
                     }
                     DownloadResult::Error(e) => {
                         self.status = format!("Error: {}", e);
-                        download_complete = true;
+                        self.download_error = Some(e);
                     }
                 }
             }
         }
-        if download_complete {
-            self.download_rx = None;
-            self.pending_download = None;
-        }
+
+        // Drain batch download progress
+        self.drain_batch(linux_tkg_path, data_dir);
 
         // Drain update check results
         let mut updates_to_apply: Vec<(String, UpdateStatus)> = Vec::new();
@@ -103,6 +198,9 @@ impl PatchesTab {
                         UpdateCheckResult::Stale { key } => {
                             updates_to_apply.push((key, UpdateStatus::Stale));
                         }
+                        UpdateCheckResult::NoValidators { key } => {
+                            updates_to_apply.push((key, UpdateStatus::NoValidators));
+                        }
                         UpdateCheckResult::Error { key, reason } => {
                             updates_to_apply.push((key, UpdateStatus::CheckError(reason)));
                         }
@@ -131,6 +229,17 @@ impl PatchesTab {
             }
         }
 
+        // Drain diff result
+        if let Some(rx) = &self.diff_rx {
+            if let Ok(result) = rx.try_recv() {
+                if let Some(view) = &mut self.diff_view {
+                    view.result = Some(result);
+                }
+                self.diff_rx = None;
+            }
+        }
+        self.diff_window(ctx);
+
         // Auto-fill filename from URL
         if self.url_input != self.last_url {
             self.filename_input = extract_filename_from_url(&self.url_input);
@@ -141,9 +250,22 @@ impl PatchesTab {
 
         ui.horizontal(|ui| {
             ui.label("Kernel Series:");
-            ui.add(egui::TextEdit::singleline(&mut self.kernel_series).desired_width(60.0));
+            let response =
+                ui.add(egui::TextEdit::singleline(&mut self.kernel_series).desired_width(60.0));
+            // "v6.13.5" -> "6.13"
+            if response.lost_focus() {
+                self.kernel_series = version::series(&self.kernel_series);
+            }
+            if !self.series_valid() {
+                ui.label(
+                    RichText::new("✗ Use major.minor, e.g. 6.13; downloads are blocked")
+                        .color(Color32::RED),
+                );
+            }
         });
 
+        self.download_error_ui(ui, ctx);
+
         ui.add_space(8.0);
 
         // Catalog section
@@ -156,11 +278,20 @@ impl PatchesTab {
         ui.add_space(8.0);
 
         // URL download section
-        egui::CollapsingHeader::new("🔗 Download from URL")
+        let url_section = egui::CollapsingHeader::new("🔗 Download from URL")
             .default_open(false)
             .show(ui, |ui| {
+                if !self.clipboard_checked {
+                    self.clipboard_checked = true;
+                    if self.url_input.is_empty() {
+                        self.paste_url(false);
+                    }
+                }
                 self.url_download_ui(ui, ctx, linux_tkg_path, data_dir);
             });
+        if url_section.body_returned.is_none() {
+            self.clipboard_checked = false;
+        }
 
         ui.add_space(8.0);
 
@@ -168,7 +299,7 @@ impl PatchesTab {
         egui::CollapsingHeader::new("📂 Installed Patches")
             .default_open(true)
             .show(ui, |ui| {
-                self.installed_patches_ui(ui, ctx, linux_tkg_path, data_dir);
+                self.installed_patches_ui(ui, ctx, linux_tkg_path, data_dir, settings);
             });
     }
 
@@ -188,10 +319,33 @@ impl PatchesTab {
             );
         });
 
-        ui.add_space(4.0);
-
         let catalog = catalog_for_series(&self.kernel_series);
+        let patch_dir = self.patch_dir(linux_tkg_path);
         let filter_lower = self.catalog_filter.to_lowercase();
+        let tag = self.catalog_tag.clone();
+        let shown = |e: &CatalogEntryOwned| {
+            e.matches_filter(&filter_lower) && tag.as_ref().is_none_or(|t| e.tags.contains(t))
+        };
+
+        // Tag chips: click to filter by a tag, click again to clear
+        let mut tags: Vec<&str> = catalog
+            .iter()
+            .flat_map(|e| e.tags.iter().map(String::as_str))
+            .collect();
+        tags.sort_unstable();
+        tags.dedup();
+        if !tags.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                for tag in tags {
+                    let selected = self.catalog_tag.as_deref() == Some(tag);
+                    if ui.selectable_label(selected, format!("#{}", tag)).clicked() {
+                        self.catalog_tag = if selected { None } else { Some(tag.to_string()) };
+                    }
+                }
+            });
+        }
+
+        ui.add_space(4.0);
 
         if catalog.is_empty() {
             ui.label(
@@ -204,53 +358,125 @@ impl PatchesTab {
             return;
         }
 
-        egui::ScrollArea::vertical()
-            .id_salt("catalog")
-            .max_height(200.0)
+        let pending: Vec<CatalogEntryOwned> = catalog
+            .iter()
+            .filter(|e| shown(e))
+            .filter(|e| !self.missing_files(e).is_empty())
+            .cloned()
+            .collect();
+        let batch_running = self.batch.as_ref().is_some_and(|b| b.rx.is_some());
+        ui.horizontal(|ui| {
+            let idle = self.download_rx.is_none() && !batch_running && self.series_valid();
+            if ui
+                .add_enabled(
+                    idle && !pending.is_empty(),
+                    egui::Button::new(format!("⬇ Download All ({})", pending.len())),
+                )
+                .on_hover_text("Download every listed patch that is not installed yet")
+                .clicked()
+            {
+                self.start_batch_download(&pending, linux_tkg_path, ctx.clone());
+            }
+            ui.checkbox(&mut self.batch_stop_on_failure, "Stop on first failure");
+        });
+        self.batch_summary_ui(ui);
+
+        let output = self
+            .scroll_area("catalog", 200.0, self.catalog_scroll)
             .show(ui, |ui| {
-                for entry in catalog {
-                    if !filter_lower.is_empty()
-                        && !entry.name.to_lowercase().contains(&filter_lower)
-                        && !entry.description.to_lowercase().contains(&filter_lower)
-                    {
+                for (category, entries) in group_by_category(catalog) {
+                    let visible: Vec<CatalogEntryOwned> =
+                        entries.into_iter().filter(|e| shown(e)).collect();
+                    if visible.is_empty() {
                         continue;
                     }
-
-                    let filename = entry.filename_for_series(&self.kernel_series);
-                    let is_installed = self.patches.iter().any(|p| p.name == filename);
-
-                    ui.group(|ui| {
-                        ui.horizontal(|ui| {
-                            ui.strong(entry.name);
-
-                            if is_installed {
-                                ui.label(RichText::new("✓ installed").color(Color32::GREEN));
-                            } else {
-                                let is_downloading = self.download_rx.is_some();
-                                if ui
-                                    .add_enabled(
-                                        !is_downloading,
-                                        egui::Button::new("⬇ Download"),
-                                    )
-                                    .clicked()
-                                {
-                                    self.start_catalog_download(
-                                        entry,
-                                        linux_tkg_path,
-                                        data_dir,
-                                        ctx.clone(),
+                    egui::CollapsingHeader::new(format!("{} ({})", category, visible.len()))
+                        .id_salt(("catalog_category", &category))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for entry in visible {
+                                let file_count = entry.files_for_series(&self.kernel_series).len();
+                                let missing = self.missing_files(&entry).len();
+                                let is_installed = missing == 0;
+
+                                ui.group(|ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.strong(&entry.name);
+                                        if file_count > 1 {
+                                            ui.label(
+                                                RichText::new(format!("📚 {} files", file_count))
+                                                    .small()
+                                                    .color(Color32::GRAY),
+                                            );
+                                        }
+
+                                        if is_installed {
+                                            ui.label(RichText::new("✓ installed").color(Color32::GREEN));
+                                        } else {
+                                            if missing < file_count {
+                                                ui.label(
+                                                    RichText::new(format!(
+                                                        "⚠ {}/{} installed",
+                                                        file_count - missing,
+                                                        file_count
+                                                    ))
+                                                    .color(Color32::YELLOW),
+                                                );
+                                            }
+                                            let can_download = self.download_rx.is_none()
+                                                && !batch_running
+                                                && self.series_valid();
+                                            if ui
+                                                .add_enabled(
+                                                    can_download,
+                                                    egui::Button::new("⬇ Download"),
+                                                )
+                                                .clicked()
+                                            {
+                                                if file_count > 1 {
+                                                    self.start_batch_download(
+                                                        std::slice::from_ref(&entry),
+                                                        linux_tkg_path,
+                                                        ctx.clone(),
+                                                    );
+                                                } else {
+                                                    self.start_catalog_download(
+                                                        &entry,
+                                                        linux_tkg_path,
+                                                        data_dir,
+                                                        ctx.clone(),
+                                                    );
+                                                }
+                                            }
+                                        }
+                                        if self.series_valid() {
+                                            let files: Vec<(String, PathBuf)> = entry
+                                                .files_for_series(&self.kernel_series)
+                                                .into_iter()
+                                                .map(|(url, name)| (url, patch_dir.join(name)))
+                                                .collect();
+                                            copy_command_menu(ui, &files, true);
+                                        }
+                                    });
+                                    ui.label(
+                                        RichText::new(&entry.description)
+                                            .small()
+                                            .color(Color32::GRAY),
                                     );
-                                }
+                                    if let Some(homepage) = &entry.homepage {
+                                        let maintainer = entry.maintainer.as_deref().unwrap_or("unknown");
+                                        ui.hyperlink_to(RichText::new("🏠 Project page").small(), homepage)
+                                            .on_hover_text(format!(
+                                                "Maintained by {}\n{}",
+                                                maintainer, homepage
+                                            ));
+                                    }
+                                });
                             }
                         });
-                        ui.label(
-                            RichText::new(entry.description)
-                                .small()
-                                .color(Color32::GRAY),
-                        );
-                    });
                 }
             });
+        self.catalog_scroll = output.state.offset.y;
     }
 
     fn url_download_ui(
@@ -263,6 +489,13 @@ impl PatchesTab {
         ui.horizontal(|ui| {
             ui.label("URL:");
             ui.add(egui::TextEdit::singleline(&mut self.url_input).desired_width(400.0));
+            if ui
+                .button("📋 Paste")
+                .on_hover_text("Paste a patch URL from the clipboard")
+                .clicked()
+            {
+                self.paste_url(true);
+            }
         });
 
         ui.horizontal(|ui| {
@@ -274,6 +507,7 @@ impl PatchesTab {
 
         ui.horizontal(|ui| {
             let can_download = self.download_rx.is_none()
+                && self.series_valid()
                 && !self.url_input.is_empty()
                 && !self.filename_input.is_empty();
 
@@ -283,6 +517,10 @@ impl PatchesTab {
             {
                 self.start_url_download(linux_tkg_path, ctx.clone());
             }
+            if !self.url_input.is_empty() && !self.filename_input.is_empty() {
+                let dest = self.patch_dir(linux_tkg_path).join(&self.filename_input);
+                copy_command_menu(ui, &[(self.url_input.clone(), dest)], true);
+            }
 
             if !self.status.is_empty() {
                 ui.label(&self.status);
@@ -290,18 +528,41 @@ impl PatchesTab {
         });
     }
 
+    /// Fill the URL field from the clipboard if it holds a URL. `explicit`
+    /// reports a non-URL clipboard; the automatic check stays silent.
+    fn paste_url(&mut self, explicit: bool) {
+        match system::clipboard_text() {
+            Some(text) if is_patch_url(&text) => {
+                self.url_input = text.trim().to_string();
+                self.status = "URL pasted from clipboard".to_string();
+            }
+            _ if explicit => {
+                self.status = "Clipboard does not contain an http(s) URL".to_string();
+            }
+            _ => {}
+        }
+    }
+
     fn installed_patches_ui(
         &mut self,
         ui: &mut Ui,
         ctx: &Context,
         linux_tkg_path: &Path,
         data_dir: &Path,
+        settings: &mut AppSettings,
     ) {
-        let patch_dir = get_patch_dir(linux_tkg_path, &self.kernel_series);
+        let patch_dir = self.patch_dir(linux_tkg_path);
 
         ui.horizontal(|ui| {
             ui.label(format!("Dir: {}", patch_dir.display()));
+            if self.dir_override.is_some() {
+                ui.label(RichText::new("(override)").small().color(Color32::YELLOW));
+            } else if !patch_dir.is_dir() {
+                ui.label(RichText::new("(will be created)").small().color(Color32::GRAY));
+            }
         });
+        self.dir_override_ui(ui, linux_tkg_path, settings);
+        self.bundle_ui(ui, linux_tkg_path, data_dir);
 
         ui.horizontal(|ui| {
             if ui.button("📂 Open in File Manager").clicked() {
@@ -338,19 +599,63 @@ impl PatchesTab {
 
         ui.add_space(8.0);
 
+        if !self.missing_patches.is_empty() {
+            let mut to_redownload: Option<PatchMeta> = None;
+            let mut to_forget: Option<String> = None;
+            ui.label(
+                RichText::new("⚠ Registered patches missing from disk:").color(Color32::YELLOW),
+            );
+            for meta in &self.missing_patches {
+                ui.horizontal(|ui| {
+                    ui.label(&meta.filename);
+                    if ui.small_button("🔄 Re-download").clicked() {
+                        to_redownload = Some(meta.clone());
+                    }
+                    if ui.small_button("Forget").clicked() {
+                        to_forget = Some(meta.filename.clone());
+                    }
+                });
+            }
+            if let Some(meta) = to_redownload {
+                self.missing_patches.retain(|m| m.filename != meta.filename);
+                self.redownload(&meta, linux_tkg_path, ctx.clone());
+            }
+            if let Some(filename) = to_forget {
+                self.registry.remove(&self.kernel_series, &filename);
+                let _ = self.registry.save(data_dir);
+                self.missing_patches.retain(|m| m.filename != filename);
+            }
+            ui.add_space(8.0);
+        }
+
         if self.patches.is_empty() {
             ui.label("No patches installed for this kernel series");
             return;
         }
 
-        egui::ScrollArea::vertical()
-            .id_salt("installed")
-            .max_height(300.0)
+        let output = self
+            .scroll_area("installed", 300.0, self.installed_scroll)
             .show(ui, |ui| {
                 let mut to_toggle: Option<usize> = None;
                 let mut to_delete: Option<usize> = None;
                 let mut to_redownload: Option<String> = None;
                 let mut to_check: Option<PatchMeta> = None;
+                let mut to_diff: Option<(PatchMeta, PathBuf)> = None;
+
+                // Multi-file catalog entries: id -> (name, filenames in order)
+                let series_groups: HashMap<String, (String, Vec<String>)> =
+                    catalog_for_series(&self.kernel_series)
+                        .into_iter()
+                        .filter(|e| !e.files.is_empty())
+                        .map(|e| {
+                            let filenames = e
+                                .files_for_series(&self.kernel_series)
+                                .into_iter()
+                                .map(|(_, filename)| filename)
+                                .collect();
+                            (e.id, (e.name, filenames))
+                        })
+                        .collect();
 
                 for (i, patch) in self.patches.iter().enumerate() {
                     let meta = self.registry.get(&self.kernel_series, &patch.name);
@@ -379,15 +684,53 @@ impl PatchesTab {
 
                             ui.strong(&patch.name);
 
+                            let group = meta
+                                .and_then(|m| m.catalog_id.as_ref())
+                                .and_then(|id| series_groups.get(id));
+                            if let Some((name, filenames)) = group {
+                                let base = patch.name.trim_end_matches(".disabled");
+                                if let Some(pos) = filenames.iter().position(|f| f == base) {
+                                    ui.label(
+                                        RichText::new(format!(
+                                            "📚 {} {}/{}",
+                                            name,
+                                            pos + 1,
+                                            filenames.len()
+                                        ))
+                                        .small()
+                                        .color(Color32::GRAY),
+                                    );
+                                }
+                            }
+
                             // Update status badge
                             if let Some(meta) = meta {
-                                let (badge, badge_color) = match &meta.update_status {
-                                    UpdateStatus::Unknown => ("⬜", Color32::GRAY),
-                                    UpdateStatus::UpToDate => ("🟢", Color32::GREEN),
-                                    UpdateStatus::Stale => ("🟡", Color32::YELLOW),
-                                    UpdateStatus::CheckError(_) => ("🔴", Color32::RED),
+                                let (badge, badge_color, tip) = match &meta.update_status {
+                                    UpdateStatus::Unknown => {
+                                        ("⬜", Color32::GRAY, "Not checked yet".to_string())
+                                    }
+                                    UpdateStatus::UpToDate => {
+                                        ("🟢", Color32::GREEN, "Up to date".to_string())
+                                    }
+                                    UpdateStatus::Stale => (
+                                        "🟡",
+                                        Color32::YELLOW,
+                                        "Changed at the source".to_string(),
+                                    ),
+                                    UpdateStatus::NoValidators => (
+                                        "❔",
+                                        Color32::LIGHT_BLUE,
+                                        "Cannot determine: the server sends no ETag or \
+                                         Last-Modified header.\nRe-download and compare the \
+                                         SHA-256 to check for changes."
+                                            .to_string(),
+                                    ),
+                                    UpdateStatus::CheckError(reason) => {
+                                        ("🔴", Color32::RED, format!("Check failed: {}", reason))
+                                    }
                                 };
-                                ui.label(RichText::new(badge).color(badge_color));
+                                ui.label(RichText::new(badge).color(badge_color))
+                                    .on_hover_text(tip);
                             } else {
                                 ui.label(RichText::new("⬜").color(Color32::GRAY));
                             }
@@ -433,6 +776,16 @@ impl PatchesTab {
                                     if ui.small_button("🔄 Re-download").clicked() {
                                         to_redownload = meta.source_url.clone();
                                     }
+                                    if meta.update_status == UpdateStatus::Stale
+                                        && ui
+                                            .add_enabled(
+                                                self.diff_rx.is_none(),
+                                                egui::Button::new("📝 View Diff").small(),
+                                            )
+                                            .clicked()
+                                    {
+                                        to_diff = Some((meta.clone(), patch.path.clone()));
+                                    }
                                 }
                             }
 
@@ -472,83 +825,455 @@ impl PatchesTab {
                     self.check_single_update(meta, ctx.clone());
                 }
 
+                if let Some((meta, path)) = to_diff {
+                    self.start_diff(&meta, path, ctx.clone());
+                }
+
                 if let Some(url) = to_redownload {
                     if let Some(meta) = self
                         .registry
                         .all_for_series(&self.kernel_series)
                         .into_iter()
                         .find(|m| m.source_url.as_ref() == Some(&url))
+                        .cloned()
                     {
-                        self.url_input = url;
-                        self.filename_input = meta.filename.clone();
-                        self.pending_download = Some(PendingDownload {
-                            url: self.url_input.clone(),
-                            catalog_id: meta.catalog_id.clone(),
-                        });
-                        self.start_url_download(linux_tkg_path, ctx.clone());
+                        self.redownload(&meta, linux_tkg_path, ctx.clone());
                     }
                 }
             });
+        self.installed_scroll = output.state.offset.y;
+    }
+
+    /// A list's scroll area, put back at `offset` when the tab was just
+    /// switched to (egui otherwise keeps the offset itself)
+    fn scroll_area(&self, id: &str, max_height: f32, offset: f32) -> egui::ScrollArea {
+        let area = egui::ScrollArea::vertical().id_salt(id).max_height(max_height);
+        if self.restore_scroll {
+            area.vertical_scroll_offset(offset)
+        } else {
+            area
+        }
+    }
+
+    /// Fetch the upstream copy of a patch and diff it against the file on disk.
+    fn start_diff(&mut self, meta: &PatchMeta, path: PathBuf, ctx: Context) {
+        let Some(url) = meta.source_url.clone() else {
+            return;
+        };
+        let (tx, rx) = channel();
+        self.diff_rx = Some(rx);
+        self.diff_view = Some(PatchDiff {
+            filename: meta.filename.clone(),
+            result: None,
+        });
+
+        thread::spawn(move || {
+            let result = std::fs::read_to_string(&path)
+                .map_err(|e| format!("Cannot read {}: {}", path.display(), e))
+                .and_then(|local| {
+                    fetch_patch_text(&url).map(|upstream| diff_lines(&local, &upstream))
+                });
+            let _ = tx.send(result);
+            ctx.request_repaint();
+        });
+    }
+
+    fn diff_window(&mut self, ctx: &Context) {
+        let Some(view) = &self.diff_view else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new(format!("Diff: {}", view.filename))
+            .open(&mut open)
+            .default_size([700.0, 500.0])
+            .show(ctx, |ui| match &view.result {
+                None => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Fetching upstream patch...");
+                    });
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(Color32::RED, format!("Error: {}", e));
+                }
+                Some(Ok(lines)) => {
+                    let added = lines
+                        .iter()
+                        .filter(|l| matches!(l, DiffLine::Added(_)))
+                        .count();
+                    let removed = lines
+                        .iter()
+                        .filter(|l| matches!(l, DiffLine::Removed(_)))
+                        .count();
+                    if added == 0 && removed == 0 {
+                        ui.label("Upstream content is identical to the installed copy.");
+                        return;
+                    }
+                    ui.label(format!(
+                        "Installed → upstream: +{} / -{} lines",
+                        added, removed
+                    ));
+                    ui.separator();
+                    egui::ScrollArea::both()
+                        .auto_shrink([false, false])
+                        .show_rows(ui, 14.0, lines.len(), |ui, range| {
+                            for line in &lines[range] {
+                                let (text, color) = match line {
+                                    DiffLine::Same(l) => (format!("  {}", l), Color32::GRAY),
+                                    DiffLine::Added(l) => (format!("+ {}", l), Color32::GREEN),
+                                    DiffLine::Removed(l) => {
+                                        (format!("- {}", l), Color32::from_rgb(255, 100, 100))
+                                    }
+                                };
+                                ui.label(RichText::new(text).monospace().color(color));
+                            }
+                        });
+                }
+            });
+        if !open {
+            self.diff_view = None;
+            self.diff_rx = None;
+        }
+    }
+
+    /// Download a registered patch again from its recorded source URL.
+    fn redownload(&mut self, meta: &PatchMeta, linux_tkg_path: &Path, ctx: Context) {
+        let Some(url) = meta.source_url.clone() else {
+            return;
+        };
+        let dest_path = self.patch_dir(linux_tkg_path).join(&meta.filename);
+        self.url_input = url.clone();
+        self.filename_input = meta.filename.clone();
+        // A catalog patch stays held to the entry's pin
+        let sha256 = meta.catalog_id.as_ref().and_then(|id| {
+            catalog_for_series(&meta.kernel_series)
+                .into_iter()
+                .find(|e| e.id == *id)?
+                .expected_sha256(&meta.filename)
+                .map(str::to_string)
+        });
+        self.spawn_download(
+            PendingDownload {
+                url,
+                catalog_id: meta.catalog_id.clone(),
+                dest_path,
+                sha256,
+                label: meta.filename.clone(),
+            },
+            ctx,
+        );
     }
 
     fn start_catalog_download(
         &mut self,
-        entry: &CatalogEntry,
+        entry: &CatalogEntryOwned,
         linux_tkg_path: &Path,
         data_dir: &Path,
         ctx: Context,
     ) {
         let url = entry.url_for_series(&self.kernel_series);
         let filename = entry.filename_for_series(&self.kernel_series);
-
-        self.pending_download = Some(PendingDownload {
-            url: url.clone(),
-            catalog_id: Some(entry.id.to_string()),
-        });
-
-        let patch_dir = get_patch_dir(linux_tkg_path, &self.kernel_series);
-        let dest_path = patch_dir.join(&filename);
+        if let Err(e) = check_file_name(&filename) {
+            self.status = format!("{}: {}", entry.name, e);
+            return;
+        }
+        let dest_path = self.patch_dir(linux_tkg_path).join(&filename);
 
         // Store data_dir for use when download completes (via last_data_dir)
         self.last_data_dir = Some(data_dir.to_path_buf());
 
-        self.status = format!("Downloading {}...", entry.name);
-        let (tx, rx) = channel();
-        self.download_rx = Some(rx);
+        self.spawn_download(
+            PendingDownload {
+                url,
+                catalog_id: Some(entry.id.clone()),
+                sha256: entry.expected_sha256(&filename).map(str::to_string),
+                dest_path,
+                label: entry.name.clone(),
+            },
+            ctx,
+        );
+    }
+
+    fn start_batch_download(
+        &mut self,
+        entries: &[CatalogEntryOwned],
+        linux_tkg_path: &Path,
+        ctx: Context,
+    ) {
+        if !self.series_valid() {
+            self.status = format!("Invalid kernel series \"{}\"", self.kernel_series);
+            return;
+        }
+        let patch_dir = self.patch_dir(linux_tkg_path);
+        let mut items: Vec<BatchItem> = Vec::new();
+        let mut catalog_ids: Vec<String> = Vec::new();
+        for entry in entries {
+            let series_files = entry.files.len() + 1;
+            for (url, filename) in self.missing_files(entry) {
+                if let Err(e) = check_file_name(&filename) {
+                    self.status = format!("{}: {}", entry.name, e);
+                    return;
+                }
+                let name = if series_files > 1 {
+                    format!("{} ({})", entry.name, filename)
+                } else {
+                    entry.name.clone()
+                };
+                items.push(BatchItem {
+                    name,
+                    url,
+                    sha256: entry.expected_sha256(&filename).map(str::to_string),
+                    dest_path: patch_dir.join(filename),
+                });
+                catalog_ids.push(entry.id.clone());
+            }
+        }
 
+        let (tx, rx) = channel();
+        let thread_items = items.clone();
+        let stop_on_failure = self.batch_stop_on_failure;
+        let cancel = CancelToken::default();
+        let thread_cancel = cancel.clone();
         thread::spawn(move || {
-            let result = download_patch(&url, &dest_path);
-            let _ = tx.send(result);
+            download_batch(&thread_items, stop_on_failure, &thread_cancel, &tx);
             ctx.request_repaint();
         });
+
+        self.status = format!("Downloading {} patches...", items.len());
+        self.batch = Some(CatalogBatch {
+            catalog_ids,
+            items,
+            rx: Some(rx),
+            cancel,
+            succeeded: Vec::new(),
+            failed: Vec::new(),
+            skipped: Vec::new(),
+        });
     }
 
-    fn start_url_download(&mut self, linux_tkg_path: &Path, ctx: Context) {
-        let patch_dir = get_patch_dir(linux_tkg_path, &self.kernel_series);
-        let dest_path = patch_dir.join(&self.filename_input);
-        let url = self.url_input.clone();
+    /// (url, filename) of the entry's files not present in the patch dir
+    fn missing_files(&self, entry: &CatalogEntryOwned) -> Vec<(String, String)> {
+        entry
+            .files_for_series(&self.kernel_series)
+            .into_iter()
+            .filter(|(_, filename)| !self.patches.iter().any(|p| &p.name == filename))
+            .collect()
+    }
+
+    /// Background jobs still running, for "Stop All"
+    pub fn background_tasks(&self) -> Vec<&'static str> {
+        let mut tasks = Vec::new();
+        if self.download_rx.is_some() {
+            tasks.push("Patch download");
+        }
+        if self
+            .batch
+            .as_ref()
+            .is_some_and(|b| b.rx.is_some() && !b.cancel.is_cancelled())
+        {
+            tasks.push("Catalog batch download");
+        }
+        if self.update_rx.is_some() {
+            tasks.push("Patch update check");
+        }
+        if self.diff_rx.is_some() {
+            tasks.push("Patch diff");
+        }
+        tasks
+    }
+
+    /// Stop the batch before its next patch and stop waiting for the rest;
+    /// a download already in flight finishes but isn't registered.
+    pub fn stop_background(&mut self) -> Vec<&'static str> {
+        let stopped = self.background_tasks();
+        if let Some(batch) = &self.batch {
+            batch.cancel.cancel();
+        }
+        if self.download_rx.take().is_some() {
+            self.status = "Download stopped.".to_string();
+        }
+        if self.update_rx.take().is_some() {
+            self.update_status = "Update check stopped.".to_string();
+        }
+        if self.diff_rx.take().is_some() {
+            self.diff_view = None;
+        }
+        stopped
+    }
+
+    fn drain_batch(&mut self, linux_tkg_path: &Path, data_dir: &Path) {
+        let Some(mut batch) = self.batch.take() else {
+            return;
+        };
+        let mut finished = false;
+        if let Some(rx) = &batch.rx {
+            while let Ok(progress) = rx.try_recv() {
+                match progress {
+                    BatchProgress::Item(i, DownloadResult::Done(info)) => {
+                        batch.succeeded.push(batch.items[i].name.clone());
+                        self.record_download(
+                            info,
+                            Some(batch.items[i].url.clone()),
+                            Some(batch.catalog_ids[i].clone()),
+                            data_dir,
+                        );
+                    }
+                    BatchProgress::Item(i, DownloadResult::Error(e)) => {
+                        batch.failed.push((batch.items[i].name.clone(), e));
+                    }
+                    BatchProgress::Finished { stopped_early } => {
+                        let attempted = batch.succeeded.len() + batch.failed.len();
+                        if stopped_early {
+                            batch.skipped = batch.items[attempted..]
+                                .iter()
+                                .map(|item| item.name.clone())
+                                .collect();
+                        }
+                        finished = true;
+                    }
+                }
+            }
+        }
+        if finished {
+            batch.rx = None;
+            self.status = format!(
+                "Batch finished: {} downloaded, {} failed, {} skipped",
+                batch.succeeded.len(),
+                batch.failed.len(),
+                batch.skipped.len()
+            );
+            self.refresh_patches(linux_tkg_path);
+        }
+        self.batch = Some(batch);
+    }
+
+    fn batch_summary_ui(&mut self, ui: &mut Ui) {
+        let Some(batch) = &self.batch else {
+            return;
+        };
+        let mut dismiss = false;
+        ui.group(|ui| {
+            if batch.rx.is_some() {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(format!(
+                        "Downloading {}/{}...",
+                        batch.succeeded.len() + batch.failed.len() + 1,
+                        batch.items.len()
+                    ));
+                });
+            }
+            for name in &batch.succeeded {
+                ui.label(RichText::new(format!("✓ {}", name)).color(Color32::GREEN));
+            }
+            for (name, error) in &batch.failed {
+                ui.label(RichText::new(format!("✗ {}: {}", name, error)).color(Color32::RED));
+            }
+            for name in &batch.skipped {
+                ui.label(
+                    RichText::new(format!("– {} (skipped after failure)", name))
+                        .color(Color32::GRAY),
+                );
+            }
+            if batch.rx.is_none() && ui.small_button("Dismiss").clicked() {
+                dismiss = true;
+            }
+        });
+        if dismiss {
+            self.batch = None;
+        }
+    }
 
-        if self.pending_download.is_none() {
-            self.pending_download = Some(PendingDownload {
-                url: url.clone(),
+    fn start_url_download(&mut self, linux_tkg_path: &Path, ctx: Context) {
+        let dest_path = self.patch_dir(linux_tkg_path).join(&self.filename_input);
+        self.spawn_download(
+            PendingDownload {
+                url: self.url_input.clone(),
                 catalog_id: None,
-            });
+                dest_path,
+                sha256: None,
+                label: self.filename_input.clone(),
+            },
+            ctx,
+        );
+    }
+
+    /// Run one download, remembering its context so a failure can be retried
+    fn spawn_download(&mut self, pending: PendingDownload, ctx: Context) {
+        if !self.series_valid() {
+            self.status = format!("Invalid kernel series \"{}\"", self.kernel_series);
+            return;
         }
+        let url = pending.url.clone();
+        let dest_path = pending.dest_path.clone();
+        let sha256 = pending.sha256.clone();
 
-        self.status = "Downloading…".to_string();
+        self.status = format!("Downloading {}…", pending.label);
+        self.pending_download = Some(pending);
+        self.download_error = None;
         let (tx, rx) = channel();
         self.download_rx = Some(rx);
 
         thread::spawn(move || {
-            let result = download_patch(&url, &dest_path);
+            let result = download_patch(&url, &dest_path, sha256.as_deref());
             let _ = tx.send(result);
             ctx.request_repaint();
         });
     }
 
+    /// Failure banner for the last single download with Retry and Dismiss
+    fn download_error_ui(&mut self, ui: &mut Ui, ctx: &Context) {
+        let (Some(error), Some(pending)) = (&self.download_error, &self.pending_download) else {
+            return;
+        };
+        let mut retry = None;
+        let mut dismiss = false;
+        ui.group(|ui| {
+            ui.label(
+                RichText::new(format!("✖ Downloading {} failed", pending.label))
+                    .color(Color32::RED),
+            );
+            ui.label(RichText::new(error).small().color(Color32::GRAY));
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(self.download_rx.is_none(), egui::Button::new("🔁 Retry"))
+                    .on_hover_text(&pending.url)
+                    .clicked()
+                {
+                    retry = Some(pending.clone());
+                }
+                if ui.button("Dismiss").clicked() {
+                    dismiss = true;
+                }
+            });
+        });
+        if let Some(pending) = retry {
+            self.spawn_download(pending, ctx.clone());
+        } else if dismiss {
+            self.pending_download = None;
+            self.download_error = None;
+            self.status.clear();
+        }
+    }
+
     fn handle_download_complete(&mut self, info: DownloadInfo, data_dir: &Path) {
         self.status = format!("Downloaded: {}", info.path.display());
+        let pending = self.pending_download.take();
+        self.record_download(
+            info,
+            pending.as_ref().map(|p| p.url.clone()),
+            pending.and_then(|p| p.catalog_id),
+            data_dir,
+        );
+    }
 
+    fn record_download(
+        &mut self,
+        info: DownloadInfo,
+        source_url: Option<String>,
+        catalog_id: Option<String>,
+        data_dir: &Path,
+    ) {
         // Get the actual filename from the path (may differ due to decompression)
         let filename = info
             .path
@@ -556,20 +1281,23 @@ impl PatchesTab {
             .map(|n| n.to_string_lossy().to_string())
             .unwrap_or_default();
 
+        let has_validators = info.etag.is_some() || info.last_modified.is_some();
+
         // Create registry entry
         let meta = PatchMeta {
             filename,
             kernel_series: self.kernel_series.clone(),
-            source_url: self.pending_download.as_ref().map(|p| p.url.clone()),
-            catalog_id: self
-                .pending_download
-                .as_ref()
-                .and_then(|p| p.catalog_id.clone()),
+            source_url,
+            catalog_id,
             sha256: info.sha256,
             downloaded_at: Utc::now(),
             etag: info.etag,
             last_modified: info.last_modified,
-            update_status: UpdateStatus::UpToDate,
+            update_status: if has_validators {
+                UpdateStatus::UpToDate
+            } else {
+                UpdateStatus::NoValidators
+            },
         };
 
         self.registry.record_download(meta);
@@ -608,9 +1336,168 @@ impl PatchesTab {
         ctx.request_repaint();
     }
 
+    /// Userpatches directory for the current series: the override if set,
+    /// else the detected layout (cached per clone once it exists).
+    fn patch_dir(&mut self, linux_tkg_path: &Path) -> PathBuf {
+        if let Some(dir) = &self.dir_override {
+            return dir.clone();
+        }
+        let key = (linux_tkg_path.to_path_buf(), self.kernel_series.clone());
+        if let Some(dir) = self.patch_dirs.get(&key) {
+            if dir.is_dir() {
+                return dir.clone();
+            }
+        }
+        let dir = get_patch_dir(linux_tkg_path, &self.kernel_series);
+        if dir.is_dir() {
+            self.patch_dirs.insert(key, dir.clone());
+        }
+        dir
+    }
+
+    /// Export the installed patches with their registry entries as one
+    /// archive, or import such an archive into the current userpatches dir
+    fn bundle_ui(&mut self, ui: &mut Ui, linux_tkg_path: &Path, data_dir: &Path) {
+        let series = self.kernel_series.clone();
+        let can_export = !self.patches.is_empty();
+        let can_import = self.series_valid();
+        let path = self.bundle_path.get_or_insert_with(|| {
+            std::env::var("HOME")
+                .map(|home| format!("{}/linux{}-tkg-patches.tar.gz", home, series))
+                .unwrap_or_default()
+        });
+        let mut export = false;
+        let mut import = false;
+        ui.horizontal(|ui| {
+            ui.label("Patch set:");
+            ui.add(
+                egui::TextEdit::singleline(path)
+                    .hint_text("/path/to/patches.tar.gz")
+                    .desired_width(300.0),
+            );
+            let has_path = !path.trim().is_empty();
+            export = ui
+                .add_enabled(
+                    has_path && can_export,
+                    egui::Button::new("📤 Export"),
+                )
+                .on_hover_text("Save the installed patches and their sources to this archive")
+                .clicked();
+            import = ui
+                .add_enabled(has_path && can_import, egui::Button::new("📥 Import"))
+                .on_hover_text(
+                    "Unpack this archive into the patch dir after checking every file's \
+                     SHA-256; files with the same name are replaced",
+                )
+                .clicked();
+        });
+
+        let bundle = PathBuf::from(path.trim());
+        if export {
+            let registry = self.registry.all_for_series(&series);
+            self.status = match export_bundle(&self.patches, &registry, &series, &bundle) {
+                Ok(n) => format!("Exported {} patches to {}", n, bundle.display()),
+                Err(e) => format!("Export failed: {}", e),
+            };
+        }
+        if import {
+            let patch_dir = self.patch_dir(linux_tkg_path);
+            match import_bundle(&bundle, &patch_dir) {
+                Ok(imported) => {
+                    for mut meta in imported.registry {
+                        meta.kernel_series = series.clone();
+                        self.registry.record_download(meta);
+                    }
+                    let _ = self.registry.save(data_dir);
+                    self.status = format!("Imported {} patches", imported.files.len());
+                    if imported.kernel_series != series {
+                        self.status += &format!(
+                            " (exported for {}, installed for {})",
+                            imported.kernel_series, series
+                        );
+                    }
+                    self.refresh_patches(linux_tkg_path);
+                }
+                Err(e) => self.status = format!("Import failed: {}", e),
+            }
+        }
+    }
+
+    fn dir_override_ui(
+        &mut self,
+        ui: &mut Ui,
+        linux_tkg_path: &Path,
+        settings: &mut AppSettings,
+    ) {
+        let input = self.dir_override_input.get_or_insert_with(|| {
+            settings
+                .patch_dir_override
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default()
+        });
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Override:");
+            ui.add(
+                egui::TextEdit::singleline(input)
+                    .hint_text("auto-detect")
+                    .desired_width(300.0),
+            );
+            let trimmed = input.trim();
+            let current = settings
+                .patch_dir_override
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default();
+            if ui
+                .add_enabled(trimmed != current, egui::Button::new("Set"))
+                .on_hover_text("Use this directory for patches regardless of layout")
+                .clicked()
+            {
+                settings.patch_dir_override =
+                    (!trimmed.is_empty()).then(|| PathBuf::from(trimmed));
+                changed = true;
+            }
+            if ui
+                .button("🔍 Re-detect")
+                .on_hover_text("Clear the override and detect the userpatches dir again")
+                .clicked()
+            {
+                input.clear();
+                settings.patch_dir_override = None;
+                self.patch_dirs.clear();
+                changed = true;
+            }
+        });
+        expansion_hint(ui, input);
+        if changed {
+            if let Err(e) = settings.save() {
+                self.status = format!("Failed to save settings: {}", e);
+            }
+            self.dir_override = settings.patch_dir_override.as_deref().map(settings::expand_path);
+            self.refresh_patches(linux_tkg_path);
+        }
+    }
+
     fn refresh_patches(&mut self, linux_tkg_path: &Path) {
-        let patch_dir = get_patch_dir(linux_tkg_path, &self.kernel_series);
+        let patch_dir = self.patch_dir(linux_tkg_path);
         self.patches = list_patches(&patch_dir);
+
+        // Keep the registry consistent with what's actually on disk
+        let on_disk: Vec<String> = self.patches.iter().map(|p| p.name.clone()).collect();
+        let before = self.registry.patches.len();
+        self.missing_patches = self.registry.reconcile(&self.kernel_series, &on_disk);
+        if self.registry.patches.len() != before {
+            if let Some(data_dir) = &self.last_data_dir {
+                let _ = self.registry.save(data_dir);
+            }
+        }
+    }
+
+    /// Patch dirs and catalog URLs are only meaningful for "major.minor"
+    fn series_valid(&self) -> bool {
+        version::is_series(&self.kernel_series)
     }
 
     pub fn set_kernel_series(&mut self, series: &str) {
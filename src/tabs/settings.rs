@@ -1,8 +1,20 @@
-use crate::core::repo_manager::{clone_linux_tkg, copy_linux_tkg, CloneMsg};
-use crate::settings::AppSettings;
+use crate::core::app_log;
+use crate::core::build_manager;
+use crate::core::catalog_index::{self, CatalogRefresh};
+use crate::core::kernel_downloader::{self, format_bytes};
+use crate::core::system;
+use crate::core::task::CancelToken;
+use crate::core::work_dir::WorkDir;
+use crate::core::repo_manager::{
+    clone_linux_tkg, copy_linux_tkg, detect_supported_series, is_shallow, reclone_backup_path,
+    reclone_linux_tkg, unshallow, verify_clone, CloneMsg, LINUX_TKG_CLONE_BYTES,
+};
+use crate::data::catalog;
+use crate::settings::{self, AppSettings};
+use chrono::{DateTime, Local, Utc};
 use egui::{Color32, Context, RichText, Ui};
 use std::path::{Path, PathBuf};
-use std::sync::mpsc::{channel, Receiver};
+use std::sync::mpsc::{channel, Receiver, Sender};
 
 #[derive(Default)]
 pub struct SettingsTab {
@@ -11,9 +23,43 @@ pub struct SettingsTab {
     clone_rx: Option<Receiver<CloneMsg>>,
     clone_running: bool,
     clone_status: String,
+    // What the running clone/copy job is, for "Stop All"
+    clone_task: &'static str,
+    // The running job fetches linux-tkg from GitHub; success is recorded
+    // as the last update
+    job_updates_repo: bool,
+    // Stops the running git job; None for copies, which can't be stopped
+    clone_cancel: Option<CancelToken>,
+    // The running git job is a verification; its exit code judges the clone
+    verifying: bool,
+    // Last verification failed; offer a fresh clone
+    clone_broken: bool,
+    // "Clone from GitHub" was pressed; waiting for the size confirmation
+    confirm_clone: bool,
+    // "Re-clone" was pressed; waiting for the confirmation
+    confirm_reclone: bool,
 
     // Install state
     install_status: String,
+
+    // Supported-series override editor (None until seeded from settings)
+    series_override_input: Option<String>,
+    // Kernel download dir editor (None until seeded from settings)
+    kernel_sources_input: Option<String>,
+
+    // Remote catalog index
+    catalog_url_input: Option<String>,
+    catalog_rx: Option<Receiver<CatalogRefresh>>,
+    catalog_status: String,
+    settings_status: String,
+
+    // Build hook command editors (None until seeded from settings)
+    pre_build_input: Option<String>,
+    post_build_input: Option<String>,
+    // Build output mirror path editor (None until seeded from settings)
+    mirror_input: Option<String>,
+    // Work directory override editor (None until seeded from settings)
+    work_dir_input: Option<String>,
 }
 
 impl SettingsTab {
@@ -22,45 +68,33 @@ impl SettingsTab {
         ui: &mut Ui,
         ctx: &Context,
         settings: &mut AppSettings,
-        work_dir_root: &Path,
+        work_dir: &WorkDir,
         linux_tkg_path: &Path,
     ) {
-        // Drain clone/copy output
-        let mut clone_done = false;
-        if let Some(rx) = &self.clone_rx {
-            while let Ok(msg) = rx.try_recv() {
-                match msg {
-                    CloneMsg::Line(line) => {
-                        self.clone_log.push(line);
-                        ctx.request_repaint();
+        let work_dir_root = work_dir.root();
+        // Drain catalog refresh result
+        if let Some(rx) = &self.catalog_rx {
+            if let Ok(result) = rx.try_recv() {
+                self.catalog_status = match result {
+                    CatalogRefresh::Updated(n) => format!("Fetched {} remote entries.", n),
+                    CatalogRefresh::NotModified(n) => {
+                        format!("Up to date ({} remote entries).", n)
                     }
-                    CloneMsg::Exit(code) => {
-                        if code == 0 {
-                            self.clone_status = "Completed successfully.".to_string();
-                        } else {
-                            self.clone_status =
-                                format!("Finished with exit code {}.", code);
-                        }
-                        clone_done = true;
-                        ctx.request_repaint();
+                    CatalogRefresh::Cached(n, e) => {
+                        format!("Offline, using {} cached entries ({}).", n, e)
                     }
-                    CloneMsg::SpawnError(e) => {
-                        self.clone_status = format!("Error: {}", e);
-                        clone_done = true;
-                        ctx.request_repaint();
+                    CatalogRefresh::Error(e) => {
+                        format!("Using built-in catalog only: {}", e)
                     }
-                }
+                };
+                self.catalog_rx = None;
             }
         }
-        if clone_done {
-            self.clone_rx = None;
-            self.clone_running = false;
-        }
-        if self.clone_running {
-            ctx.request_repaint();
-        }
 
         ui.heading("Settings");
+        if !self.settings_status.is_empty() {
+            ui.label(&self.settings_status);
+        }
         ui.add_space(8.0);
 
         // ── Work Directory ──────────────────────────────────────────────────────
@@ -68,12 +102,45 @@ impl SettingsTab {
             .default_open(true)
             .show(ui, |ui| {
                 ui.label(
-                    "All build operations run inside a temporary directory. \
-                     It is cleaned up on exit (or on crash).",
+                    "All build operations run inside a work directory, in the system temp \
+                     dir unless a location is set below. It is cleaned up on exit (or on crash).",
                 );
                 ui.add_space(4.0);
 
                 ui.label(format!("Work dir: {}", work_dir_root.display()));
+                let input = self.work_dir_input.get_or_insert_with(|| {
+                    settings
+                        .work_dir_override
+                        .as_ref()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_default()
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Location:");
+                    ui.add(
+                        egui::TextEdit::singleline(input)
+                            .hint_text("empty uses the system temp dir")
+                            .desired_width(320.0),
+                    );
+                    if ui.button("Save").clicked() {
+                        let path = input.trim();
+                        settings.work_dir_override =
+                            (!path.is_empty()).then(|| PathBuf::from(path));
+                        self.settings_status = match settings.save() {
+                            Ok(()) => "Saved; the new work dir is used after a restart."
+                                .to_string(),
+                            Err(e) => format!("Error saving settings: {}", e),
+                        };
+                    }
+                });
+                expansion_hint(ui, input);
+                ui.label(
+                    RichText::new(
+                        "A tkg-gui-<pid> folder is created inside it and removed on exit.",
+                    )
+                    .small()
+                    .color(Color32::GRAY),
+                );
                 ui.label(format!("linux-tkg: {}", linux_tkg_path.display()));
 
                 ui.add_space(4.0);
@@ -81,10 +148,41 @@ impl SettingsTab {
                 // linux-tkg status in work dir
                 let is_ready = linux_tkg_path.join("customization.cfg").exists();
                 if is_ready {
-                    ui.label(
-                        RichText::new("✓ linux-tkg ready")
-                            .color(Color32::GREEN),
-                    );
+                    ui.horizontal(|ui| {
+                        ui.label(
+                            RichText::new("✓ linux-tkg ready")
+                                .color(Color32::GREEN),
+                        );
+                        if let Some(updated) = settings.linux_tkg_updated {
+                            ui.label(
+                                RichText::new(format!(
+                                    "last updated {}",
+                                    format_ago(updated, Utc::now())
+                                ))
+                                .color(Color32::GRAY),
+                            )
+                            .on_hover_text(updated.with_timezone(&Local).to_rfc2822());
+                        }
+                        if is_shallow(linux_tkg_path) {
+                            ui.label(RichText::new("⚠ Shallow clone").color(Color32::YELLOW))
+                                .on_hover_text(
+                                    "Cloned with --depth=1: tags and older commits are missing, \
+                                     so checking out a release, bisecting or browsing the \
+                                     linux-tkg history won't work until the full history is \
+                                     fetched.",
+                                );
+                            if ui
+                                .add_enabled(
+                                    !self.clone_running,
+                                    egui::Button::new("⬇ Unshallow"),
+                                )
+                                .on_hover_text("git fetch --unshallow --tags")
+                                .clicked()
+                            {
+                                self.start_unshallow(linux_tkg_path.to_path_buf(), ctx.clone());
+                            }
+                        }
+                    });
                 } else {
                     ui.label(
                         RichText::new("✗ linux-tkg not found in work directory")
@@ -116,10 +214,10 @@ impl SettingsTab {
                     } else if has_settings_ref
                         && ui
                             .add_enabled(can_act, egui::Button::new("📋 Copy from Local"))
-                            .on_hover_text(format!("Copy {}", settings.linux_tkg_path.display()))
+                            .on_hover_text(format!("Copy {}", settings.linux_tkg_dir().display()))
                             .clicked()
                     {
-                        self.start_copy(&settings.linux_tkg_path, linux_tkg_path, ctx.clone());
+                        self.start_copy(&settings.linux_tkg_dir(), linux_tkg_path, ctx.clone());
                     }
 
                     if ui
@@ -129,7 +227,35 @@ impl SettingsTab {
                         )
                         .clicked()
                     {
-                        self.start_clone(linux_tkg_path.to_path_buf(), ctx.clone());
+                        if settings.confirm_clone {
+                            self.confirm_clone = true;
+                        } else {
+                            self.start_clone(linux_tkg_path.to_path_buf(), ctx.clone());
+                        }
+                    }
+
+                    if linux_tkg_path.exists()
+                        && ui
+                            .add_enabled(!self.clone_running, egui::Button::new("🩺 Verify Clone"))
+                            .on_hover_text(
+                                "Check customization.cfg, git status and git fsck, e.g. after \
+                                 an interrupted clone",
+                            )
+                            .clicked()
+                    {
+                        self.start_verify(linux_tkg_path.to_path_buf(), ctx.clone());
+                    }
+
+                    if self.clone_broken
+                        && ui
+                            .add_enabled(!self.clone_running, egui::Button::new("🔁 Re-clone"))
+                            .on_hover_text(
+                                "Move the linux-tkg directory aside, including any edits to \
+                                 customization.cfg, and clone it again",
+                            )
+                            .clicked()
+                    {
+                        self.confirm_reclone = true;
                     }
 
                     if self.clone_running {
@@ -141,6 +267,20 @@ impl SettingsTab {
                     }
                 });
 
+                if ui
+                    .checkbox(&mut settings.confirm_clone, "Ask before cloning")
+                    .on_hover_text("Show the download size and free space first")
+                    .changed()
+                {
+                    self.settings_status = save_status(settings);
+                }
+                if self.confirm_clone {
+                    self.clone_confirm_panel(ui, ctx, linux_tkg_path);
+                }
+                if self.confirm_reclone {
+                    self.reclone_confirm_panel(ui, ctx, linux_tkg_path);
+                }
+
                 // Clone/copy log
                 if !self.clone_log.is_empty() {
                     ui.add_space(4.0);
@@ -163,15 +303,334 @@ impl SettingsTab {
 
         ui.add_space(8.0);
 
+        // ── Kernel Sources ───────────────────────────────────────────────────────
+        egui::CollapsingHeader::new("Kernel Sources")
+            .default_open(false)
+            .show(ui, |ui| {
+                if ui
+                    .checkbox(
+                        &mut settings.persist_kernel_sources,
+                        "Keep kernel downloads between sessions",
+                    )
+                    .on_hover_text(
+                        "When off, sources are downloaded into the work directory \
+                         and removed on clean exit",
+                    )
+                    .changed()
+                {
+                    self.settings_status = save_status(settings);
+                }
+
+                let input = self
+                    .kernel_sources_input
+                    .get_or_insert_with(|| settings.kernel_sources_dir.display().to_string());
+                ui.add_enabled_ui(settings.persist_kernel_sources, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Location:");
+                        ui.add(egui::TextEdit::singleline(input).desired_width(320.0));
+                        if ui.button("Apply").clicked() && !input.trim().is_empty() {
+                            settings.kernel_sources_dir = PathBuf::from(input.trim());
+                            self.settings_status = save_status(settings);
+                        }
+                    });
+                    expansion_hint(ui, input);
+                });
+                if !settings.persist_kernel_sources {
+                    ui.label(
+                        RichText::new(format!(
+                            "Downloads go to {}",
+                            work_dir_root.join("kernel-sources").display()
+                        ))
+                        .small()
+                        .color(Color32::GRAY),
+                    );
+                }
+
+                ui.add_enabled_ui(settings.persist_kernel_sources, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Keep last");
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut settings.kernel_keep_downloads)
+                                    .range(0..=50),
+                            )
+                            .on_hover_text("0 keeps every download")
+                            .changed()
+                        {
+                            self.settings_status = save_status(settings);
+                        }
+                        ui.label("kernel downloads");
+                        if settings.kernel_keep_downloads > 0
+                            && ui
+                                .checkbox(&mut settings.confirm_kernel_prune, "Ask before deleting")
+                                .changed()
+                        {
+                            self.settings_status = save_status(settings);
+                        }
+                    });
+                });
+
+                if ui
+                    .checkbox(&mut settings.verify_kernel_tree, "Verify extracted kernel tree")
+                    .on_hover_text(
+                        "Check for Makefile, Kconfig and kernel/ and that the Makefile \
+                         version matches the download",
+                    )
+                    .changed()
+                {
+                    self.settings_status = save_status(settings);
+                }
+                if ui
+                    .checkbox(&mut settings.keep_kernel_tarball, "Keep downloaded tarballs")
+                    .on_hover_text(
+                        "Keep the .tar.xz next to the extracted tree so \"Verify & Reuse\" can \
+                         re-extract it without downloading again. Applies to new downloads.",
+                    )
+                    .changed()
+                {
+                    self.settings_status = save_status(settings);
+                }
+
+                let sources_dir = if settings.persist_kernel_sources {
+                    settings.kernel_sources()
+                } else {
+                    work_dir_root.join("kernel-sources")
+                };
+                self.kept_tarballs_ui(ui, &sources_dir);
+            });
+
+        ui.add_space(8.0);
+
+        // ── Patch Catalog ────────────────────────────────────────────────────────
+        egui::CollapsingHeader::new("Patch Catalog")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.label(
+                    "Optionally merge a remote catalog index (JSON) into the built-in \
+                     patch catalog. It is fetched on startup and cached for offline use.",
+                );
+                ui.add_space(4.0);
+                let input = self
+                    .catalog_url_input
+                    .get_or_insert_with(|| settings.catalog_index_url.clone());
+                let mut refresh = false;
+                ui.horizontal(|ui| {
+                    ui.label("Index URL:");
+                    ui.add(
+                        egui::TextEdit::singleline(input)
+                            .hint_text("https://… (empty = built-in only)")
+                            .desired_width(320.0),
+                    );
+                    if ui.button("Apply").clicked() {
+                        settings.catalog_index_url = input.trim().to_string();
+                        self.settings_status = save_status(settings);
+                        refresh = true;
+                    }
+                    if ui
+                        .add_enabled(
+                            self.catalog_rx.is_none() && !settings.catalog_index_url.is_empty(),
+                            egui::Button::new("🔄 Refresh"),
+                        )
+                        .clicked()
+                    {
+                        refresh = true;
+                    }
+                });
+                if refresh {
+                    self.start_catalog_refresh(settings, ctx.clone());
+                }
+                if self.catalog_rx.is_some() {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Fetching catalog index…");
+                    });
+                } else if !self.catalog_status.is_empty() {
+                    ui.label(&self.catalog_status);
+                }
+            });
+
+        ui.add_space(8.0);
+
+        // ── Kernel Support ───────────────────────────────────────────────────────
+        egui::CollapsingHeader::new("Kernel Support")
+            .default_open(false)
+            .show(ui, |ui| {
+                let detected = detect_supported_series(linux_tkg_path);
+                if detected.is_empty() {
+                    ui.label("Detected series: none (using built-in list)");
+                } else {
+                    ui.label(format!("Detected series: {}", detected.join(", ")));
+                }
+                ui.add_space(4.0);
+
+                let input = self
+                    .series_override_input
+                    .get_or_insert_with(|| settings.supported_series_override.join(", "));
+                ui.horizontal(|ui| {
+                    ui.label("Override:");
+                    ui.add(
+                        egui::TextEdit::singleline(input)
+                            .hint_text("e.g. 6.12, 6.13 (empty = auto)")
+                            .desired_width(240.0),
+                    );
+                    if ui.button("Apply").clicked() {
+                        settings.supported_series_override = input
+                            .split(|c: char| c == ',' || c.is_whitespace())
+                            .filter(|s| !s.is_empty())
+                            .map(|s| s.to_string())
+                            .collect();
+                        self.settings_status = save_status(settings);
+                    }
+                });
+            });
+
+        ui.add_space(8.0);
+
+        // ── Build Hooks ──────────────────────────────────────────────────────────
+        egui::CollapsingHeader::new("Build Hooks")
+            .default_open(false)
+            .show(ui, |ui| {
+                let input = self
+                    .pre_build_input
+                    .get_or_insert_with(|| settings.pre_build_command.clone());
+                ui.horizontal(|ui| {
+                    ui.label("Pre-build command:");
+                    ui.add(
+                        egui::TextEdit::singleline(input)
+                            .hint_text("e.g. modprobed-db store")
+                            .desired_width(300.0),
+                    );
+                    if ui.button("Apply").clicked() {
+                        settings.pre_build_command = input.trim().to_string();
+                        self.settings_status = save_status(settings);
+                    }
+                });
+                hook_preview(ui, input);
+                if ui
+                    .checkbox(
+                        &mut settings.pre_build_abort_on_failure,
+                        "Abort the build if it fails",
+                    )
+                    .changed()
+                {
+                    self.settings_status = save_status(settings);
+                }
+
+                ui.add_space(4.0);
+                let input = self
+                    .post_build_input
+                    .get_or_insert_with(|| settings.post_build_command.clone());
+                ui.horizontal(|ui| {
+                    ui.label("Post-build command:");
+                    ui.add(
+                        egui::TextEdit::singleline(input)
+                            .hint_text("e.g. sudo mkinitcpio -P")
+                            .desired_width(300.0),
+                    );
+                    if ui.button("Apply").clicked() {
+                        settings.post_build_command = input.trim().to_string();
+                        self.settings_status = save_status(settings);
+                    }
+                });
+                hook_preview(ui, input);
+                if ui
+                    .checkbox(&mut settings.post_build_on_failure, "Also run when the build fails")
+                    .changed()
+                {
+                    self.settings_status = save_status(settings);
+                }
+                ui.label(
+                    RichText::new(
+                        "Commands run in the build directory. The post-build command runs \
+                         after the install when installing is confirmed separately. Quoting \
+                         follows shell rules; pipes and variables are not expanded.",
+                    )
+                    .small()
+                    .color(Color32::GRAY),
+                );
+            });
+
+        ui.add_space(8.0);
+
+        // ── Logging ──────────────────────────────────────────────────────────────
+        egui::CollapsingHeader::new("Logging")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("App log level:");
+                    let before = settings.log_level.clone();
+                    egui::ComboBox::from_id_salt("log_level")
+                        .selected_text(&settings.log_level)
+                        .show_ui(ui, |ui| {
+                            for level in app_log::LEVELS {
+                                ui.selectable_value(
+                                    &mut settings.log_level,
+                                    level.to_string(),
+                                    *level,
+                                );
+                            }
+                        });
+                    if settings.log_level != before {
+                        app_log::set_level(&settings.log_level);
+                        self.settings_status = save_status(settings);
+                    }
+                });
+                ui.label(
+                    RichText::new(format!(
+                        "Written to {}. ${} overrides this level.",
+                        AppSettings::data_dir().join("tkg-gui.log").display(),
+                        app_log::LEVEL_ENV_VAR
+                    ))
+                    .small()
+                    .color(Color32::GRAY),
+                );
+
+                ui.add_space(4.0);
+                let input = self.mirror_input.get_or_insert_with(|| {
+                    settings
+                        .build_output_mirror
+                        .as_ref()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_default()
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Mirror build output to:");
+                    ui.add(
+                        egui::TextEdit::singleline(input)
+                            .hint_text("FIFO or socket path, empty disables")
+                            .desired_width(300.0),
+                    );
+                    if ui.button("Apply").clicked() {
+                        let path = input.trim();
+                        settings.build_output_mirror =
+                            (!path.is_empty()).then(|| PathBuf::from(path));
+                        self.settings_status = save_status(settings);
+                    }
+                });
+                expansion_hint(ui, input);
+                ui.label(
+                    RichText::new(
+                        "Every build line is also written here. A missing path is created \
+                         as a FIFO; follow it with `cat`, then start the build. Lines are \
+                         dropped while no reader keeps up.",
+                    )
+                    .small()
+                    .color(Color32::GRAY),
+                );
+            });
+
+        ui.add_space(8.0);
+
         // ── Install ──────────────────────────────────────────────────────────────
         egui::CollapsingHeader::new("Install tkg-gui")
             .default_open(true)
             .show(ui, |ui| {
-                let local_bin = home_local_bin();
-                ui.label(format!(
-                    "Install the running tkg-gui binary to {}",
-                    local_bin.display()
-                ));
+                ui.label(match home_local_bin() {
+                    Ok(local_bin) => {
+                        format!("Install the running tkg-gui binary to {}", local_bin.display())
+                    }
+                    Err(e) => e,
+                });
                 ui.add_space(4.0);
 
                 if ui.button("Install to ~/.local/bin").clicked() {
@@ -203,31 +662,307 @@ impl SettingsTab {
                     "Patch registry: {}",
                     AppSettings::data_dir().join("patch_registry.json").display()
                 ));
+                ui.label(format!(
+                    "App log:        {}",
+                    AppSettings::data_dir().join("tkg-gui.log").display()
+                ));
                 ui.label(format!(
                     "Work directory: {}",
                     work_dir_root.display()
                 ));
+                if let Some(free) = work_dir.available_space() {
+                    ui.label(format!("Free space:     {}", format_bytes(free)));
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Don't start downloads or builds with less than");
+                    if ui
+                        .add(egui::DragValue::new(&mut settings.min_free_space_gb).range(0..=1000))
+                        .on_hover_text("0 disables the check")
+                        .changed()
+                    {
+                        self.settings_status = save_status(settings);
+                    }
+                    ui.label("GiB free");
+                });
             });
     }
 
-    fn start_clone(&mut self, dest: PathBuf, ctx: Context) {
+    /// Fetch the remote catalog index configured in settings, if any.
+    pub fn start_catalog_refresh(&mut self, settings: &AppSettings, ctx: Context) {
+        if settings.catalog_index_url.is_empty() {
+            catalog::set_remote_entries(Vec::new());
+            self.catalog_status.clear();
+            return;
+        }
+        let (tx, rx) = channel();
+        self.catalog_rx = Some(rx);
+        let url = settings.catalog_index_url.clone();
+        std::thread::spawn(move || {
+            let result = catalog_index::refresh(&url, &AppSettings::cache_dir());
+            let _ = tx.send(result);
+            ctx.request_repaint();
+        });
+    }
+
+    /// Drain clone/copy output; also called while another tab is shown
+    pub fn poll_clone(&mut self, ctx: &Context, settings: &mut AppSettings) {
+        let mut clone_done = false;
+        let cancelled = self.clone_cancel.as_ref().is_some_and(CancelToken::is_cancelled);
+        if let Some(rx) = &self.clone_rx {
+            while let Ok(msg) = rx.try_recv() {
+                match msg {
+                    CloneMsg::Line(line) => {
+                        // Git redraws "Receiving objects:  42% ..." in place
+                        let stage = |l: &str| {
+                            l.contains('%').then(|| l.split(':').next().map(str::to_string))
+                        };
+                        match self.clone_log.last_mut() {
+                            Some(last) if stage(last).flatten().is_some()
+                                && stage(last) == stage(&line) =>
+                            {
+                                *last = line;
+                            }
+                            _ => self.clone_log.push(line),
+                        }
+                        ctx.request_repaint();
+                    }
+                    CloneMsg::Exit(_) if cancelled => {
+                        self.clone_status = "Stopped.".to_string();
+                        clone_done = true;
+                        ctx.request_repaint();
+                    }
+                    CloneMsg::Exit(code) if self.verifying => {
+                        self.clone_broken = code != 0;
+                        self.clone_status = if code == 0 {
+                            "✓ Clone is healthy.".to_string()
+                        } else {
+                            "✗ Clone is damaged; re-clone to recover.".to_string()
+                        };
+                        clone_done = true;
+                        ctx.request_repaint();
+                    }
+                    CloneMsg::Exit(code) => {
+                        if code == 0 {
+                            self.clone_status = "Completed successfully.".to_string();
+                            if self.job_updates_repo {
+                                settings.linux_tkg_updated = Some(Utc::now());
+                                if let Err(e) = settings.save() {
+                                    log::warn!("Could not record the linux-tkg update: {}", e);
+                                }
+                            }
+                        } else {
+                            self.clone_status =
+                                format!("Finished with exit code {}.", code);
+                        }
+                        clone_done = true;
+                        ctx.request_repaint();
+                    }
+                    CloneMsg::SpawnError(e) => {
+                        self.clone_status = format!("Error: {}", e);
+                        clone_done = true;
+                        ctx.request_repaint();
+                    }
+                }
+            }
+        }
+        if clone_done {
+            self.clone_rx = None;
+            self.clone_running = false;
+            self.clone_cancel = None;
+            self.verifying = false;
+        }
+        if self.clone_running {
+            ctx.request_repaint();
+        }
+    }
+
+    pub fn is_cloning(&self) -> bool {
+        self.clone_running
+    }
+
+    pub fn clone_status(&self) -> &str {
+        &self.clone_status
+    }
+
+    /// Kept kernel tarballs with their size and a button to delete each
+    fn kept_tarballs_ui(&mut self, ui: &mut Ui, sources_dir: &Path) {
+        let kept: Vec<(String, u64)> = kernel_downloader::cached_kernels(sources_dir)
+            .into_iter()
+            .filter_map(|k| Some((k.version, k.tarball_size?)))
+            .collect();
+        if kept.is_empty() {
+            return;
+        }
+        let total: u64 = kept.iter().map(|(_, size)| size).sum();
+        ui.label(format!("Kept tarballs ({}):", format_bytes(total)));
+        for (version, size) in &kept {
+            ui.horizontal(|ui| {
+                ui.label(kernel_downloader::tarball_name(version));
+                ui.label(RichText::new(format_bytes(*size)).small().color(Color32::GRAY));
+                if ui
+                    .small_button("🗑")
+                    .on_hover_text(
+                        "Delete the tarball but keep the extracted tree; reusing this \
+                         version will need a new download",
+                    )
+                    .clicked()
+                {
+                    self.settings_status =
+                        match kernel_downloader::remove_tarball(sources_dir, version) {
+                            Ok(()) => {
+                                format!("Deleted {}", kernel_downloader::tarball_name(version))
+                            }
+                            Err(e) => format!("Error: {}", e),
+                        };
+                }
+            });
+        }
+    }
+
+    /// Background jobs still running, for "Stop All"
+    pub fn background_tasks(&self) -> Vec<&'static str> {
+        let mut tasks = Vec::new();
+        if self.clone_cancel.as_ref().is_some_and(|c| !c.is_cancelled()) {
+            tasks.push(self.clone_task);
+        }
+        if self.catalog_rx.is_some() {
+            tasks.push("Catalog refresh");
+        }
+        tasks
+    }
+
+    /// Stop the running git job and stop waiting for the catalog refresh.
+    /// A local copy is left to finish.
+    pub fn stop_background(&mut self) -> Vec<&'static str> {
+        let stopped = self.background_tasks();
+        if let Some(cancel) = &self.clone_cancel {
+            cancel.cancel();
+            self.clone_status = "Stopping…".to_string();
+        }
+        if self.catalog_rx.take().is_some() {
+            self.catalog_status = "Catalog refresh stopped.".to_string();
+        }
+        stopped
+    }
+
+    /// Reset the clone log and status for a new job and return its sender
+    fn begin_job(&mut self, task: &'static str, status: &str) -> Sender<CloneMsg> {
         self.clone_log.clear();
-        self.clone_status = "Cloning…".to_string();
+        self.clone_status = status.to_string();
+        self.clone_task = task;
         self.clone_running = true;
+        self.clone_cancel = None;
+        self.job_updates_repo = false;
 
         let (tx, rx) = channel();
         self.clone_rx = Some(rx);
-        clone_linux_tkg(dest, tx);
+        tx
+    }
+
+    /// Token for a git job that fetches linux-tkg
+    fn fetch_token(&mut self) -> CancelToken {
+        self.job_updates_repo = true;
+        self.cancel_token()
+    }
+
+    fn cancel_token(&mut self) -> CancelToken {
+        let cancel = CancelToken::default();
+        self.clone_cancel = Some(cancel.clone());
+        cancel
+    }
+
+    pub fn start_clone(&mut self, dest: PathBuf, ctx: Context) {
+        let tx = self.begin_job("linux-tkg clone", "Cloning…");
+        clone_linux_tkg(dest, tx, self.fetch_token());
         ctx.request_repaint();
     }
 
-    fn start_copy(&mut self, source: &Path, dest: &Path, ctx: Context) {
-        self.clone_log.clear();
-        self.clone_status = "Copying…".to_string();
-        self.clone_running = true;
+    fn start_unshallow(&mut self, repo: PathBuf, ctx: Context) {
+        let tx = self.begin_job("Unshallow fetch", "Fetching full history…");
+        unshallow(repo, tx, self.fetch_token());
+        ctx.request_repaint();
+    }
 
-        let (tx, rx) = channel();
-        self.clone_rx = Some(rx);
+    fn clone_confirm_panel(&mut self, ui: &mut Ui, ctx: &Context, dest: &Path) {
+        ui.add_space(4.0);
+        ui.group(|ui| {
+            ui.label(
+                RichText::new(format!(
+                    "🌐 This will download about {} into {}. Continue?",
+                    format_bytes(LINUX_TKG_CLONE_BYTES),
+                    dest.display()
+                ))
+                .strong(),
+            );
+            // The destination doesn't exist yet; measure the nearest parent
+            if let Some(free) = dest.ancestors().find(|p| p.exists()).and_then(system::free_space)
+            {
+                let color = if free < LINUX_TKG_CLONE_BYTES * 2 {
+                    Color32::YELLOW
+                } else {
+                    Color32::GRAY
+                };
+                ui.label(RichText::new(format!("{} free there", format_bytes(free))).color(color));
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Clone").clicked() {
+                    self.confirm_clone = false;
+                    self.start_clone(dest.to_path_buf(), ctx.clone());
+                }
+                if ui.button("Cancel").clicked() {
+                    self.confirm_clone = false;
+                }
+            });
+        });
+    }
+
+    fn reclone_confirm_panel(&mut self, ui: &mut Ui, ctx: &Context, dest: &Path) {
+        ui.add_space(4.0);
+        ui.group(|ui| {
+            ui.label(
+                RichText::new(format!(
+                    "🔁 This moves {} to {} and clones linux-tkg again. Continue?",
+                    dest.display(),
+                    reclone_backup_path(dest).display()
+                ))
+                .strong(),
+            );
+            ui.label(
+                RichText::new(
+                    "Your customization.cfg and userpatches stay in the moved folder; copy \
+                     back what you need, then delete it.",
+                )
+                .small()
+                .color(Color32::GRAY),
+            );
+            ui.horizontal(|ui| {
+                if ui.add_enabled(!self.clone_running, egui::Button::new("Re-clone")).clicked() {
+                    self.confirm_reclone = false;
+                    self.start_reclone(dest.to_path_buf(), ctx.clone());
+                }
+                if ui.button("Cancel").clicked() {
+                    self.confirm_reclone = false;
+                }
+            });
+        });
+    }
+
+    fn start_verify(&mut self, repo: PathBuf, ctx: Context) {
+        let tx = self.begin_job("Clone verification", "Verifying…");
+        self.verifying = true;
+        verify_clone(repo, tx, self.cancel_token());
+        ctx.request_repaint();
+    }
+
+    fn start_reclone(&mut self, dest: PathBuf, ctx: Context) {
+        let tx = self.begin_job("linux-tkg re-clone", "Re-cloning…");
+        self.clone_broken = false;
+        reclone_linux_tkg(dest, tx, self.fetch_token());
+        ctx.request_repaint();
+    }
+
+    fn start_copy(&mut self, source: &Path, dest: &Path, ctx: Context) {
+        let tx = self.begin_job("linux-tkg copy", "Copying…");
         copy_linux_tkg(source, dest, tx);
         ctx.request_repaint();
     }
@@ -242,7 +977,13 @@ impl SettingsTab {
             }
         };
 
-        let local_bin = home_local_bin();
+        let local_bin = match home_local_bin() {
+            Ok(dir) => dir,
+            Err(e) => {
+                self.install_status = e;
+                return;
+            }
+        };
         if let Err(e) = std::fs::create_dir_all(&local_bin) {
             self.install_status = format!("Failed to create {}: {}", local_bin.display(), e);
             return;
@@ -270,10 +1011,68 @@ impl SettingsTab {
     }
 }
 
-fn home_local_bin() -> PathBuf {
-    if let Ok(home) = std::env::var("HOME") {
-        PathBuf::from(home).join(".local").join("bin")
+/// Show how a hook command will be split into arguments, or why it can't be
+fn hook_preview(ui: &mut Ui, command_line: &str) {
+    if command_line.trim().is_empty() {
+        return;
+    }
+    let (text, color) = match build_manager::parse_command(command_line) {
+        Ok(args) => (
+            format!(
+                "Runs: {}",
+                args.iter()
+                    .map(|a| format!("[{}]", a))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            Color32::GRAY,
+        ),
+        Err(e) => (format!("Invalid command: {}", e), Color32::RED),
+    };
+    ui.label(RichText::new(text).small().color(color));
+}
+
+/// Where a path typed with `$VAR`/`${VAR}` currently points
+pub fn expansion_hint(ui: &mut Ui, input: &str) {
+    let input = input.trim();
+    if !input.contains('$') {
+        return;
+    }
+    let expanded = settings::expand_path(Path::new(input));
+    ui.label(
+        RichText::new(format!("→ {}", expanded.display()))
+            .small()
+            .color(Color32::GRAY),
+    );
+}
+
+/// "just now", "5 minutes ago", "3 days ago"...
+fn format_ago(then: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let age = now.signed_duration_since(then);
+    let (count, unit) = if age.num_days() >= 365 {
+        (age.num_days() / 365, "year")
+    } else if age.num_days() >= 30 {
+        (age.num_days() / 30, "month")
+    } else if age.num_days() >= 1 {
+        (age.num_days(), "day")
+    } else if age.num_hours() >= 1 {
+        (age.num_hours(), "hour")
+    } else if age.num_minutes() >= 1 {
+        (age.num_minutes(), "minute")
     } else {
-        PathBuf::from(".local").join("bin")
+        return "just now".to_string();
+    };
+    format!("{} {}{} ago", count, unit, if count == 1 { "" } else { "s" })
+}
+
+/// Persist settings and describe the outcome for the status label.
+fn save_status(settings: &AppSettings) -> String {
+    match settings.save() {
+        Ok(()) => "Settings saved.".to_string(),
+        Err(e) => format!("Error saving settings: {}", e),
     }
 }
+
+fn home_local_bin() -> Result<PathBuf, String> {
+    settings::home_dir().map(|home| home.join(".local").join("bin"))
+}
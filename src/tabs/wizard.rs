@@ -0,0 +1,180 @@
+use crate::app::Tab;
+use crate::core::kernel_downloader::format_bytes;
+use crate::core::repo_manager::LINUX_TKG_CLONE_BYTES;
+use crate::core::version;
+use crate::tabs::build::BuildState;
+use egui::{Color32, RichText, Ui};
+
+/// What the wizard needs to know about the other tabs to judge each step
+pub struct WizardState<'a> {
+    pub selected_version: Option<&'a str>,
+    pub linux_tkg_ready: bool,
+    pub cloning: bool,
+    pub clone_status: &'a str,
+    pub config_version: Option<&'a str>,
+    pub build_state: BuildState,
+}
+
+/// Something the app should do on the wizard's behalf, using the same tab
+/// methods the normal UI calls
+pub enum WizardAction {
+    Open(Tab),
+    CloneLinuxTkg,
+    /// Write the selected version to customization.cfg and offer the
+    /// series template
+    ApplyVersion,
+    Skip,
+}
+
+/// Guided first-run flow: kernel → linux-tkg → config → patches → build
+#[derive(Default)]
+pub struct WizardTab {
+    // Patches are optional; set once they were opened or skipped
+    patches_done: bool,
+}
+
+impl WizardTab {
+    pub fn ui(&mut self, ui: &mut Ui, state: &WizardState) -> Option<WizardAction> {
+        let mut action = None;
+
+        ui.heading("🧭 Getting Started");
+        ui.label(
+            "Walk through a first build step by step. Each step opens the tab that does \
+             the work; come back here to continue.",
+        );
+        ui.add_space(8.0);
+
+        let version_done = state.selected_version.is_some();
+        let config_done = match (state.selected_version, state.config_version) {
+            (Some(selected), Some(configured)) => {
+                version::strip_v(selected) == version::strip_v(configured)
+            }
+            _ => false,
+        };
+
+        step(ui, 1, "Pick a kernel version", version_done, true, |ui| {
+            match state.selected_version {
+                Some(v) => {
+                    ui.label(format!("Selected {}", v));
+                }
+                None => {
+                    ui.label("Choose a release on the Kernel tab.");
+                }
+            }
+            if ui.button("🐧 Open Kernel tab").clicked() {
+                action = Some(WizardAction::Open(Tab::Kernel));
+            }
+        });
+
+        step(ui, 2, "Get linux-tkg", state.linux_tkg_ready, version_done, |ui| {
+            if state.linux_tkg_ready {
+                ui.label("linux-tkg is ready in the work directory.");
+            } else {
+                if ui
+                    .add_enabled(!state.cloning, egui::Button::new("🌐 Clone from GitHub"))
+                    .clicked()
+                {
+                    action = Some(WizardAction::CloneLinuxTkg);
+                }
+                if !state.cloning {
+                    ui.label(
+                        RichText::new(format!(
+                            "Downloads about {}",
+                            format_bytes(LINUX_TKG_CLONE_BYTES)
+                        ))
+                        .small()
+                        .color(Color32::GRAY),
+                    );
+                }
+            }
+            if state.cloning {
+                ui.spinner();
+            }
+            if !state.clone_status.is_empty() {
+                ui.label(state.clone_status);
+            }
+        });
+
+        let config_enabled = version_done && state.linux_tkg_ready;
+        step(ui, 3, "Apply the recommended config", config_done, config_enabled, |ui| {
+            if ui
+                .button("📋 Apply Version & Template")
+                .on_hover_text(
+                    "Write the selected version to customization.cfg and open the Config tab \
+                     with the recommended settings for its series",
+                )
+                .clicked()
+            {
+                action = Some(WizardAction::ApplyVersion);
+            }
+        });
+
+        step(ui, 4, "Add patches (optional)", self.patches_done, config_done, |ui| {
+            if ui.button("🩹 Open Patches tab").clicked() {
+                self.patches_done = true;
+                action = Some(WizardAction::Open(Tab::Patches));
+            }
+            if ui.button("Skip").clicked() {
+                self.patches_done = true;
+            }
+        });
+
+        let build_done = state.build_state == BuildState::Done(0);
+        let build_enabled = config_done && self.patches_done;
+        step(ui, 5, "Build the kernel", build_done, build_enabled, |ui| {
+            let label = match state.build_state {
+                BuildState::Idle => "Press ▶ Build on the Build tab.",
+                BuildState::Running => "Building…",
+                BuildState::Done(0) => "Build finished.",
+                BuildState::Done(_) | BuildState::Failed => {
+                    "The build failed; check the log on the Build tab."
+                }
+            };
+            ui.label(label);
+            if ui.button("🔨 Open Build tab").clicked() {
+                action = Some(WizardAction::Open(Tab::Build));
+            }
+        });
+
+        ui.add_space(12.0);
+        if ui
+            .button("Skip wizard")
+            .on_hover_text("Go to the tabs and don't open the wizard on startup")
+            .clicked()
+        {
+            action = Some(WizardAction::Skip);
+        }
+
+        action
+    }
+}
+
+/// One numbered step; its controls are disabled until `enabled`
+fn step(
+    ui: &mut Ui,
+    number: usize,
+    title: &str,
+    done: bool,
+    enabled: bool,
+    add_contents: impl FnOnce(&mut Ui),
+) {
+    ui.group(|ui| {
+        ui.set_width(ui.available_width());
+        let (mark, color) = if done {
+            ("✓", Color32::GREEN)
+        } else if enabled {
+            ("▶", Color32::YELLOW)
+        } else {
+            ("○", Color32::GRAY)
+        };
+        ui.label(
+            RichText::new(format!("{} {}. {}", mark, number, title))
+                .strong()
+                .color(color),
+        );
+        ui.add_enabled_ui(enabled, |ui| {
+            ui.horizontal_wrapped(add_contents);
+        });
+    });
+    ui.add_space(4.0);
+}